@@ -1,6 +1,7 @@
 use crate::{simulator::*, config, project::ProjectRef, renderer::{vector::Vector2, Color}, id::Id};
+use std::collections::HashMap;
 
-use super::*;
+use super::{*, selection::Selection, editor::{GridStyle, WireStyle}};
 
 #[derive(Default)]
 pub struct ActionStack {
@@ -10,15 +11,34 @@ pub struct ActionStack {
 }
 
 impl ActionStack {
+    const UNDO_TOOLTIP: &'static str = "Un-do last action (Ctrl+Z)";
+    const REDO_TOOLTIP: &'static str = "Re-do last action (Ctrl+Y)";
+
+    // reports and refuses an action that would change a locked module
+    fn refuse_locked(app: &Application, action: &Action) -> bool {
+        let Some(module_name) = action.locked_module() else {
+            return false;
+        };
+
+        warn!("refused to change locked module `{module_name}`");
+        if let Some(window) = app.imp().window().borrow().as_ref() {
+            window.panel().push_error(format!("Module \"{module_name}\" is locked. Unlock it to make changes."));
+        }
+        true
+    }
+
     pub fn undo(&mut self, app: &Application) {
         if self.next > 0 {
             let action = self.actions.get(self.next - 1);
             if action.is_none() {
                 println!("{} {} {}", self.actions.len(), self.next, self.dirty);
             }
+            if action.map_or(false, |action| Self::refuse_locked(app, action)) {
+                return;
+            }
             self.next -= 1;
             self.dirty = true;
-            self.update_buttons(&app.imp().undo_button(), &app.imp().redo_button());
+            self.refresh_buttons(app);
 
             info!("Un-doing action {}", self.next);
             action.unwrap().undo(app);
@@ -27,22 +47,59 @@ impl ActionStack {
 
     pub fn redo(&mut self, app: &Application) {
         if let Some(action) = self.actions.get_mut(self.next) {
+            if Self::refuse_locked(app, action) {
+                return;
+            }
             self.next += 1;
             self.dirty = true;
             
             info!("Re-doing action {}", self.next - 1);
             action.exec(app);
 
+            self.refresh_buttons(app);
+        }
+    }
+
+    // the buttons belong to the window, an application without one has nothing to update
+    fn refresh_buttons(&self, app: &Application) {
+        if app.imp().window().borrow().is_some() {
             self.update_buttons(&app.imp().undo_button(), &app.imp().redo_button());
         }
     }
 
+    // the name of the action the next undo would revert
+    pub fn undo_name(&self) -> Option<String> {
+        self.next.checked_sub(1).and_then(|i| self.actions.get(i)).map(Action::name)
+    }
+
+    // the name of the action the next redo would repeat
+    pub fn redo_name(&self) -> Option<String> {
+        self.actions.get(self.next).map(Action::name)
+    }
+
     fn update_buttons(&self, undo_button: &gtk::Button, redo_button: &gtk::Button) {
-        undo_button.set_sensitive(self.next != 0);
-        redo_button.set_sensitive(self.actions.get(self.next).is_some());
+        let (undo, redo) = (self.undo_name(), self.redo_name());
+        undo_button.set_sensitive(undo.is_some());
+        redo_button.set_sensitive(redo.is_some());
+        undo_button.set_tooltip_text(Some(&match undo {
+            Some(name) => format!("Un-do \"{name}\" (Ctrl+Z)"),
+            None => String::from(Self::UNDO_TOOLTIP)
+        }));
+        redo_button.set_tooltip_text(Some(&match redo {
+            Some(name) => format!("Re-do \"{name}\" (Ctrl+Y)"),
+            None => String::from(Self::REDO_TOOLTIP)
+        }));
+    }
+
+    pub fn reset_buttons(undo_button: &gtk::Button, redo_button: &gtk::Button) {
+        Self::default().update_buttons(undo_button, redo_button);
     }
 
     pub fn add(&mut self, app: &Application, mut action: Action) {
+        if Self::refuse_locked(app, &action) {
+            return;
+        }
+
         while self.actions.get(self.next).is_some() {
             self.actions.pop();
         }
@@ -52,7 +109,7 @@ impl ActionStack {
         self.next += 1;
         self.dirty = true;
         self.actions.push(action);
-        self.update_buttons(&app.imp().undo_button(), &app.imp().redo_button());
+        self.refresh_buttons(app);
 
         while self.actions.len() > config::MAX_ACTION_STACK_SIZE {
             self.next -= 1;
@@ -80,20 +137,161 @@ pub enum Action {
     PasteBlocks(PlotProvider, Vec<Block>, Vec<Connection>),
     MoveBlock(PlotProvider, BlockID, Vector2<i32>, Vector2<i32>),
     MoveWaypoint(PlotProvider, SegmentID, Vector2<i32>, Vector2<i32>),
-    NewConnection(PlotProvider, Connection),
+    NewConnection(PlotProvider, Connection, Option<(ConnectionID, HashMap<Id, Segment>)>),
     WaypointToConnection(PlotProvider, SegmentID, Segment, BlockID, u8),
     AddSegment(PlotProvider, SegmentID, Segment, Option<Id>),
     ChangeBorderColor(PlotProvider, Color, Vec<BlockID>, Vec<Option<Color>>),
     DeleteSelection(PlotProvider, Vec<Block>, Vec<Connection>, Vec<Connection>),
     CreateModule(ProjectRef, Module),
-    DeleteModule(ProjectRef, Module),
+    DeleteModule(ProjectRef, Module, Vec<(PlotDescriptor, Block, Vec<Connection>)>),
+    ReplaceModule(ProjectRef, String, String, Vec<(PlotDescriptor, Block)>),
+    // module, its new pin names and the ones they replaced
+    NamePins(ProjectRef, String, PinNames, PinNames),
+    NameNet(PlotProvider, ConnectionID, (Option<String>, Option<Color>), (Option<String>, Option<Color>)),
+    ReshapeConnection(PlotProvider, ConnectionID, HashMap<Id, Segment>, HashMap<Id, Segment>),
+    SwapInputs(PlotProvider, BlockID),
+    ChangeBlockModule(PlotProvider, BlockID, String, Option<Block>),
+    SetDisabled(PlotProvider, bool, Vec<BlockID>, Vec<bool>),
+    SetLabeled(PlotProvider, bool, Vec<ConnectionID>, Vec<bool>),
+    SetWireStyle(PlotProvider, Option<WireStyle>, Vec<ConnectionID>, Vec<Option<WireStyle>>),
+    SetAppearance(PlotProvider, (Option<Color>, GridStyle), (Option<Color>, GridStyle)),
+    RerouteConnections(PlotProvider, Vec<(ConnectionID, HashMap<Id, Segment>, HashMap<Id, Segment>)>),
+    // each removed wire with the one it duplicated
+    RemoveDuplicates(PlotProvider, Vec<(ConnectionID, Connection)>),
+    // wire, the input pin it ended at and the one it was dragged to
+    ReanchorConnection(PlotProvider, ConnectionID, (BlockID, u8), (BlockID, u8)),
+    // blocks with the pin counts they are given, and each block as it was with the wires removed from it
+    FitPins(PlotProvider, Vec<(BlockID, u8, u8)>, Vec<(Block, Vec<Connection>)>),
+    // new block, the wire it was dropped on and the two wires replacing it
+    SpliceBlock(PlotProvider, Block, Connection, Connection, Connection),
+    // several actions that are done and undone as one step
+    Transaction(String, Vec<Action>),
 }
 
 impl Action {
+    // shown in the tooltips of the undo and redo buttons
+    pub fn name(&self) -> String {
+        String::from(match self {
+            Self::NewBlock(..) => "Place Block",
+            Self::PasteBlocks(..) => "Paste",
+            Self::MoveBlock(..) => "Move Block",
+            Self::MoveWaypoint(..) => "Move Bend Point",
+            Self::NewConnection(..) => "Connect",
+            Self::WaypointToConnection(..) => "Connect",
+            Self::AddSegment(..) => "Extend Wire",
+            Self::ChangeBorderColor(..) => "Set Color",
+            Self::DeleteSelection(..) => "Delete",
+            Self::CreateModule(..) => "Create Module",
+            Self::DeleteModule(..) => "Delete Module",
+            Self::ReplaceModule(..) => "Replace Module",
+            Self::NamePins(..) => "Name Pins",
+            Self::NameNet(..) => "Name Net",
+            Self::ReshapeConnection(..) => "Reshape Wire",
+            Self::SwapInputs(..) => "Swap Inputs",
+            Self::ChangeBlockModule(..) => "Change Module",
+            Self::SetDisabled(..) => "Enable/Disable",
+            Self::SetLabeled(..) => "Label Wires",
+            Self::SetWireStyle(..) => "Change Wire Style",
+            Self::SetAppearance(..) => "Canvas Appearance",
+            Self::RerouteConnections(..) => "Re-route Connections",
+            Self::SpliceBlock(..) => "Insert Block",
+            Self::ReanchorConnection(..) => "Move Wire End",
+            Self::RemoveDuplicates(..) => "Remove Duplicate Connections",
+            Self::FitPins(..) => "Fit Blocks to Their Modules",
+            Self::Transaction(name, _) => return name.clone()
+        })
+    }
+
+    // the name of the locked module whose circuit this action would change, if any
+    pub fn locked_module(&self) -> Option<String> {
+        let plot_provider = match self {
+            Self::NewBlock(plot_provider, ..) |
+            Self::PasteBlocks(plot_provider, ..) |
+            Self::MoveBlock(plot_provider, ..) |
+            Self::MoveWaypoint(plot_provider, ..) |
+            Self::NewConnection(plot_provider, ..) |
+            Self::WaypointToConnection(plot_provider, ..) |
+            Self::AddSegment(plot_provider, ..) |
+            Self::ChangeBorderColor(plot_provider, ..) |
+            Self::DeleteSelection(plot_provider, ..) |
+            Self::NameNet(plot_provider, ..) |
+            Self::ReshapeConnection(plot_provider, ..) |
+            Self::SwapInputs(plot_provider, ..) |
+            Self::ChangeBlockModule(plot_provider, ..) |
+            Self::SetDisabled(plot_provider, ..) |
+            Self::SetLabeled(plot_provider, ..) |
+            Self::SetWireStyle(plot_provider, ..) |
+            Self::SetAppearance(plot_provider, ..) |
+            Self::RerouteConnections(plot_provider, ..) |
+            Self::SpliceBlock(plot_provider, ..) |
+            Self::RemoveDuplicates(plot_provider, ..) |
+            Self::FitPins(plot_provider, ..) |
+            Self::ReanchorConnection(plot_provider, ..) => plot_provider,
+            Self::Transaction(_, actions) => return actions.iter().find_map(|action| action.locked_module()),
+            // a deleted module takes its lock with it, the copy kept for undoing still has it
+            Self::DeleteModule(_, module, _) if module.locked() => return Some(module.name().clone()),
+            Self::DeleteModule(project, module, instances) => return Self::locked_around(project, module.name(), true, instances.iter().map(|(plot, ..)| plot)),
+            Self::ReplaceModule(project, from, _, originals) => return Self::locked_around(project, from, false, originals.iter().map(|(plot, _)| plot)),
+            Self::NamePins(project, mod_name, ..) => return Self::locked_around(project, mod_name, true, [].iter()),
+            Self::CreateModule(..) => return None
+        };
+
+        plot_provider.is_module().filter(|_| plot_provider.is_locked()).cloned()
+    }
+
+    // the locked module among the circuits changed along with every instance of `mod_name`: the ones placing it,
+    // the ones the action recorded and, if `own`, the module's own circuit
+    fn locked_around<'a>(project: &ProjectRef, mod_name: &String, own: bool, recorded: impl Iterator<Item = &'a PlotDescriptor>) -> Option<String> {
+        let project = project.lock();
+        let own = own.then(|| PlotDescriptor::Module(mod_name.clone()));
+        let placing = project.find_instances(mod_name).into_iter().map(|(plot, _)| plot).collect::<Vec<_>>();
+        project.locked_among(own.iter().chain(placing.iter()).chain(recorded))
+    }
+
     fn exec(&mut self, app: &Application) {
         match self {
+            Self::Transaction(_, actions) => actions.iter_mut().for_each(|action| action.exec(app)),
             Self::NewBlock(plot_provider, block) => { // place a new block
                 plot_provider.with_mut(|plot| plot.add_block(block.clone()));
+                if let Some(project) = plot_provider.project() {
+                    project.lock().add_recent_module(block.module_id());
+                }
+                app.imp().rerender_editor();
+            }
+            Self::SpliceBlock(plot_provider, block, original, upstream, downstream) => {
+                plot_provider.with_mut(|plot| {
+                    plot.remove_connection(original.id());
+                    plot.add_block(block.clone());
+                    plot.add_connection(upstream.clone());
+                    plot.add_connection(downstream.clone());
+                });
+                if let Some(project) = plot_provider.project() {
+                    project.lock().add_recent_module(block.module_id());
+                }
+                app.imp().rerender_editor();
+            }
+            Self::ReanchorConnection(plot_provider, connection_id, from, to) => {
+                plot_provider.with_mut(|plot| plot.reanchor_connection(*connection_id, *from, *to));
+                app.imp().rerender_editor();
+            }
+            Self::FitPins(plot_provider, blocks, previous) => {
+                let old = plot_provider.with_mut(|plot| blocks.iter()
+                    .filter_map(|(block_id, inputs, outputs)| plot.fit_pins(*block_id, *inputs, *outputs))
+                    .collect()
+                );
+                if let Some(old) = old {
+                    *previous = old;
+                }
+                app.imp().rerender_editor();
+            }
+            Self::RemoveDuplicates(plot_provider, removed) => {
+                plot_provider.with_mut(|plot| removed.iter().for_each(|(kept, connection)| {
+                    plot.remove_connection(connection.id());
+                    // the pins may have been linked to the removed wire
+                    if let Some(kept) = plot.get_connection(kept).cloned() {
+                        plot.restore_connection(kept);
+                    }
+                }));
                 app.imp().rerender_editor();
             }
             Self::PasteBlocks(plot_provier, blocks, connections) => {
@@ -117,10 +315,8 @@ impl Action {
                 );
                 app.imp().rerender_editor();
             }
-            Self::NewConnection(plot_provider, connection) => {
-                plot_provider.with_mut(|plot| {
-                    plot.add_connection(connection.clone());
-                });
+            Self::NewConnection(plot_provider, connection, merged) => {
+                *merged = plot_provider.with_mut(|plot| plot.add_connection(connection.clone())).flatten();
                 app.imp().rerender_editor();
             }
             Self::WaypointToConnection(plot_provider, segment_id, _segment, block_id, block_port) => {
@@ -191,23 +387,165 @@ impl Action {
                 if let Some(window) = app.imp().window().borrow().as_ref() {
                     window.add_module_to_ui(app, module);
                 }
-                project.lock().unwrap().add_module(module.clone());
+                project.lock().add_module(module.clone());
             }
-            Self::DeleteModule(project, module) => {
+            Self::DeleteModule(project, module, instances) => {
                 if let Some(window) = app.imp().window().borrow().as_ref() {
                     window.remove_module_from_ui(module.name());
                 }
-                project.lock().unwrap().remove_module(module.name());
+                let mut project = project.lock();
+                *instances = project.remove_instances(module.name());
+                project.remove_module(module.name());
+                drop(project);
+                app.imp().rerender_editor();
+            }
+            Self::NameNet(plot_provider, connection_id, (name, color), old) => {
+                let previous = plot_provider.with_mut(|plot| plot.get_connection_mut(connection_id).map(|connection| {
+                    let previous = (connection.name().clone(), *connection.color());
+                    connection.set_name(name.clone());
+                    connection.set_color(*color);
+                    previous
+                })).flatten();
+                if let Some(previous) = previous {
+                    *old = previous;
+                }
+                app.imp().rerender_editor();
+            }
+            Self::SwapInputs(plot_provider, block_id) => {
+                plot_provider.with_mut(|plot| plot.swap_inputs(*block_id, 0, 1));
+                app.imp().rerender_editor();
+            }
+            Self::ChangeBlockModule(plot_provider, block_id, module_name, original) => {
+                let module = plot_provider.project().and_then(|project| project.lock().module(module_name).cloned());
+                if let Some(module) = module {
+                    // the block keeps its id, position and connections, port by port
+                    *original = plot_provider.with_mut(|plot| {
+                        let block = plot.get_block_mut(*block_id)?;
+                        let replaced = block.clone();
+                        block.swap_module(&module);
+                        plot.add_block_to_update(*block_id);
+                        Some(replaced)
+                    }).flatten();
+                }
+                app.imp().rerender_editor();
+            }
+            Self::RerouteConnections(plot_provider, routes) => {
+                plot_provider.with_mut(|plot| {
+                    plot.unhighlight();
+                    routes.iter().for_each(|(connection_id, segments, _)| if let Some(connection) = plot.get_connection_mut(connection_id) {
+                        connection.set_segments(segments.clone());
+                    });
+                });
+                app.imp().rerender_editor();
+            }
+            Self::SetAppearance(plot_provider, (background, grid_style), old) => {
+                let previous = plot_provider.with_mut(|plot| {
+                    let previous = (plot.background(), plot.grid_style());
+                    plot.set_background(*background);
+                    plot.set_grid_style(*grid_style);
+                    previous
+                });
+                if let Some(previous) = previous {
+                    *old = previous;
+                }
+                app.imp().rerender_editor();
+            }
+            Self::SetDisabled(plot_provider, disabled, block_ids, previous) => {
+                let old = plot_provider.with_mut(|plot| block_ids.iter().filter_map(|block_id| {
+                    let block = plot.get_block_mut(*block_id)?;
+                    let old = block.disabled();
+                    block.set_disabled(*disabled);
+                    plot.add_block_to_update(*block_id);
+                    Some(old)
+                }).collect());
+                if let Some(old) = old {
+                    *previous = old;
+                }
+                app.imp().rerender_editor();
+            }
+            Self::SetLabeled(plot_provider, labeled, connection_ids, previous) => {
+                let old = plot_provider.with_mut(|plot| connection_ids.iter().filter_map(|connection_id| {
+                    let connection = plot.get_connection_mut(connection_id)?;
+                    let old = connection.labeled();
+                    connection.set_labeled(*labeled);
+                    Some(old)
+                }).collect());
+                if let Some(old) = old {
+                    *previous = old;
+                }
+                app.imp().rerender_editor();
+            }
+            Self::SetWireStyle(plot_provider, style, connection_ids, previous) => {
+                let old = plot_provider.with_mut(|plot| connection_ids.iter().filter_map(|connection_id| {
+                    let connection = plot.get_connection_mut(connection_id)?;
+                    let old = connection.style();
+                    connection.set_style(*style);
+                    Some(old)
+                }).collect());
+                if let Some(old) = old {
+                    *previous = old;
+                }
+                app.imp().rerender_editor();
+            }
+            Self::ReshapeConnection(plot_provider, connection_id, segments, _old) => {
+                plot_provider.with_mut(|plot| {
+                    plot.unhighlight();
+                    plot.set_selection(Selection::None);
+                    if let Some(connection) = plot.get_connection_mut(connection_id) {
+                        connection.set_segments(segments.clone());
+                    }
+                });
+                app.imp().rerender_editor();
+            }
+            Self::ReplaceModule(project, from, to, originals) => {
+                match project.lock().replace_module(from, to) {
+                    Ok(replaced) => *originals = replaced,
+                    Err(err) => error!("{err}")
+                }
+                app.imp().rerender_editor();
+            }
+            Self::NamePins(project, mod_name, pin_names, old) => {
+                match project.lock().set_pin_names(mod_name, pin_names.clone()) {
+                    Ok(previous) => *old = previous,
+                    Err(err) => error!("{err}")
+                }
+                app.imp().rerender_editor();
             }
         }
     }
 
     fn undo(&self, app: &Application) {
         match self {
+            Self::Transaction(_, actions) => actions.iter().rev().for_each(|action| action.undo(app)),
             Self::NewBlock(plot_provider, block) => { // remove a block
                 plot_provider.with_mut(|plot| plot.delete_block(block.id()));
                 app.imp().rerender_editor();
             }
+            Self::ReanchorConnection(plot_provider, connection_id, from, to) => {
+                plot_provider.with_mut(|plot| plot.reanchor_connection(*connection_id, *to, *from));
+                app.imp().rerender_editor();
+            }
+            Self::FitPins(plot_provider, _blocks, previous) => {
+                plot_provider.with_mut(|plot| previous.iter().for_each(|(block, connections)| {
+                    plot.blocks_mut().insert(block.id(), block.clone());
+                    plot.add_block_to_update(block.id());
+                    connections.iter().for_each(|connection| plot.restore_connection(connection.clone()));
+                }));
+                app.imp().rerender_editor();
+            }
+            Self::RemoveDuplicates(plot_provider, removed) => {
+                plot_provider.with_mut(|plot| removed.iter().rev().for_each(|(_, connection)| plot.restore_connection(connection.clone())));
+                app.imp().rerender_editor();
+            }
+            Self::SpliceBlock(plot_provider, block, original, upstream, downstream) => {
+                plot_provider.with_mut(|plot| {
+                    plot.remove_connection(downstream.id());
+                    plot.remove_connection(upstream.id());
+                    plot.delete_block(block.id());
+                    plot.add_connection(original.clone());
+                });
+                app.imp().rerender_editor();
+            }
             Self::PasteBlocks(plot_provier, blocks, connections) => {
                 plot_provier.with_mut(|plot|  {
                     blocks.iter().for_each(|block| { 
@@ -235,9 +573,10 @@ impl Action {
                 );
                 app.imp().rerender_editor();
             }
-            Self::NewConnection(plot_provider, connection) => {
-                plot_provider.with_mut(|plot| {
-                    plot.remove_connection(connection.id());
+            Self::NewConnection(plot_provider, connection, merged) => {
+                plot_provider.with_mut(|plot| match merged {
+                    Some((existing, previous)) => plot.unmerge_connection(*existing, previous.clone(), connection),
+                    None => { plot.remove_connection(connection.id()); }
                 });
                 app.imp().rerender_editor();
             }
@@ -283,7 +622,7 @@ impl Action {
                 println!("restore connections: {connections:?} incoming: {incoming:?}");
                 plot_provider.with_mut(|plot| {
                     blocks.iter().for_each(|block| plot.add_block(block.clone()));
-                    connections.iter().chain(incoming.iter()).for_each(|connection| unsafe { plot.add_connection_unsafe(connection.clone()) });
+                    connections.iter().chain(incoming.iter()).for_each(|connection| plot.restore_connection(connection.clone()));
                 });
                 app.imp().rerender_editor();
             }
@@ -291,14 +630,547 @@ impl Action {
                 if let Some(window) = app.imp().window().borrow().as_ref() {
                     window.remove_module_from_ui(module.name());
                 }
-                project.lock().unwrap().remove_module(module.name());
+                project.lock().remove_module(module.name());
             }
-            Self::DeleteModule(project, module) => {
+            Self::DeleteModule(project, module, instances) => {
                 if let Some(window) = app.imp().window().borrow().as_ref() {
                     window.add_module_to_ui(app, module);
                 }
-                project.lock().unwrap().add_module(module.clone());
+                let mut project = project.lock();
+                project.add_module(module.clone());
+                project.restore_instances(instances);
+                drop(project);
+                app.imp().rerender_editor();
+            }
+            Self::NameNet(plot_provider, connection_id, _new, (name, color)) => {
+                plot_provider.with_mut(|plot| if let Some(connection) = plot.get_connection_mut(connection_id) {
+                    connection.set_name(name.clone());
+                    connection.set_color(*color);
+                });
+                app.imp().rerender_editor();
+            }
+            Self::SwapInputs(plot_provider, block_id) => {
+                // swapping is its own inverse
+                plot_provider.with_mut(|plot| plot.swap_inputs(*block_id, 0, 1));
+                app.imp().rerender_editor();
+            }
+            Self::ChangeBlockModule(plot_provider, block_id, _, original) => {
+                if let Some(original) = original {
+                    plot_provider.with_mut(|plot| if let Some(block) = plot.get_block_mut(*block_id) {
+                        *block = original.clone();
+                        plot.add_block_to_update(*block_id);
+                    });
+                }
+                app.imp().rerender_editor();
+            }
+            Self::RerouteConnections(plot_provider, routes) => {
+                plot_provider.with_mut(|plot| {
+                    plot.unhighlight();
+                    routes.iter().for_each(|(connection_id, _, segments)| if let Some(connection) = plot.get_connection_mut(connection_id) {
+                        connection.set_segments(segments.clone());
+                    });
+                });
+                app.imp().rerender_editor();
+            }
+            Self::SetAppearance(plot_provider, _new, (background, grid_style)) => {
+                plot_provider.with_mut(|plot| {
+                    plot.set_background(*background);
+                    plot.set_grid_style(*grid_style);
+                });
+                app.imp().rerender_editor();
+            }
+            Self::SetDisabled(plot_provider, _disabled, block_ids, previous) => {
+                plot_provider.with_mut(|plot| block_ids.iter().zip(previous).for_each(|(block_id, disabled)| {
+                    if let Some(block) = plot.get_block_mut(*block_id) {
+                        block.set_disabled(*disabled);
+                        plot.add_block_to_update(*block_id);
+                    }
+                }));
+                app.imp().rerender_editor();
+            }
+            Self::SetLabeled(plot_provider, _labeled, connection_ids, previous) => {
+                plot_provider.with_mut(|plot| connection_ids.iter().zip(previous).for_each(|(connection_id, labeled)| {
+                    if let Some(connection) = plot.get_connection_mut(connection_id) {
+                        connection.set_labeled(*labeled);
+                    }
+                }));
+                app.imp().rerender_editor();
+            }
+            Self::SetWireStyle(plot_provider, _style, connection_ids, previous) => {
+                plot_provider.with_mut(|plot| connection_ids.iter().zip(previous).for_each(|(connection_id, style)| {
+                    if let Some(connection) = plot.get_connection_mut(connection_id) {
+                        connection.set_style(*style);
+                    }
+                }));
+                app.imp().rerender_editor();
+            }
+            Self::ReshapeConnection(plot_provider, connection_id, _new, segments) => {
+                plot_provider.with_mut(|plot| {
+                    plot.unhighlight();
+                    plot.set_selection(Selection::None);
+                    if let Some(connection) = plot.get_connection_mut(connection_id) {
+                        connection.set_segments(segments.clone());
+                    }
+                });
+                app.imp().rerender_editor();
+            }
+            Self::ReplaceModule(project, _from, _to, originals) => {
+                let mut project = project.lock();
+                for (descriptor, block) in originals.iter() {
+                    if let Some(plot) = project.plot_by_descriptor_mut(descriptor) {
+                        if let Some(instance) = plot.get_block_mut(block.id()) {
+                            *instance = block.clone();
+                        }
+                        plot.add_block_to_update(block.id());
+                    }
+                }
+                drop(project);
+                app.imp().rerender_editor();
+            }
+            Self::NamePins(project, mod_name, _new, old) => {
+                if let Err(err) = project.lock().set_pin_names(mod_name, old.clone()) {
+                    error!("{err}");
+                }
+                app.imp().rerender_editor();
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::selection::{Selectable, SelectionField};
+
+    fn instance_of(project: &ProjectRef, module_name: &str, position: Vector2<i32>) -> Block {
+        Block::new(&project.lock().module(&module_name.to_string()).unwrap(), position, None)
+    }
+
+    #[test]
+    fn undoing_module_creation_removes_it() {
+        let app = Application::new();
+        let project = app.imp().project().clone();
+        let mut stack = ActionStack::default();
+
+        let name = String::from("Half Adder");
+        stack.add(&app, Action::CreateModule(project.clone(), Module::new(name.clone(), 2, 2)));
+        assert!(project.lock().module(&name).is_some());
+
+        stack.undo(&app);
+        assert!(project.lock().module(&name).is_none());
+    }
+
+    #[test]
+    fn undoing_module_deletion_restores_its_instances() {
+        let app = Application::new();
+        let project = app.imp().project().clone();
+        let mut stack = ActionStack::default();
+
+        let name = String::from("Half Adder");
+        project.lock().add_module(Module::new(name.clone(), 2, 2));
+        let instance = instance_of(&project, &name, Vector2(100, 100));
+        let instance_id = instance.id();
+        project.lock().main_plot_mut().add_block(instance);
+
+        let module = project.lock().module(&name).unwrap().clone();
+        stack.add(&app, Action::DeleteModule(project.clone(), module, vec![]));
+        assert!(project.lock().module(&name).is_none());
+        assert!(project.lock().main_plot().get_block(instance_id).is_none());
+
+        stack.undo(&app);
+        assert!(project.lock().module(&name).is_some());
+        assert_eq!(project.lock().main_plot().get_block(instance_id).map(|block| block.module_id().clone()), Some(name));
+    }
+
+    #[test]
+    fn deleting_a_selected_wire_removes_only_that_wire() {
+        let app = Application::new();
+        let project = app.imp().project().clone();
+        let plot_provider = PlotProvider::Main(project.clone());
+        let mut stack = ActionStack::default();
+
+        let source = instance_of(&project, "And", Vector2(0, 0));
+        let (first, second) = (instance_of(&project, "Not", Vector2(200, 0)), instance_of(&project, "Not", Vector2(200, 100)));
+        let (kept, removed) = (Connection::new_basic(source.id(), 0, first.id(), 0), Connection::new_basic(first.id(), 0, second.id(), 0));
+        let (kept_id, removed_id, second_id) = (kept.id(), removed.id(), second.id());
+        plot_provider.with_mut(|plot| {
+            [source, first, second].into_iter().for_each(|block| plot.add_block(block));
+            plot.add_connection(kept);
+            plot.add_connection(removed);
+            plot.set_selection(Selection::Single(Selectable::Connection(removed_id), Vector2(0, 0)));
+        });
+
+        let selected = plot_provider.with(|plot| plot.selected().iter()
+            .filter_map(|selected| selected.connection_id().and_then(|id| plot.get_connection(&id)).cloned())
+            .collect::<Vec<_>>()
+        ).unwrap();
+        assert_eq!(selected.len(), 1);
+        stack.add(&app, Action::DeleteSelection(plot_provider.clone(), vec![], selected, vec![]));
+
+        plot_provider.with(|plot| {
+            assert!(plot.get_connection(&removed_id).is_none());
+            assert!(plot.get_connection(&kept_id).is_some());
+            assert_eq!(plot.blocks().len(), 3);
+            assert_eq!(plot.get_block(second_id).unwrap().inputs()[0], None);
+        });
+    }
+
+    #[test]
+    fn undoing_a_bulk_delete_restores_the_wires_between_the_blocks() {
+        let app = Application::new();
+        let project = app.imp().project().clone();
+        let plot_provider = PlotProvider::Main(project.clone());
+        let mut stack = ActionStack::default();
+
+        let (source, target) = (instance_of(&project, "And", Vector2(0, 0)), instance_of(&project, "Not", Vector2(200, 0)));
+        let connection = Connection::new_basic(source.id(), 0, target.id(), 0);
+        let (source_id, target_id, connection_id) = (source.id(), target.id(), connection.id());
+        plot_provider.with_mut(|plot| {
+            plot.add_block(source);
+            plot.add_block(target);
+            plot.add_connection(connection);
+        });
+
+        let blocks = plot_provider.with(|plot| plot.blocks().values().cloned().collect::<Vec<_>>()).unwrap();
+        stack.add(&app, Action::DeleteSelection(plot_provider.clone(), blocks, vec![], vec![]));
+        plot_provider.with(|plot| {
+            assert!(plot.blocks().is_empty());
+            assert!(plot.connections().is_empty());
+        });
+
+        stack.undo(&app);
+        plot_provider.with(|plot| {
+            assert_eq!(plot.blocks().len(), 2);
+            assert!(plot.get_connection(&connection_id).is_some());
+            assert_eq!(plot.get_block(source_id).unwrap().outputs()[0], Some(connection_id));
+            assert_eq!(plot.get_block(target_id).unwrap().inputs()[0], Some(connection_id));
+        });
+    }
+
+    #[test]
+    fn undoing_a_wire_merged_into_a_net_frees_only_its_pin() {
+        let app = Application::new();
+        let project = app.imp().project().clone();
+        let plot_provider = PlotProvider::Main(project.clone());
+        let mut stack = ActionStack::default();
+
+        let source = instance_of(&project, "And", Vector2(0, 0));
+        let (first, second) = (instance_of(&project, "Not", Vector2(200, 0)), instance_of(&project, "Not", Vector2(200, 100)));
+        let existing = Connection::new_basic(source.id(), 0, first.id(), 0);
+        let (source_id, first_id, second_id, existing_id) = (source.id(), first.id(), second.id(), existing.id());
+        plot_provider.with_mut(|plot| {
+            [source, first, second].into_iter().for_each(|block| plot.add_block(block));
+            plot.add_connection(existing);
+        });
+
+        // a second wire from the same output is merged into the existing net
+        stack.add(&app, Action::NewConnection(plot_provider.clone(), Connection::new_basic(source_id, 0, second_id, 0), None));
+        plot_provider.with(|plot| {
+            assert_eq!(plot.connections().len(), 1);
+            assert_eq!(plot.get_block(second_id).unwrap().inputs()[0], Some(existing_id));
+        });
+
+        stack.undo(&app);
+        plot_provider.with(|plot| {
+            assert_eq!(plot.connections().len(), 1);
+            assert_eq!(plot.get_block(source_id).unwrap().outputs()[0], Some(existing_id));
+            assert_eq!(plot.get_block(first_id).unwrap().inputs()[0], Some(existing_id));
+            assert_eq!(plot.get_block(second_id).unwrap().inputs()[0], None);
+        });
+
+        stack.redo(&app);
+        plot_provider.with(|plot| assert_eq!(plot.get_block(second_id).unwrap().inputs()[0], Some(existing_id)));
+    }
+
+    #[test]
+    fn undoing_a_new_wire_frees_both_pins() {
+        let app = Application::new();
+        let project = app.imp().project().clone();
+        let plot_provider = PlotProvider::Main(project.clone());
+        let mut stack = ActionStack::default();
+
+        let (source, sink) = (instance_of(&project, "And", Vector2(0, 0)), instance_of(&project, "Not", Vector2(200, 0)));
+        let (source_id, sink_id) = (source.id(), sink.id());
+        plot_provider.with_mut(|plot| [source, sink].into_iter().for_each(|block| plot.add_block(block)));
+
+        let connection = Connection::new_basic(source_id, 0, sink_id, 0);
+        let connection_id = connection.id();
+        stack.add(&app, Action::NewConnection(plot_provider.clone(), connection, None));
+
+        stack.undo(&app);
+        plot_provider.with(|plot| {
+            assert!(plot.connections().is_empty());
+            assert_eq!(plot.get_block(source_id).unwrap().outputs()[0], None);
+            assert_eq!(plot.get_block(sink_id).unwrap().inputs()[0], None);
+        });
+
+        stack.redo(&app);
+        plot_provider.with(|plot| {
+            assert_eq!(plot.get_block(source_id).unwrap().outputs()[0], Some(connection_id));
+            assert_eq!(plot.get_block(sink_id).unwrap().inputs()[0], Some(connection_id));
+        });
+    }
+
+    #[test]
+    fn a_transaction_is_undone_in_one_step() {
+        let app = Application::new();
+        let project = app.imp().project().clone();
+        let plot_provider = PlotProvider::Main(project.clone());
+        let mut stack = ActionStack::default();
+
+        let (first, second) = (instance_of(&project, "And", Vector2(0, 0)), instance_of(&project, "Not", Vector2(200, 0)));
+        let (first_id, second_id) = (first.id(), second.id());
+        stack.add(&app, Action::Transaction(String::from("Place Gates"), vec![
+            Action::NewBlock(plot_provider.clone(), first),
+            Action::NewBlock(plot_provider.clone(), second)
+        ]));
+        assert_eq!(stack.undo_name(), Some(String::from("Place Gates")));
+        assert_eq!(plot_provider.with(|plot| plot.blocks().len()), Some(2));
+
+        stack.undo(&app);
+        assert_eq!(stack.undo_name(), None);
+        assert_eq!(stack.redo_name(), Some(String::from("Place Gates")));
+        assert_eq!(plot_provider.with(|plot| plot.blocks().len()), Some(0));
+
+        stack.redo(&app);
+        assert_eq!(stack.redo_name(), None);
+        plot_provider.with(|plot| {
+            assert!(plot.get_block(first_id).is_some());
+            assert!(plot.get_block(second_id).is_some());
+        });
+    }
+
+    #[test]
+    fn module_actions_touching_a_locked_circuit_are_refused() {
+        let app = Application::new();
+        let project = app.imp().project().clone();
+        let mut stack = ActionStack::default();
+
+        // "Adder" is placed inside the locked module "Alu"
+        let (inner, outer) = (String::from("Adder"), String::from("Alu"));
+        project.lock().add_module(Module::new(inner.clone(), 2, 1));
+        project.lock().add_module(Module::new(outer.clone(), 2, 1));
+        let instance = instance_of(&project, &inner, Vector2(100, 100));
+        let instance_id = instance.id();
+        project.lock().plot_by_descriptor_mut(&PlotDescriptor::Module(outer.clone())).unwrap().add_block(instance);
+        project.lock().module_mut(&outer).unwrap().set_locked(true);
+
+        let module = project.lock().module(&inner).unwrap().clone();
+        let action = Action::DeleteModule(project.clone(), module, vec![]);
+        assert_eq!(action.locked_module(), Some(outer.clone()));
+        stack.add(&app, action);
+        assert!(project.lock().module(&inner).is_some());
+        assert!(project.lock().plot_by_descriptor(&PlotDescriptor::Module(outer.clone())).unwrap().get_block(instance_id).is_some());
+
+        let action = Action::ReplaceModule(project.clone(), inner.clone(), String::from("Xor"), vec![]);
+        assert_eq!(action.locked_module(), Some(outer.clone()));
+        stack.add(&app, action);
+        assert_eq!(project.lock().find_instances(&inner).len(), 1);
+
+        let action = Action::NamePins(project.clone(), outer.clone(), PinNames::default(), PinNames::default());
+        assert_eq!(action.locked_module(), Some(outer));
+        assert_eq!(stack.undo_name(), None);
+    }
+
+    #[test]
+    fn replacing_a_module_keeps_every_wire_on_its_pin() {
+        let app = Application::new();
+        let project = app.imp().project().clone();
+        let mut stack = ActionStack::default();
+
+        let wrapper = String::from("Wrapper");
+        project.lock().add_module(Module::new(wrapper.clone(), 2, 1));
+        let descriptors = [PlotDescriptor::Main(), PlotDescriptor::Module(wrapper)];
+
+        // an "And" gate fed on its second input and driving a "Not" gate, once in every plot
+        let placed = descriptors.iter().map(|descriptor| {
+            let (source, gate, sink) = (instance_of(&project, "Not", Vector2(0, 0)), instance_of(&project, "And", Vector2(200, 0)), instance_of(&project, "Not", Vector2(400, 0)));
+            let (input, output) = (Connection::new_basic(source.id(), 0, gate.id(), 1), Connection::new_basic(gate.id(), 0, sink.id(), 0));
+            let ids = (gate.id(), input.id(), output.id());
+            let mut lock = project.lock();
+            let plot = lock.plot_by_descriptor_mut(descriptor).unwrap();
+            [source, gate, sink].into_iter().for_each(|block| plot.add_block(block));
+            plot.add_connection(input);
+            plot.add_connection(output);
+            (descriptor.clone(), ids)
+        }).collect::<Vec<_>>();
+
+        let assert_wired_as = |module_name: &str| {
+            let project = project.lock();
+            for (descriptor, (gate_id, input_id, output_id)) in placed.iter() {
+                let gate = project.plot_by_descriptor(descriptor).unwrap().get_block(*gate_id).unwrap();
+                assert_eq!(gate.module_id(), module_name);
+                assert_eq!(gate.inputs(), &vec![None, Some(*input_id)]);
+                assert_eq!(gate.outputs(), &vec![Some(*output_id)]);
+            }
+        };
+
+        stack.add(&app, Action::ReplaceModule(project.clone(), String::from("And"), String::from("Or"), vec![]));
+        assert_wired_as("Or");
+        assert!(project.lock().find_instances(&String::from("And")).is_empty());
+
+        stack.undo(&app);
+        assert_wired_as("And");
+    }
+
+    #[test]
+    fn changing_a_gate_in_place_keeps_its_wires() {
+        let app = Application::new();
+        let project = app.imp().project().clone();
+        let plot_provider = PlotProvider::Main(project.clone());
+        let mut stack = ActionStack::default();
+
+        let (source, gate, sink) = (instance_of(&project, "Not", Vector2(0, 0)), instance_of(&project, "And", Vector2(200, 0)), instance_of(&project, "Not", Vector2(400, 0)));
+        let (input, output) = (Connection::new_basic(source.id(), 0, gate.id(), 1), Connection::new_basic(gate.id(), 0, sink.id(), 0));
+        let (gate_id, input_id, output_id) = (gate.id(), input.id(), output.id());
+        plot_provider.with_mut(|plot| {
+            [source, gate, sink].into_iter().for_each(|block| plot.add_block(block));
+            plot.add_connection(input);
+            plot.add_connection(output);
+        });
+
+        let lock = project.lock();
+        assert!(lock.check_change_block_module(&PlotDescriptor::Main(), gate_id, &String::from("Not")).is_err());
+        assert!(lock.check_change_block_module(&PlotDescriptor::Main(), gate_id, &String::from("Or")).is_ok());
+        drop(lock);
+
+        stack.add(&app, Action::ChangeBlockModule(plot_provider.clone(), gate_id, String::from("Or"), None));
+        plot_provider.with(|plot| {
+            let gate = plot.get_block(gate_id).unwrap();
+            assert_eq!(gate.module_id(), "Or");
+            assert_eq!(gate.inputs(), &vec![None, Some(input_id)]);
+            assert_eq!(gate.outputs(), &vec![Some(output_id)]);
+        });
+
+        stack.undo(&app);
+        plot_provider.with(|plot| assert_eq!(plot.get_block(gate_id).unwrap().module_id(), "And"));
+    }
+
+    #[test]
+    fn dropping_a_gate_on_a_wire_runs_the_wire_through_it() {
+        let app = Application::new();
+        let project = app.imp().project().clone();
+        let plot_provider = PlotProvider::Main(project.clone());
+        let mut stack = ActionStack::default();
+
+        let (a, b) = (instance_of(&project, "And", Vector2(0, 0)), instance_of(&project, "Not", Vector2(400, 0)));
+        let wire = Connection::new_basic(a.id(), 0, b.id(), 0);
+        let (a_id, b_id, wire_id) = (a.id(), b.id(), wire.id());
+        plot_provider.with_mut(|plot| {
+            plot.add_block(a);
+            plot.add_block(b);
+            plot.add_connection(wire);
+        });
+
+        let not = instance_of(&project, "Not", Vector2(200, 0));
+        let not_id = not.id();
+        let (original, upstream, downstream) = plot_provider.with(|plot| plot.splice_block(&not, wire_id)).flatten().unwrap();
+        let downstream_id = downstream.id();
+        stack.add(&app, Action::SpliceBlock(plot_provider.clone(), not, original, upstream, downstream));
+
+        let pins = |plot: &Plot, id: ConnectionID| plot.get_connection(&id).unwrap().destinations().iter().map(|port| (port.block_id(), port.index())).collect::<Vec<_>>();
+        plot_provider.with(|plot| {
+            assert_eq!(plot.get_block(a_id).unwrap().outputs()[0], Some(wire_id));
+            assert_eq!(pins(plot, wire_id), vec![(not_id, 0)]);
+            assert_eq!(plot.get_block(not_id).unwrap().outputs()[0], Some(downstream_id));
+            assert_eq!(pins(plot, downstream_id), vec![(b_id, 0)]);
+            assert_eq!(plot.get_block(b_id).unwrap().inputs()[0], Some(downstream_id));
+        });
+
+        stack.undo(&app);
+        plot_provider.with(|plot| {
+            assert!(plot.get_block(not_id).is_none());
+            assert!(plot.get_connection(&downstream_id).is_none());
+            assert_eq!(pins(plot, wire_id), vec![(b_id, 0)]);
+            assert_eq!(plot.get_block(b_id).unwrap().inputs()[0], Some(wire_id));
+        });
+    }
+
+    #[test]
+    fn only_gates_with_one_input_and_output_are_spliced_in() {
+        let app = Application::new();
+        let project = app.imp().project().clone();
+        let plot_provider = PlotProvider::Main(project.clone());
+
+        let (a, b) = (instance_of(&project, "Not", Vector2(0, 0)), instance_of(&project, "Not", Vector2(400, 0)));
+        let wire = Connection::new_basic(a.id(), 0, b.id(), 0);
+        let wire_id = wire.id();
+        plot_provider.with_mut(|plot| {
+            plot.add_block(a);
+            plot.add_block(b);
+            plot.add_connection(wire);
+        });
+
+        let and = instance_of(&project, "And", Vector2(200, 0));
+        assert!(plot_provider.with(|plot| plot.splice_block(&and, wire_id)).flatten().is_none());
+    }
+
+    #[test]
+    fn removing_duplicate_wires_leaves_exactly_one() {
+        let app = Application::new();
+        let project = app.imp().project().clone();
+        let plot_provider = PlotProvider::Main(project.clone());
+        let mut stack = ActionStack::default();
+
+        let (a, b) = (instance_of(&project, "And", Vector2(0, 0)), instance_of(&project, "Not", Vector2(200, 0)));
+        let (a_id, b_id) = (a.id(), b.id());
+        plot_provider.with_mut(|plot| {
+            plot.add_block(a);
+            plot.add_block(b);
+            plot.add_connection(Connection::new_basic(a_id, 0, b_id, 0));
+            // a second wire between the same pins, as left behind by pasting
+            plot.restore_connection(Connection::new_basic(a_id, 0, b_id, 0));
+        });
+
+        assert_eq!(plot_provider.with(|plot| plot.duplicate_connections().len()), Some(1));
+        assert!(validation::validate(&project.lock()).iter().any(|diagnostic| diagnostic.location().map(|(_, block_id)| *block_id) == Some(b_id)));
+
+        let removable = plot_provider.with(|plot| plot.removable_duplicates()).unwrap();
+        assert_eq!(removable.len(), 1);
+        stack.add(&app, Action::RemoveDuplicates(plot_provider.clone(), removable));
+
+        plot_provider.with(|plot| {
+            assert_eq!(plot.connections().len(), 1);
+            assert!(plot.duplicate_connections().is_empty());
+            let remaining = plot.connections().keys().next().copied();
+            assert_eq!(plot.get_block(a_id).unwrap().outputs()[0], remaining);
+            assert_eq!(plot.get_block(b_id).unwrap().inputs()[0], remaining);
+        });
+
+        stack.undo(&app);
+        assert_eq!(plot_provider.with(|plot| plot.connections().len()), Some(2));
+    }
+
+    #[test]
+    fn moving_a_wire_end_updates_the_wire_and_both_pins() {
+        let app = Application::new();
+        let project = app.imp().project().clone();
+        let plot_provider = PlotProvider::Main(project.clone());
+        let mut stack = ActionStack::default();
+
+        let (source, first, second) = (instance_of(&project, "Not", Vector2(0, 0)), instance_of(&project, "Not", Vector2(200, 0)), instance_of(&project, "And", Vector2(200, 200)));
+        let wire = Connection::new_basic(source.id(), 0, first.id(), 0);
+        let (source_id, first_id, second_id, wire_id) = (source.id(), first.id(), second.id(), wire.id());
+        plot_provider.with_mut(|plot| {
+            [source, first, second].into_iter().for_each(|block| plot.add_block(block));
+            plot.add_connection(wire);
+        });
+
+        assert_eq!(plot_provider.with(|plot| plot.check_connection((source_id, 0), (second_id, 1)).is_ok()), Some(true));
+        stack.add(&app, Action::ReanchorConnection(plot_provider.clone(), wire_id, (first_id, 0), (second_id, 1)));
+
+        let pins = |plot: &Plot| plot.get_connection(&wire_id).unwrap().destinations().iter().map(|port| (port.block_id(), port.index())).collect::<Vec<_>>();
+        plot_provider.with(|plot| {
+            assert_eq!(pins(plot), vec![(second_id, 1)]);
+            assert_eq!(plot.get_block(first_id).unwrap().inputs()[0], None);
+            assert_eq!(plot.get_block(second_id).unwrap().inputs()[1], Some(wire_id));
+        });
+
+        stack.undo(&app);
+        plot_provider.with(|plot| {
+            assert_eq!(pins(plot), vec![(first_id, 0)]);
+            assert_eq!(plot.get_block(first_id).unwrap().inputs()[0], Some(wire_id));
+            assert_eq!(plot.get_block(second_id).unwrap().inputs()[1], None);
+        });
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{simulator::*, renderer::vector::*, id::Id};
 use serde::{Serialize, Deserialize};
+use std::{cell::Cell, collections::HashSet};
 
 use super::{action::Action, selection::*};
 
@@ -7,7 +8,8 @@ use super::{action::Action, selection::*};
 pub enum Clipboard {
     Empty,
     Blocks(Vec<Block>, Vec<Connection>),
-    Module(Box<Module>)
+    // a module with the custom modules it uses
+    Module(Box<Module>, Vec<Module>)
 }
 
 impl Default for Clipboard {
@@ -29,14 +31,52 @@ impl Clipboard {
         if let Clipboard::Blocks(blocks, connections) = self {
             let mut data = (blocks.to_owned(), connections.to_owned());
             data.prepare_pasting(position);
+
+            // select the pasted waypoints as well, so that moving the selection keeps the wire routing intact
+            let mut selection: Vec<Selectable> = data.0.iter().map(|block| Selectable::Block(block.id())).collect();
+            data.1.iter_mut().for_each(|connection| 
+                connection.for_each_mut_segment_id(|_, waypoint_id| selection.push(Selectable::Waypoint(waypoint_id.clone())))
+            );
+
             plot_provider.with_mut(|plot| {
                 plot.unhighlight();
-                plot.set_selection(Selection::Many(data.0.iter().map(|block| Selectable::Block(block.id())).collect()));
+                plot.set_selection(Selection::Many(selection));
             });
             return Ok(Action::PasteBlocks(plot_provider, data.0, data.1));
         }
+
+        if let Clipboard::Module(module, dependencies) = self {
+            return Self::paste_module(module, dependencies, plot_provider, position);
+        }
         
-        panic!("called `paste_to()` on clipboard != Clipboard::Blocks")
+        panic!("called `paste_to()` on an empty clipboard")
+    }
+
+    // adds the module and its dependencies to the project unless equivalent ones exist, then places an instance of it
+    fn paste_module(module: &Module, dependencies: &[Module], plot_provider: PlotProvider, position: Vector2<f64>) -> Result<Action, String> {
+        let project = plot_provider.project().ok_or_else(|| String::from("There is no project to paste the module into."))?;
+        let (name, added) = project.lock().paste_module(module, dependencies)?;
+
+        let module = added.iter().find(|module| module.name() == &name).unwrap_or(module);
+        let mut actions = added.iter().map(|module| Action::CreateModule(project.clone(), module.clone())).collect::<Vec<_>>();
+
+        // an instance can't go into the module itself or into one of the modules it uses
+        let recursive = plot_provider.is_module().map_or(false, |parent| parent == &name || {
+            let project = project.lock();
+            let uses_parent = |module: &Module| module.plot().map_or(false, |plot| plot.blocks().values().any(|block|
+                block.module_id() == parent || validation::contains_module(&project, block.module_id(), parent, &mut HashSet::new())
+            ));
+            uses_parent(module) || added.iter().any(uses_parent)
+        });
+        if recursive && actions.is_empty() {
+            return Err(format!("\"{name}\" uses this module itself; placing it here would cause recursion."));
+        }
+        if !recursive {
+            let block = Block::new(&module, Vector2::cast(position), None);
+            actions.push(Action::NewBlock(plot_provider, block));
+        }
+
+        Ok(Action::Transaction(String::from("Paste Module"), actions))
     }
 }
 
@@ -120,8 +160,15 @@ trait Pasteable<T> {
 
 impl Pasteable<Vector2<f64>> for (Vec<Block>, Vec<Connection>) {
     fn prepare_pasting(&mut self, position: Vector2<f64>) -> &mut Self {
-        let min = self.0.iter().map(|block| block.position()).min().unwrap_or_default();
-        let offset = Vector2::cast(position) - min;
+        // use the top-left corner of all blocks and waypoints as reference point
+        let min = self.0.iter().map(|block| block.position()).reduce(|a, b| Vector2(a.0.min(b.0), a.1.min(b.1)));
+        let min = Cell::new(min);
+        self.1.iter_mut().for_each(|connection| connection.for_each_mut_segment(|segment|
+            if let Some(position) = segment.position() {
+                min.set(Some(min.get().map_or(*position, |min| Vector2(min.0.min(position.0), min.1.min(position.1)))));
+            }
+        ));
+        let offset = Vector2::cast(position) - min.get().unwrap_or_default();
 
         self.0.iter_mut().for_each(|block| {
             let new_id = Id::new();
@@ -139,7 +186,8 @@ impl Pasteable<Vector2<f64>> for (Vec<Block>, Vec<Connection>) {
             connection.set_id(new_id);
             connection.for_each_mut_segment(|segment| 
                 if let Some(position) = segment.position() {
-                    segment.set_position(*position + offset)
+                    segment.set_position(*position + offset);
+                    segment.set_highlighted(true);
                 }
             );
 
@@ -157,3 +205,53 @@ impl Pasteable<Vector2<f64>> for (Vec<Block>, Vec<Connection>) {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{project::Project, application::{Application, action::ActionStack}};
+    use gtk::subclass::prelude::ObjectSubclassIsExt;
+
+    #[test]
+    fn a_copied_module_is_pasted_with_its_dependencies() {
+        let mut source = Project::default();
+        let xor = source.subcircuit_module(&String::from("Xor")).unwrap();
+        let xor_name = xor.name().clone();
+        source.add_module(xor);
+        let mut outer = Module::new(String::from("Outer"), 2, 1);
+        outer.plot_mut().unwrap().add_block(Block::new(&&source.modules()[&xor_name], Vector2(100, 100), None));
+        source.add_module(outer);
+
+        let clipboard = Clipboard::Module(Box::new(source.modules()["Outer"].clone()), vec![source.modules()[&xor_name].clone()]);
+        let clipboard = Clipboard::deserialize(&clipboard.serialize().unwrap()).unwrap();
+
+        let app = Application::new();
+        let project = app.imp().project().clone();
+        let action = clipboard.paste_to(PlotProvider::Main(project.clone()), Vector2(200., 200.)).unwrap();
+        ActionStack::default().add(&app, action);
+
+        let project = project.lock();
+        assert!(project.modules()[&xor_name].same_contents(&source.modules()[&xor_name]));
+        assert!(project.modules()["Outer"].same_contents(&source.modules()["Outer"]));
+        assert!(project.main_plot().blocks().values().any(|block| block.module_id() == "Outer"));
+    }
+
+    #[test]
+    fn pasting_moves_waypoints_along_with_the_blocks() {
+        let source = Block::new(&builtin::BUILTINS["And"].module(), Vector2(0, 0), None);
+        let sink = Block::new(&builtin::BUILTINS["Not"].module(), Vector2(300, 200), None);
+        let corner = Vector2(200, 50);
+        let to_sink = std::collections::HashMap::from([(Id::new(), Segment::Block(sink.id(), 0))]);
+        let connection = Connection::new(Port::Output(source.id(), 0), vec![Segment::Waypoint(to_sink, corner, false)]);
+
+        // the top-left corner of everything copied is the source block's at the origin
+        let offset = Vector2(500, 300);
+        let mut data = (vec![source, sink], vec![connection]);
+        data.prepare_pasting(Vector2(500., 300.));
+
+        let waypoint = data.1[0].segments().values().find_map(|segment| segment.position().copied());
+        assert_eq!(waypoint, Some(corner + offset));
+        assert_eq!(data.0[0].position(), Vector2(0, 0) + offset);
+        assert_eq!(data.0[1].position(), Vector2(300, 200) + offset);
+    }
+}
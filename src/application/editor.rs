@@ -1,4 +1,5 @@
 use crate::renderer::{Renderable, COLOR_THEME, vector::{Vector2, VectorCast}};
+use serde::{Serialize, Deserialize};
 
 #[derive(Default, Copy, Clone)]
 pub enum EditorMode {
@@ -16,6 +17,35 @@ impl From<bool> for EditorMode {
     }
 }
 
+#[derive(Default, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum WireStyle {
+    // curved wires, straight ones while the grid is enabled
+    #[default]
+    Auto = 0,
+    Straight = 1,
+    Orthogonal = 2,
+    Bezier = 3
+}
+
+#[derive(Default, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum GridStyle {
+    #[default]
+    Dots,
+    Lines
+}
+
+impl From<u8> for WireStyle {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Auto,
+            1 => Self::Straight,
+            2 => Self::Orthogonal,
+            3 => Self::Bezier,
+            _ => panic!()
+        }
+    }
+}
+
 // grid size in pixels at scale 1.0
 pub const GRID_SIZE: i32 = 25;
 
@@ -32,7 +62,7 @@ impl EditorMode {
 const SCALE_CUTOFF: f64 = 0.30; 
 
 impl Renderable for EditorMode {
-    fn render<R>(&self, renderer: &R, _data: &crate::simulator::Plot) -> Result<(), R::Error>
+    fn render<R>(&self, renderer: &R, data: &crate::simulator::Plot) -> Result<(), R::Error>
         where R: crate::renderer::Renderer {
         match self {
             EditorMode::Grid => {
@@ -44,9 +74,20 @@ impl Renderable for EditorMode {
                 let offset = VectorCast::cast(start) / GRID_SIZE.into() * GRID_SIZE.into();
 
                 renderer.set_color(unsafe { &COLOR_THEME.grid_color });
-                for i in (offset.0..end.0 as i32).step_by(GRID_SIZE as usize) {
-                    for j in (offset.1..end.1 as i32).step_by(GRID_SIZE as usize) {
-                        renderer.rectangle(Vector2(i - 1, j - 1), Vector2(2, 2)).fill()?;
+                match data.grid_style() {
+                    GridStyle::Dots => for i in (offset.0..end.0 as i32).step_by(GRID_SIZE as usize) {
+                        for j in (offset.1..end.1 as i32).step_by(GRID_SIZE as usize) {
+                            renderer.rectangle(Vector2(i - 1, j - 1), Vector2(2, 2)).fill()?;
+                        }
+                    }
+                    GridStyle::Lines => {
+                        renderer.set_line_width(1.);
+                        for i in (offset.0..end.0 as i32).step_by(GRID_SIZE as usize) {
+                            renderer.move_to(Vector2(i, offset.1)).line_to(Vector2(i, end.1 as i32)).stroke()?;
+                        }
+                        for j in (offset.1..end.1 as i32).step_by(GRID_SIZE as usize) {
+                            renderer.move_to(Vector2(offset.0, j)).line_to(Vector2(end.0 as i32, j)).stroke()?;
+                        }
                     }
                 }
 
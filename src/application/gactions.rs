@@ -1,5 +1,6 @@
-use super::{*, selection::Selectable};
-use crate::{fatal::*, project::Project, simulator::Simulator, FileExtension, export::ModuleFile};
+use super::{*, selection::{Selectable, Selection}, editor::WireStyle};
+use crate::{fatal::*, project::{Project, SaveFormat}, simulator::{Simulator, Block, Connection, PlotDescriptor, routing::Router, validation}, FileExtension, export::{ModuleFile, LibraryFile, TruthTable, HtmlViewer}, logisim::LogisimFile};
+use std::{rc::Rc, cell::Cell, collections::HashSet};
 
 #[derive(Default, Clone, Copy)]
 enum Theme {
@@ -36,6 +37,18 @@ impl From<Theme> for adw::ColorScheme {
     }
 }
 
+impl ToVariant for WireStyle {
+    fn to_variant(&self) -> glib::Variant {
+        (*self as isize as u8).to_variant()
+    }
+}
+
+impl ToVariant for CloseBehavior {
+    fn to_variant(&self) -> glib::Variant {
+        (*self as isize as u8).to_variant()
+    }
+}
+
 pub(super) type GActionCallbackFn = fn(Application, &gio::SimpleAction, Option<&glib::Variant>);
 
 pub(super) struct GAction<'a> {
@@ -82,11 +95,16 @@ impl<'a> From<&GAction<'a>> for gio::SimpleAction {
 }
 
 lazy_static! {
-    pub(super) static ref ACTIONS: [GAction<'static>; 22] = [
+    pub(super) static ref ACTIONS: [GAction<'static>; 81] = [
         GAction::new("quit", &["<primary>Q", "<primary>W"], None, None, Application::gaction_quit),
         GAction::new("about", &["<primary>comma"], None, None, Application::gaction_about),  
         GAction::new("save", &["<primary>S"], None, None, Application::gaction_save),
         GAction::new("save-as", &["<primary><shift>S"], None, None, Application::gaction_save_as),
+        GAction::new("save-as-compressed", &[], None, None, Application::gaction_save_as_compressed),
+        GAction::new("save-as-binary", &[], None, None, Application::gaction_save_as_binary),
+        GAction::new("save-copy", &[], None, None, Application::gaction_save_copy),
+        GAction::new("reveal-file", &[], None, None, Application::gaction_reveal_file),
+        GAction::new("reload-file", &[], None, None, Application::gaction_reload_file),
         GAction::new("open", &["<primary>O"], None, None, Application::gaction_open),
         GAction::new("new", &["<primary>N"], None, None, Application::gaction_new),
         GAction::new("delete-block", &["Delete"], None, None, Application::gaction_delete_block),
@@ -97,14 +115,68 @@ lazy_static! {
         GAction::new("cut", &["<primary>X"], None, None, Application::gaction_cut),
         GAction::new("paste", &["<primary>V"], None, None, Application::gaction_paste),
         GAction::new("select-all", &["<primary>A"], None, None, Application::gaction_select_all),
+        GAction::new("select-module-instances", &[], None, None, Application::gaction_select_module_instances),
         GAction::new("set-selection-color", &[], None, None, Application::gaction_set_selection_color),
         GAction::new("delete-module", &[], Some(glib::VariantTy::STRING), None, Application::gaction_delete_module),
         GAction::new("edit-module", &[], Some(glib::VariantTy::STRING), None, Application::gaction_edit_module),
+        GAction::new("toggle-module-lock", &[], Some(glib::VariantTy::STRING), None, Application::gaction_toggle_module_lock),
+        GAction::new("toggle-module-source-sink", &[], Some(glib::VariantTy::STRING), None, Application::gaction_toggle_module_source_sink),
+        GAction::new("toggle-favorite-module", &[], Some(glib::VariantTy::STRING), None, Application::gaction_toggle_favorite_module),
+        GAction::new("find-module-instances", &[], Some(glib::VariantTy::STRING), None, Application::gaction_find_module_instances),
+        GAction::new("replace-module", &[], Some(glib::VariantTy::STRING), None, Application::gaction_replace_module),
         GAction::new("search-module", &["<primary>F"], None, None, Application::gaction_search_module),
         GAction::new("change-theme", &[], None, Some((glib::VariantTy::BYTE, Theme::SystemPreference.to_variant())), Application::gaction_change_theme),
         GAction::new("change-tick-speed", &[], None, Some((glib::VariantTy::INT32, Simulator::DEFAULT_TICKS_PER_SECOND.to_variant())), Application::gaction_change_tps),
+        GAction::new("change-close-behavior", &[], None, Some((glib::VariantTy::BYTE, CloseBehavior::default().to_variant())), Application::gaction_change_close_behavior),
+        GAction::new("change-wire-style", &[], None, Some((glib::VariantTy::BYTE, WireStyle::default().to_variant())), Application::gaction_change_wire_style),
+        GAction::new("toggle-net-colors", &[], None, Some((glib::VariantTy::BOOLEAN, false.to_variant())), Application::gaction_toggle_net_colors),
+        GAction::new("toggle-connection-counts", &["<primary><shift>C"], None, Some((glib::VariantTy::BOOLEAN, false.to_variant())), Application::gaction_toggle_connection_counts),
+        GAction::new("toggle-auto-throttle", &[], None, Some((glib::VariantTy::BOOLEAN, false.to_variant())), Application::gaction_toggle_auto_throttle),
+        GAction::new("name-net", &[], None, None, Application::gaction_name_net),
+        GAction::new("toggle-block-disabled", &["<primary>slash"], None, None, Application::gaction_toggle_block_disabled),
+        GAction::new("label-wires", &[], None, None, Application::gaction_label_wires),
+        GAction::new("set-connection-style", &[], Some(glib::VariantTy::BYTE), None, Application::gaction_set_connection_style),
+        GAction::new("swap-inputs", &[], None, None, Application::gaction_swap_inputs),
+        GAction::new("change-block-module", &[], None, None, Application::gaction_change_block_module),
+        GAction::new("replace-with-subcircuit", &[], None, None, Application::gaction_replace_with_subcircuit),
+        GAction::new("connect-selected-pins", &["<primary>J"], None, None, Application::gaction_connect_selected_pins),
+        GAction::new("remove-waypoint", &[], None, None, Application::gaction_remove_waypoint),
+        GAction::new("simplify-connection", &[], None, None, Application::gaction_simplify_connection),
+        GAction::new("reroute-connections", &[], None, None, Application::gaction_reroute_connections),
+        GAction::new("fit-pin-counts", &[], None, None, Application::gaction_fit_pin_counts),
+        GAction::new("separate-stacked-blocks", &[], None, None, Application::gaction_separate_stacked_blocks),
+        GAction::new("remove-duplicate-connections", &[], None, None, Application::gaction_remove_duplicate_connections),
+        GAction::new("project-properties", &[], None, None, Application::gaction_project_properties),
+        GAction::new("canvas-appearance", &[], None, None, Application::gaction_canvas_appearance),
+        GAction::new("normalize-ids", &[], None, None, Application::gaction_normalize_ids),
+        GAction::new("copy-image", &[], Some(glib::VariantTy::BOOLEAN), None, Application::gaction_copy_image),
+        GAction::new("trace-source", &[], None, None, Application::gaction_trace_source),
+        GAction::new("add-breakpoint", &[], None, None, Application::gaction_add_breakpoint),
+        GAction::new("watch-net", &[], None, None, Application::gaction_watch_net),
+        GAction::new("simulate-to-stable", &[], None, None, Application::gaction_simulate_to_stable),
+        GAction::new("edit-stimulus", &[], None, None, Application::gaction_edit_stimulus),
+        GAction::new("toggle-stimulus", &[], None, Some((glib::VariantTy::BOOLEAN, false.to_variant())), Application::gaction_toggle_stimulus),
+        GAction::new("show-breakpoints", &[], None, None, Application::gaction_show_breakpoints),
+        GAction::new("show-snapshots", &[], None, None, Application::gaction_show_snapshots),
+        GAction::new("toggle-activity-tracking", &[], None, Some((glib::VariantTy::BOOLEAN, false.to_variant())), Application::gaction_toggle_activity_tracking),
+        GAction::new("activity-report", &[], None, None, Application::gaction_activity_report),
+        GAction::new("wire-statistics", &[], None, None, Application::gaction_wire_statistics),
+        GAction::new("validate-simulation", &[], None, Some((glib::VariantTy::BOOLEAN, true.to_variant())), Application::gaction_validate_simulation),
+        GAction::new("highlight-problems", &[], None, Some((glib::VariantTy::BOOLEAN, false.to_variant())), Application::gaction_highlight_problems),
+        GAction::new("toggle-focus-mode", &["<primary><shift>F"], None, Some((glib::VariantTy::BOOLEAN, false.to_variant())), Application::gaction_toggle_focus_mode),
+        GAction::new("show-tab-overview", &["<primary><shift>O"], None, None, Application::gaction_show_tab_overview),
         GAction::new("export-module", &[], Some(glib::VariantTy::STRING), None, Application::gaction_export_module),
-        GAction::new("import-module", &[], None, None, Application::gaction_import_module)
+        GAction::new("copy-module", &[], Some(glib::VariantTy::STRING), None, Application::gaction_copy_module),
+        GAction::new("name-pins", &[], Some(glib::VariantTy::STRING), None, Application::gaction_name_pins),
+        GAction::new("export-truth-table", &[], Some(glib::VariantTy::STRING), None, Application::gaction_export_truth_table),
+        GAction::new("show-truth-table", &[], Some(glib::VariantTy::STRING), None, Application::gaction_show_truth_table),
+        GAction::new("export-html", &[], None, None, Application::gaction_export_html),
+        GAction::new("toggle-value-labels", &[], None, Some((glib::VariantTy::BOOLEAN, false.to_variant())), Application::gaction_toggle_value_labels),
+        GAction::new("import-module", &[], None, None, Application::gaction_import_module),
+        GAction::new("import-project-modules", &[], None, None, Application::gaction_import_project_modules),
+        GAction::new("export-library", &[], None, None, Application::gaction_export_library),
+        GAction::new("import-library", &[], None, None, Application::gaction_import_library),
+        GAction::new("import-logisim", &[], None, None, Application::gaction_import_logisim)
     ];
 }
 
@@ -131,6 +203,101 @@ impl Application {
         self.save_as(|_| ());
     }
 
+    fn gaction_save_as_compressed(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        self.save_as_format("Save As Compressed", SaveFormat::CompressedJson, |_| ());
+    }
+
+    fn gaction_save_as_binary(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        self.save_as_format("Save As Compact Binary", SaveFormat::Binary, |_| ());
+    }
+
+    fn gaction_save_copy(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        self.save_copy();
+    }
+
+    fn gaction_reload_file(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if self.imp().file().is_none() {
+            return;
+        }
+
+        if !self.imp().is_dirty() {
+            self.reload_file();
+            return;
+        }
+
+        let window = self.active_window().unwrap();
+        let reload_dialog = adw::MessageDialog::builder()
+            .transient_for(&window)
+            .modal(true)
+            .heading("Reload from Disk?")
+            .body(format!("All unsaved changes in \"{}\" will be lost.", self.imp().file_name()).as_str())
+            .close_response("Cancel")
+            .default_response("Cancel")
+            .build();
+
+        reload_dialog.add_response("Cancel", "Cancel");
+        reload_dialog.add_response("Reload", "Reload");
+        reload_dialog.set_response_appearance("Reload", adw::ResponseAppearance::Destructive);
+        reload_dialog.present();
+
+        reload_dialog.connect_response(None, glib::clone!(@weak self as app => move |_, response| if response == "Reload" {
+            app.reload_file();
+        }));
+    }
+
+    // replaces the project with the contents of its file, restarting the simulation
+    fn reload_file(&self) {
+        let Some(file) = self.imp().file() else {
+            return;
+        };
+
+        match Project::load_from(&file) {
+            Ok(project) => self.imp().set_project(project, Some(file)),
+            Err(err) => {
+                let message = format!("Error reloading '{}': {}", self.imp().file_name(), err);
+                error!("{}", message);
+                if let Some(window) = self.active_window() {
+                    dialogs::run(self.clone(), window, message, dialogs::basic_error);
+                }
+            }
+        }
+    }
+
+    fn gaction_reveal_file(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let Some(file) = self.imp().file() else {
+            return;
+        };
+
+        // ask the file manager to select the file, fall back to just opening its folder
+        let uri = file.uri().to_string();
+        let show_folder = move || if let Some(folder) = file.parent() {
+            if let Err(err) = gio::AppInfo::launch_default_for_uri(&folder.uri(), gio::AppLaunchContext::NONE) {
+                error!("Could not open folder `{}`: {err}", folder.uri());
+            }
+        };
+
+        match gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE) {
+            Ok(connection) => {
+                connection.call(
+                    Some("org.freedesktop.FileManager1"),
+                    "/org/freedesktop/FileManager1",
+                    "org.freedesktop.FileManager1",
+                    "ShowItems",
+                    Some(&(vec![uri], "").to_variant()),
+                    None,
+                    gio::DBusCallFlags::NONE,
+                    -1,
+                    gio::Cancellable::NONE,
+                    move |result| if let Err(err) = result {
+                        warn!("Could not reveal file in file manager: {err}");
+                        show_folder();
+                    }
+                );
+            }
+            Err(_) => show_folder()
+        }
+    }
+
     fn gaction_open(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
         self.open();
     }
@@ -141,107 +308,862 @@ impl Application {
 
     fn gaction_delete_block(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
         if let Some(plot_provider) = self.imp().current_plot() {
-            let (blocks, connections) = plot_provider.with_mut(|plot| (
+            let (blocks, connections): (Vec<Block>, Vec<Connection>) = plot_provider.with_mut(|plot| (
                 plot.selected().iter().filter_map(|selected| {
                     match selected {
                         Selectable::Block(id) if let Some(block) = plot.get_block(*id) => Some(block.to_owned()),
                         _ => None
                     }
                 }).collect(),
-                plot.selected().iter().filter_map(|selected| {
-                    match selected {
-                        Selectable::Waypoint(id) if let Some(connection) = plot.get_connection(id.connection_id()) => Some(connection.to_owned()),
-                        _ => None
-                    }
-                }).collect()
+                plot.selected().iter()
+                    .filter_map(|selected| selected.connection_id().and_then(|id| plot.get_connection(&id)))
+                    .map(|connection| connection.to_owned())
+                    .collect()
             )).unwrap_or_default();
-            self.new_action(Action::DeleteSelection(plot_provider, blocks, connections, vec![]));
+
+            // unique blocks (module inputs and outputs) cannot be deleted
+            let (unique, blocks): (Vec<Block>, Vec<Block>) = blocks.into_iter().partition(|block| block.unique());
+            if !unique.is_empty() && let Some(window) = self.imp().window().borrow().as_ref() {
+                window.panel().push_error(format!("Skipped {} input/output block(s); they cannot be deleted.", unique.len()));
+            }
+
+            if !blocks.is_empty() || !connections.is_empty() {
+                self.new_action(Action::DeleteSelection(plot_provider, blocks, connections, vec![]));
+            }
+        }
+    }
+
+    fn gaction_create_new_module(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(window) = self.active_window() {
+            dialogs::run(self, window, (), dialogs::new_module); 
+        }
+    }
+
+    fn gaction_undo(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        self.undo_action();
+    }
+
+    fn gaction_redo(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        self.redo_action();
+    }
+
+
+    fn gaction_copy(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        self.copy_clipboard(false);
+    }
+
+    fn gaction_cut(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        self.copy_clipboard(true);
+    }
+
+    fn gaction_copy_image(self, _: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        let transparent = parameter.and_then(|parameter| parameter.get::<bool>()).unwrap_or_default();
+        self.copy_image(transparent);
+    }
+
+    fn gaction_copy_module(self, _: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        if let Some(module_name) = parameter.and_then(|parameter| parameter.get::<String>()) {
+            self.copy_module(&module_name);
         }
     }
 
-    fn gaction_create_new_module(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+    fn gaction_paste(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        self.paste_clipboard();
+    }
+
+    fn gaction_select_all(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        self.imp().with_current_plot_mut(|plot| plot.select_all());
+    }
+
+    fn gaction_select_module_instances(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        self.imp().with_current_plot_mut(|plot| {
+            let module_ids = plot.selected().iter()
+                .filter_map(|selected| selected.block_id())
+                .filter_map(|block_id| plot.get_block(block_id))
+                .map(|block| block.module_id().clone())
+                .collect::<HashSet<_>>();
+
+            if !module_ids.is_empty() {
+                plot.select_module_instances(&module_ids);
+            }
+        });
+        self.imp().rerender_editor();
+    }
+
+    fn gaction_set_selection_color(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(window) = self.active_window() {
+            dialogs::run(self, window, (), dialogs::select_border_color);
+        }
+    }
+
+    fn gaction_delete_module(self, _: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        let module_name = parameter
+                .expect("Could not get module name target.")
+                .get::<String>().unwrap();
+
+        if let Some(window) = self.active_window() {
+            dialogs::run(self, window, module_name, dialogs::confirm_delete_module);
+        }
+    }
+
+    fn gaction_edit_module(self, _: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        let module_name = parameter
+            .expect("Could not get module name target.")
+            .get::<String>().unwrap();
+        self.imp().edit_module(module_name);
+    }
+
+    fn gaction_toggle_module_lock(self, _: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        let module_name = parameter
+            .expect("Could not get module name target.")
+            .get::<String>().unwrap();
+
+        let locked = self.imp().project().lock().module_mut(&module_name).map(|module| {
+            module.set_locked(!module.locked());
+            module.locked()
+        });
+
+        if let Some(locked) = locked {
+            info!("{} module `{module_name}`", if locked { "locked" } else { "unlocked" });
+            self.imp().action_stack().borrow_mut().set_dirty(true);
+            if let Some(view) = self.imp().current_circuit_view() && view.plot_provider().is_module() == Some(&module_name) {
+                view.plot_provider().with_mut(|plot| {
+                    plot.unhighlight();
+                    plot.set_selection(Selection::None);
+                });
+                view.rerender();
+            }
+        }
+    }
+
+    fn gaction_toggle_module_source_sink(self, _: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        let module_name = parameter
+            .expect("Could not get module name target.")
+            .get::<String>().unwrap();
+
+        let source_or_sink = self.imp().project().lock().module_mut(&module_name).map(|module| {
+            module.set_source_or_sink(!module.source_or_sink());
+            module.source_or_sink()
+        });
+
+        if let Some(source_or_sink) = source_or_sink {
+            info!("{} module `{module_name}` as a source or sink", if source_or_sink { "marked" } else { "unmarked" });
+            self.imp().action_stack().borrow_mut().set_dirty(true);
+        }
+    }
+
+    fn gaction_toggle_favorite_module(self, _: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        let module_name = parameter
+            .expect("Could not get module name target.")
+            .get::<String>().unwrap();
+
+        let favorite = self.imp().preferences().borrow_mut().toggle_favorite(&module_name);
+        if let Err(err) = self.imp().preferences().borrow().save() {
+            error!("Error saving preferences: {err}");
+        }
+
+        let module = self.imp().project().lock().module(&module_name).cloned();
+        if let Some(module) = module && let Some(window) = self.imp().window().borrow().as_ref() {
+            window.module_list().set_favorite(&self, &module, favorite);
+        }
+    }
+
+    fn gaction_search_module(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        self.imp().window()
+            .borrow().as_ref().unwrap()
+            .module_list().show_search();
+    }
+
+    fn gaction_change_theme(self, action: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        let new: Theme = parameter
+            .expect("could not get theme parameter")
+            .get::<u8>()
+            .expect("the parameter needs to be of type `u8`")
+            .into();
+
+        adw::StyleManager::default().set_color_scheme(new.into());
+        action.set_state(&new.to_variant());
+    }
+
+    fn gaction_change_close_behavior(self, action: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        let new: CloseBehavior = parameter
+            .expect("could not get close behavior parameter")
+            .get::<u8>()
+            .expect("the parameter needs to be of type `u8`")
+            .into();
+
+        self.imp().preferences().borrow_mut().set_close_behavior(new);
+        if let Err(err) = self.imp().preferences().borrow().save() {
+            warn!("Could not save preferences: {err}");
+        }
+        action.set_state(&new.to_variant());
+    }
+
+    // actions showing user preferences get their state once the preferences are loaded
+    pub(super) fn sync_preference_gactions(&self) {
+        if let Some(action) = self.lookup_action("change-close-behavior").and_then(|action| action.downcast::<gio::SimpleAction>().ok()) {
+            action.set_state(&self.imp().preferences().borrow().close_behavior().to_variant());
+        }
+        if let Some(action) = self.lookup_action("change-wire-style").and_then(|action| action.downcast::<gio::SimpleAction>().ok()) {
+            action.set_state(&self.imp().preferences().borrow().wire_style().to_variant());
+        }
+    }
+
+    fn gaction_change_tps(self, action: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        let new = parameter
+            .expect("could not get theme parameter")
+            .get::<i32>()
+            .expect("the parameter needs to be of type `u8`");
+
+        self.imp()
+            .project()
+            .lock().set_tps(new);
+
+        action.set_state(&new.to_variant());
+    }
+
+    fn gaction_change_wire_style(self, action: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        let new: WireStyle = parameter
+            .expect("could not get wire style parameter")
+            .get::<u8>()
+            .expect("the parameter needs to be of type `u8`")
+            .into();
+
+        self.imp().preferences().borrow_mut().set_wire_style(new);
+        if let Err(err) = self.imp().preferences().borrow().save() {
+            warn!("Could not save preferences: {err}");
+        }
+        action.set_state(&new.to_variant());
+        self.imp().rerender_editor();
+    }
+
+    fn gaction_toggle_net_colors(self, action: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let mut project = self.imp().project().lock();
+        let enabled = !project.net_colors();
+        project.set_net_colors(enabled);
+        drop(project);
+
+        action.set_state(&enabled.to_variant());
+        self.imp().rerender_editor();
+    }
+
+    fn gaction_toggle_connection_counts(self, action: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let mut project = self.imp().project().lock();
+        let enabled = !project.connection_counts();
+        project.set_connection_counts(enabled);
+        drop(project);
+
+        action.set_state(&enabled.to_variant());
+        self.imp().rerender_editor();
+    }
+
+    fn gaction_toggle_auto_throttle(self, action: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let mut project = self.imp().project().lock();
+        let enabled = !project.auto_throttle();
+        project.set_auto_throttle(enabled);
+        drop(project);
+
+        action.set_state(&enabled.to_variant());
+    }
+
+    fn gaction_name_net(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(plot_provider) = self.imp().current_plot() && let Some(window) = self.active_window() {
+            let net = plot_provider.with(|plot| plot.selected().iter()
+                .find_map(|selected| selected.connection_id().and_then(|id| plot.get_connection(&id)))
+                .map(|connection| (connection.id(), connection.name().clone(), *connection.color()))
+            ).flatten();
+
+            if let Some((connection_id, name, color)) = net {
+                dialogs::run(self, window, (plot_provider, connection_id, name, color), dialogs::name_net);
+            }
+        }
+    }
+
+    fn gaction_toggle_block_disabled(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(plot_provider) = self.imp().current_plot() {
+            let blocks = plot_provider.with(|plot| plot.selected().iter()
+                .filter_map(|selected| selected.block_id())
+                .filter_map(|block_id| plot.get_block(block_id).filter(|block| !block.unique()))
+                .map(|block| (block.id(), block.disabled()))
+                .collect::<Vec<_>>()
+            ).unwrap_or_default();
+
+            if !blocks.is_empty() {
+                // disable the selection unless all of it already is
+                let disabled = blocks.iter().any(|(_, disabled)| !disabled);
+                self.new_action(Action::SetDisabled(plot_provider, disabled, blocks.into_iter().map(|(id, _)| id).collect(), vec![]));
+            }
+        }
+    }
+
+    fn gaction_label_wires(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let Some(plot_provider) = self.imp().current_plot() else {
+            return;
+        };
+
+        let Some((labeled, connection_ids, unnamed)) = plot_provider.with(|plot| {
+            let connections = plot.selected_connections().into_iter()
+                .filter_map(|connection_id| plot.get_connection(&connection_id))
+                .collect::<Vec<_>>();
+            // label the wires unless all of them already are
+            let labeled = connections.iter().any(|connection| !connection.labeled());
+
+            // wires without a name get a fresh one, so that both ends of each show a matching label
+            let unnamed = connections.iter()
+                .filter(|connection| labeled && connection.name().is_none())
+                .map(|connection| (connection.id(), *connection.color()))
+                .collect::<Vec<_>>();
+            let names = plot.unused_net_names(unnamed.len());
+            (labeled, connections.iter().map(|connection| connection.id()).collect::<Vec<_>>(), unnamed.into_iter().zip(names).collect::<Vec<_>>())
+        }) else {
+            return;
+        };
+
+        if connection_ids.is_empty() {
+            if let Some(window) = self.imp().window().borrow().as_ref() {
+                window.panel().push_error(String::from("Select wires, or the blocks they run between, to replace them with labels."));
+            }
+            return;
+        }
+
+        let mut actions = unnamed.into_iter()
+            .map(|((connection_id, color), name)| Action::NameNet(plot_provider.clone(), connection_id, (Some(name), color), (None, color)))
+            .collect::<Vec<_>>();
+        actions.push(Action::SetLabeled(plot_provider, labeled, connection_ids, vec![]));
+        self.new_transaction(if labeled { "Replace Wires with Labels" } else { "Draw Labeled Wires" }, actions);
+    }
+
+    fn gaction_set_connection_style(self, _: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        let style = parameter
+            .expect("could not get wire style parameter")
+            .get::<u8>()
+            .expect("the parameter needs to be of type `u8`");
+        // anything that isn't a wire style goes back to the project's one
+        let style = (style <= WireStyle::Bezier as u8).then(|| WireStyle::from(style));
+
+        let Some(plot_provider) = self.imp().current_plot() else {
+            return;
+        };
+
+        let connection_ids = plot_provider.with(|plot| plot.selected_connections()).unwrap_or_default();
+        if connection_ids.is_empty() {
+            if let Some(window) = self.imp().window().borrow().as_ref() {
+                window.panel().push_error(String::from("Select wires, or the blocks they run between, to change their style."));
+            }
+            return;
+        }
+
+        self.new_action(Action::SetWireStyle(plot_provider, style, connection_ids, vec![]));
+    }
+
+    fn gaction_swap_inputs(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(plot_provider) = self.imp().current_plot() {
+            let block_id = plot_provider.with(|plot| plot.selected().iter()
+                .find_map(|selected| match selected {
+                    Selectable::Block(id) => plot.get_block(*id).filter(|block| block.inputs().len() == 2).map(|block| block.id()),
+                    _ => None
+                })
+            ).flatten();
+
+            if let Some(block_id) = block_id {
+                self.new_action(Action::SwapInputs(plot_provider, block_id));
+            }
+        }
+    }
+
+    fn gaction_change_block_module(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(plot_provider) = self.imp().current_plot() {
+            let block_id = plot_provider.with(|plot| plot.selected().iter()
+                .find_map(|selected| selected.block_id())
+            ).flatten();
+
+            if let Some(block_id) = block_id && let Some(window) = self.active_window() {
+                dialogs::run(self, window, (plot_provider, block_id), dialogs::change_block_module);
+            }
+        }
+    }
+
+    fn gaction_replace_with_subcircuit(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(plot_provider) = self.imp().current_plot() {
+            let selected = plot_provider.with(|plot| plot.selected().iter()
+                .find_map(|selected| selected.block_id())
+                .and_then(|block_id| plot.get_block(block_id).map(|block| (block_id, block.module_id().clone())))
+            ).flatten();
+
+            let Some((block_id, module_id)) = selected else { return };
+            let project = self.imp().project().clone();
+            let module = project.lock().subcircuit_module(&module_id);
+            match module {
+                Ok(module) => {
+                    let name = module.name().clone();
+                    self.new_transaction("Replace with Subcircuit", vec![
+                        Action::CreateModule(project, module),
+                        Action::ChangeBlockModule(plot_provider, block_id, name, None)
+                    ]);
+                }
+                Err(err) => if let Some(window) = self.imp().window().borrow().as_ref() {
+                    window.panel().push_error(err);
+                }
+            }
+        }
+    }
+
+    fn gaction_connect_selected_pins(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(plot_provider) = self.imp().current_plot() {
+            match plot_provider.with(|plot| plot.connect_selected_pins()) {
+                Some(Ok(connection)) => self.new_action(Action::NewConnection(plot_provider, connection, None)),
+                Some(Err(err)) => if let Some(window) = self.imp().window().borrow().as_ref() {
+                    window.panel().push_error(err);
+                }
+                None => ()
+            }
+        }
+    }
+
+    fn gaction_remove_waypoint(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(plot_provider) = self.imp().current_plot() {
+            let reshaped = plot_provider.with(|plot| plot.selected().iter()
+                .find_map(|selected| match selected {
+                    Selectable::Waypoint(id) => plot.get_connection(id.connection_id()).map(|connection| (id.clone(), connection.clone())),
+                    _ => None
+                })
+                .and_then(|(segment_id, mut connection)| {
+                    let old = connection.segments().clone();
+                    connection.remove_waypoint(segment_id.location()).then(|| (connection.id(), connection.segments().clone(), old))
+                })
+            ).flatten();
+
+            if let Some((connection_id, segments, old)) = reshaped {
+                self.new_action(Action::ReshapeConnection(plot_provider, connection_id, segments, old));
+            }
+        }
+    }
+
+    fn gaction_simplify_connection(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(plot_provider) = self.imp().current_plot() {
+            let reshaped = plot_provider.with(|plot| plot.selected().iter()
+                .find_map(|selected| selected.connection_id().and_then(|id| plot.get_connection(&id)))
+                .and_then(|connection| connection.simplified_segments(plot).map(|segments| (connection.id(), segments, connection.segments().clone())))
+            ).flatten();
+
+            if let Some((connection_id, segments, old)) = reshaped {
+                self.new_action(Action::ReshapeConnection(plot_provider, connection_id, segments, old));
+            }
+        }
+    }
+
+    fn gaction_reroute_connections(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(plot_provider) = self.imp().current_plot() {
+            let Some((routes, failed)) = plot_provider.with(|plot| {
+                let router = Router::new(plot);
+                let mut connections = plot.connections().values().collect::<Vec<_>>();
+                connections.sort_by_key(|connection| connection.id());
+
+                let (routes, failed): (Vec<_>, Vec<_>) = connections.into_iter()
+                    .map(|connection| (connection, router.route_connection(plot, connection)))
+                    .partition(|(_, segments)| segments.is_some());
+                let routes = routes.into_iter()
+                    .filter_map(|(connection, segments)| Some((connection.id(), segments?, connection.segments().clone())))
+                    .collect::<Vec<_>>();
+                (routes, failed.len())
+            }) else {
+                return;
+            };
+
+            if failed > 0 && let Some(window) = self.imp().window().borrow().as_ref() {
+                window.panel().push_error(format!("Could not find a route for {failed} connection(s); they were left unchanged."));
+            }
+            if !routes.is_empty() {
+                self.new_action(Action::RerouteConnections(plot_provider, routes));
+            }
+        }
+    }
+
+    fn gaction_remove_duplicate_connections(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let Some(plot_provider) = self.imp().current_plot() else {
+            return;
+        };
+
+        let Some((removable, duplicates)) = plot_provider.with(|plot| (plot.removable_duplicates(), plot.duplicate_connections().len())) else {
+            return;
+        };
+        if let Some(window) = self.imp().window().borrow().as_ref() {
+            if duplicates == 0 {
+                window.panel().push_notice(String::from("No duplicate connections found in this circuit."));
+            }
+            else if removable.is_empty() {
+                window.panel().push_error(String::from("The duplicate connections in this circuit also reach other pins and have to be removed by hand."));
+            }
+        }
+        if !removable.is_empty() {
+            self.new_action(Action::RemoveDuplicates(plot_provider, removable));
+        }
+    }
+
+    fn gaction_fit_pin_counts(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let project_ref = self.imp().project().clone();
+        let fixes = {
+            let project = project_ref.lock();
+            let mut names = project.modules().iter()
+                .filter(|(_, module)| module.plot().is_some())
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+            names.sort();
+
+            names.into_iter()
+                .map(PlotDescriptor::Module)
+                .chain(std::iter::once(PlotDescriptor::Main()))
+                .filter_map(|descriptor| {
+                    let blocks = project.plot_by_descriptor(&descriptor)?.pin_mismatches(&project);
+                    (!blocks.is_empty()).then_some((descriptor, blocks))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        if fixes.is_empty() {
+            if let Some(window) = self.imp().window().borrow().as_ref() {
+                window.panel().push_notice(String::from("Every block already has the pins of its module."));
+            }
+            return;
+        }
+
+        self.new_transaction("Fit Blocks to Their Modules", fixes.into_iter()
+            .map(|(descriptor, blocks)| Action::FitPins(descriptor.to_provider(project_ref.clone()), blocks, vec![]))
+            .collect()
+        );
+    }
+
+    fn gaction_separate_stacked_blocks(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let Some(plot_provider) = self.imp().current_plot() else {
+            return;
+        };
+
+        let moves = plot_provider.with(|plot| plot.separate_stacked_blocks()).unwrap_or_default();
+        if moves.is_empty() {
+            if let Some(window) = self.imp().window().borrow().as_ref() {
+                window.panel().push_notice(String::from("No stacked blocks found in this circuit."));
+            }
+            return;
+        }
+
+        let block_ids = moves.iter().map(|(block_id, ..)| *block_id).collect::<Vec<_>>();
+        self.new_transaction("Separate Stacked Blocks", moves.into_iter()
+            .map(|(block_id, from, to)| Action::MoveBlock(plot_provider.clone(), block_id, from, to))
+            .collect()
+        );
+
+        // leave the moved blocks selected so they can be placed properly
+        plot_provider.with_mut(|plot| {
+            plot.unhighlight();
+            block_ids.iter().for_each(|block_id| if let Some(block) = plot.get_block_mut(*block_id) {
+                block.set_highlighted(true);
+            });
+            plot.set_selection(Selection::Many(block_ids.iter().map(|block_id| Selectable::Block(*block_id)).collect()));
+        });
+        self.imp().rerender_editor();
+    }
+
+    fn gaction_trace_source(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(plot_provider) = self.imp().current_plot() {
+            let connection_id = plot_provider.with(|plot| plot.selected().iter()
+                .find_map(|selected| selected.connection_id())
+            ).flatten();
+            let Some(connection_id) = connection_id else {
+                return;
+            };
+
+            let chain = {
+                let project = self.imp().project().lock();
+                project.plot_by_descriptor(&PlotDescriptor::from(&plot_provider))
+                    .map(|plot| plot.trace_source(&project, &connection_id))
+                    .unwrap_or_default()
+            };
+
+            plot_provider.with_mut(|plot| {
+                plot.unhighlight();
+                chain.iter().for_each(|block_id| if let Some(block) = plot.get_block_mut(*block_id) {
+                    block.set_highlighted(true);
+                });
+                plot.set_selection(Selection::Many(chain.iter().map(|block_id| Selectable::Block(*block_id)).collect()));
+            });
+            self.imp().rerender_editor();
+        }
+    }
+
+    fn gaction_add_breakpoint(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(plot_provider) = self.imp().current_plot() && let Some(window) = self.active_window() {
+            let connection_id = plot_provider.with(|plot| plot.selected().iter()
+                .find_map(|selected| selected.connection_id())
+            ).flatten();
+
+            if let Some(connection_id) = connection_id {
+                dialogs::run(self, window, (plot_provider, connection_id), dialogs::add_breakpoint);
+            }
+        }
+    }
+
+    fn gaction_watch_net(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(plot_provider) = self.imp().current_plot() {
+            let connection_id = plot_provider.with(|plot| plot.selected().iter()
+                .find_map(|selected| selected.connection_id())
+            ).flatten();
+
+            if let Some(connection_id) = connection_id {
+                self.imp().project().lock().add_watch(PlotDescriptor::from(&plot_provider), connection_id);
+                if let Some(window) = self.imp().window().borrow().as_ref() {
+                    window.panel().show_watches();
+                }
+            }
+        }
+    }
+
+    fn gaction_simulate_to_stable(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let result = {
+            let mut project = self.imp().project().lock();
+            // pause first, so the simulation thread doesn't keep ticking afterwards
+            project.set_tps(0);
+            Simulator::settle(&mut project, Simulator::MAX_SETTLE_TICKS)
+        };
+        self.sync_project_gactions();
+        self.imp().rerender_editor();
+
+        let Some(window) = self.imp().window().borrow().clone() else {
+            return;
+        };
+        match result {
+            Ok(ticks) => {
+                let message = format!("The circuit settled after {ticks} tick{}.", if ticks == 1 { "" } else { "s" });
+                info!("{message}");
+                window.panel().push_notice(message);
+            }
+            Err(err) => {
+                info!("{err}");
+                window.panel().push_error(err);
+            }
+        }
+    }
+
+    fn gaction_edit_stimulus(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(window) = self.active_window() {
+            dialogs::run(self, window, (), dialogs::stimulus);
+        }
+    }
+
+    fn gaction_toggle_stimulus(self, action: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let mut project = self.imp().project().lock();
+        let ticks = project.ticks();
+        let playing = match project.stimulus_mut() {
+            Some(stimulus) if stimulus.playing() => {
+                stimulus.stop();
+                false
+            }
+            Some(stimulus) => {
+                stimulus.play(ticks);
+                true
+            }
+            None => false
+        };
+        let missing = project.stimulus().is_none();
+        drop(project);
+
+        action.set_state(&playing.to_variant());
+        if missing {
+            self.gaction_edit_stimulus(action, None);
+        }
+    }
+
+    fn gaction_show_breakpoints(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(window) = self.active_window() {
+            dialogs::run(self, window, (), dialogs::breakpoints);
+        }
+    }
+
+    fn gaction_show_snapshots(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(window) = self.active_window() {
+            dialogs::run(self, window, (), dialogs::snapshots);
+        }
+    }
+
+    fn gaction_project_properties(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(window) = self.active_window() {
+            dialogs::run(self, window, (), dialogs::project_properties);
+        }
+    }
+
+    fn gaction_canvas_appearance(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(plot_provider) = self.imp().current_plot() && let Some(window) = self.active_window() {
+            let appearance = plot_provider.with(|plot| (plot.background(), plot.grid_style()));
+            if let Some(appearance) = appearance {
+                dialogs::run(self, window, (plot_provider, appearance), dialogs::canvas_appearance);
+            }
+        }
+    }
+
+    fn gaction_normalize_ids(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        self.imp().project().lock().normalize_ids();
+
+        // recorded actions refer to the old ids
+        let mut action_stack = self.imp().action_stack().borrow_mut();
+        action_stack.reset();
+        action_stack.set_dirty(true);
+        drop(action_stack);
+        self.imp().undo_button().set_sensitive(false);
+        self.imp().redo_button().set_sensitive(false);
+
+        self.imp().rerender_editor();
+    }
+
+    fn gaction_toggle_activity_tracking(self, action: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let mut project = self.imp().project().lock();
+        let enabled = !project.activity_tracking();
+        project.set_activity_tracking(enabled);
+        action.set_state(&enabled.to_variant());
+    }
+
+    fn gaction_activity_report(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
         if let Some(window) = self.active_window() {
-            dialogs::run(self, window, (), dialogs::new_module); 
+            dialogs::run(self, window, (), dialogs::activity_report);
         }
     }
 
-    fn gaction_undo(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
-        self.undo_action();
+    fn gaction_wire_statistics(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(window) = self.active_window() {
+            dialogs::run(self, window, (), dialogs::wire_statistics);
+        }
     }
 
-    fn gaction_redo(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
-        self.redo_action();
+    fn gaction_validate_simulation(self, action: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let enabled = !self.validation_enabled();
+        action.set_state(&enabled.to_variant());
+        self.imp().restart_simulation();
     }
 
-
-    fn gaction_copy(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
-        self.copy_clipboard(false);
+    pub(super) fn validation_enabled(&self) -> bool {
+        self.action_state("validate-simulation")
+            .and_then(|state| state.get::<bool>())
+            .unwrap_or(true)
     }
 
-    fn gaction_cut(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
-        self.copy_clipboard(true);
+    fn gaction_toggle_value_labels(self, action: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        action.set_state(&(!self.value_labels()).to_variant());
     }
 
-    fn gaction_paste(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
-        self.paste_clipboard();
+    // whether exported images get the value of each net written next to it
+    pub(crate) fn value_labels(&self) -> bool {
+        self.action_state("toggle-value-labels")
+            .and_then(|state| state.get::<bool>())
+            .unwrap_or(false)
     }
 
-    fn gaction_select_all(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
-        self.imp().with_current_plot_mut(|plot| plot.select_all());
+    fn gaction_toggle_focus_mode(self, action: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        action.set_state(&(!self.focus_mode()).to_variant());
         self.imp().rerender_editor();
     }
 
-    fn gaction_set_selection_color(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
-        if let Some(window) = self.active_window() {
-            dialogs::run(self, window, (), dialogs::select_border_color);
+    fn gaction_show_tab_overview(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let panel = self.imp().window().borrow().as_ref().map(|window| window.panel().clone());
+        if let Some(panel) = panel && let Some(window) = self.active_window() {
+            dialogs::run(self, window, panel, dialogs::tab_overview);
         }
     }
 
-    fn gaction_delete_module(self, _: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
-        let module_name = parameter
-                .expect("Could not get module name target.")
-                .get::<String>().unwrap();
+    // whether the editor fades everything but the selection and its direct neighbors
+    pub(crate) fn focus_mode(&self) -> bool {
+        self.action_state("toggle-focus-mode")
+            .and_then(|state| state.get::<bool>())
+            .unwrap_or(false)
+    }
 
-        if let Some(window) = self.active_window() {
-            dialogs::run(self, window, module_name, dialogs::confirm_delete_module);
-        }
+    fn gaction_highlight_problems(self, action: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let enabled = !self.highlight_problems();
+        action.set_state(&enabled.to_variant());
+        self.check_problems();
     }
 
-    fn gaction_edit_module(self, _: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
-        let module_name = parameter
-            .expect("Could not get module name target.")
-            .get::<String>().unwrap();
-        self.imp().edit_module(module_name);
+    fn highlight_problems(&self) -> bool {
+        self.action_state("highlight-problems")
+            .and_then(|state| state.get::<bool>())
+            .unwrap_or(false)
     }
 
-    fn gaction_search_module(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
-        self.imp().window()
-            .borrow().as_ref().unwrap()
-            .module_list().show_search();
+    // re-validates the project and tints every block with a problem, or removes the tints if turned off;
+    // drive conflicts are marked either way
+    pub(crate) fn check_problems(&self) {
+        let enabled = self.highlight_problems();
+        let mut project = self.imp().project().lock();
+        let diagnostics = if enabled { validation::validate(&project) } else { vec![] };
+        project.flag_problems(&diagnostics);
+        project.mark_drive_conflicts();
+        drop(project);
+        self.imp().rerender_editor();
     }
 
-    fn gaction_change_theme(self, action: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
-        let new: Theme = parameter
-            .expect("could not get theme parameter")
-            .get::<u8>()
-            .expect("the parameter needs to be of type `u8`")
-            .into();
+    pub(crate) fn sync_project_gactions(&self) {
+        let (tps, net_colors, connection_counts, activity_tracking, stimulus_playing, auto_throttle) = {
+            let project = self.imp().project().lock();
+            (
+                project.tps(), project.net_colors(), project.connection_counts(),
+                project.activity_tracking(), project.stimulus().map_or(false, |stimulus| stimulus.playing()),
+                project.auto_throttle()
+            )
+        };
 
-        adw::StyleManager::default().set_color_scheme(new.into());
-        action.set_state(&new.to_variant());
+        let set_state = |name: &str, state: glib::Variant| {
+            if let Some(action) = self.lookup_action(name).and_then(|action| action.downcast::<gio::SimpleAction>().ok()) {
+                action.set_state(&state);
+            }
+        };
+        set_state("change-tick-speed", tps.to_variant());
+        set_state("toggle-net-colors", net_colors.to_variant());
+        set_state("toggle-connection-counts", connection_counts.to_variant());
+        set_state("toggle-activity-tracking", activity_tracking.to_variant());
+        set_state("toggle-stimulus", stimulus_playing.to_variant());
+        set_state("toggle-auto-throttle", auto_throttle.to_variant());
+
+        for name in ["reveal-file", "reload-file"] {
+            if let Some(action) = self.lookup_action(name).and_then(|action| action.downcast::<gio::SimpleAction>().ok()) {
+                action.set_enabled(self.imp().file().is_some());
+            }
+        }
     }
 
-    fn gaction_change_tps(self, action: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
-        let new = parameter
-            .expect("could not get theme parameter")
-            .get::<i32>()
-            .expect("the parameter needs to be of type `u8`");
+    fn gaction_find_module_instances(self, _: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        let module_id = parameter
+            .expect("could not get module paramerter")
+            .get::<String>()
+            .expect("the parameter needs  to be of type `String`");
 
-        self.imp()
-            .project()
-            .lock().unwrap().set_tps(new);
+        let instances = self.imp().project().lock()
+            .find_instances(&module_id)
+            .into_iter()
+            .map(|(plot, block)| (plot, block.id(), block.position()))
+            .collect();
 
-        action.set_state(&new.to_variant());
+        if let Some(window) = self.active_window() {
+            dialogs::run(self, window, (module_id, instances), dialogs::module_instances);
+        }
+    }
+
+    fn gaction_replace_module(self, _: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        let module_id = parameter
+            .expect("could not get module paramerter")
+            .get::<String>()
+            .expect("the parameter needs  to be of type `String`");
+
+        if let Some(window) = self.active_window() {
+            dialogs::run(self, window, module_id, dialogs::replace_module);
+        }
+    }
+
+    fn gaction_name_pins(self, _: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        let module_id = parameter
+            .expect("could not get module paramerter")
+            .get::<String>()
+            .expect("the parameter needs  to be of type `String`");
+
+        if let Some(window) = self.active_window() {
+            dialogs::run(self, window, module_id, dialogs::name_pins);
+        }
     }
 
     fn gaction_export_module(self, _: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
@@ -277,7 +1199,7 @@ impl Application {
                             file.create(gio::FileCreateFlags::NONE, gio::Cancellable::NONE).unwrap_or_die();
                         }
                         let app_template = app.imp();
-                        let mod_file = ModuleFile::from_existing(&app_template.project().lock().unwrap(), module_id.clone()).unwrap();
+                        let mod_file = ModuleFile::from_existing(&app_template.project().lock(), module_id.clone()).unwrap();
                         if let Err(msg) = mod_file.export(&file) {
                             dialogs::run(app, window, msg, dialogs::basic_error);
                         }
@@ -291,6 +1213,42 @@ impl Application {
         export_dialog.show();
     }
 
+    fn gaction_show_truth_table(self, _: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        let module_id = parameter
+            .expect("could not get module paramerter")
+            .get::<String>()
+            .expect("the parameter needs  to be of type `String`");
+
+        // only the first rows of huge tables are shown, just like when exporting them capped
+        let table = TruthTable::from_module(&self.imp().project().lock(), &module_id, Some(Project::MAX_TRUTH_TABLE_ROWS));
+        if let Some(window) = self.active_window() {
+            match table {
+                Ok(table) => dialogs::run(self, window, (module_id, table), dialogs::truth_table),
+                Err(err) => dialogs::run(self, window, err, dialogs::basic_error)
+            }
+        }
+    }
+
+    fn gaction_export_truth_table(self, _: &gio::SimpleAction, parameter: Option<&glib::Variant>) {
+        let module_id = parameter
+            .expect("could not get module paramerter")
+            .get::<String>()
+            .expect("the parameter needs  to be of type `String`");
+
+        let num_rows = self.imp().project().lock().truth_table_rows(&module_id);
+        match num_rows {
+            Ok(num_rows) if num_rows > Project::MAX_TRUTH_TABLE_ROWS => {
+                if let Some(window) = self.active_window() {
+                    dialogs::run(self, window, (module_id, num_rows), dialogs::large_truth_table);
+                }
+            }
+            Ok(_) => self.export_truth_table(module_id, None),
+            Err(err) => if let Some(window) = self.active_window() {
+                dialogs::run(self, window, err, dialogs::basic_error);
+            }
+        }
+    }
+
     fn gaction_import_module(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
         let window = self.active_window().unwrap();
 
@@ -332,6 +1290,142 @@ impl Application {
         open_dialog.show();
     }
 
+    fn gaction_export_library(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        if let Some(window) = self.active_window() {
+            dialogs::run(self, window, (), dialogs::export_library);
+        }
+    }
+
+    pub(crate) fn export_library(&self, name: String, mod_names: Vec<String>) {
+        let file_name = format!("{name}.{}", LibraryFile::FILE_EXTENSION);
+        self.choose_save_file("Export Library", LibraryFile::file_filter(), &file_name, move |app, file| {
+            let library = LibraryFile::from_project(&app.imp().project().lock(), name.clone(), &mod_names);
+            if let Err(err) = library.export(&file) {
+                error!("Error exporting library `{name}`: {err}");
+                if let Some(window) = app.active_window() {
+                    dialogs::run(app, window, err, dialogs::basic_error);
+                }
+            }
+        });
+    }
+
+    fn gaction_import_library(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let window = self.active_window().unwrap();
+
+        let open_dialog = gtk::FileChooserNative::builder()
+            .transient_for(&window)
+            .modal(true)
+            .title("Import Library")
+            .action(gtk::FileChooserAction::Open)
+            .accept_label("Open")
+            .cancel_label("Cancel")
+            .filter(&LibraryFile::file_filter())
+            .build();
+        
+        open_dialog.connect_response({
+            let file_chooser = RefCell::new(Some(open_dialog.clone()));
+            glib::clone!(@weak self as app, @weak window => move |_, response| {
+                if let Some(file_chooser) = file_chooser.take() {
+                    if response != gtk::ResponseType::Accept {
+                        return;
+                    }
+                    if let Some(file) = file_chooser.files().snapshot().into_iter().next() {
+                        let file: gio::File = file
+                            .downcast()
+                            .expect("unexpected type returned from file chooser");
+                        match LibraryFile::import(&file) {
+                            Ok(library) => dialogs::run(app, window, library, dialogs::import_library),
+                            Err(err) => dialogs::run(app, window, format!("Error opening library: {err}"), dialogs::basic_error)
+                        }
+                    }
+                }
+                else {
+                    warn!("got file chooser response after window was freed");
+                }
+            })
+        });
+        
+        open_dialog.show();
+    }
+
+    fn gaction_import_logisim(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let window = self.active_window().unwrap();
+
+        let open_dialog = gtk::FileChooserNative::builder()
+            .transient_for(&window)
+            .modal(true)
+            .title("Import Logisim Circuit")
+            .action(gtk::FileChooserAction::Open)
+            .accept_label("Open")
+            .cancel_label("Cancel")
+            .filter(&LogisimFile::file_filter())
+            .build();
+        
+        open_dialog.connect_response({
+            let file_chooser = RefCell::new(Some(open_dialog.clone()));
+            glib::clone!(@weak self as app, @weak window => move |_, response| {
+                if let Some(file_chooser) = file_chooser.take() {
+                    if response != gtk::ResponseType::Accept {
+                        return;
+                    }
+                    if let Some(file) = file_chooser.files().snapshot().into_iter().next() {
+                        let file: gio::File = file
+                            .downcast()
+                            .expect("unexpected type returned from file chooser");
+                        match LogisimFile::import(&file) {
+                            Ok(circuit) => dialogs::run(app, window, circuit, dialogs::import_logisim),
+                            Err(err) => dialogs::run(app, window, format!("Error opening Logisim circuit: {err}"), dialogs::basic_error)
+                        }
+                    }
+                }
+                else {
+                    warn!("got file chooser response after window was freed");
+                }
+            })
+        });
+        
+        open_dialog.show();
+    }
+
+    fn gaction_import_project_modules(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let window = self.active_window().unwrap();
+
+        let open_dialog = gtk::FileChooserNative::builder()
+            .transient_for(&window)
+            .modal(true)
+            .title("Import Modules from Project")
+            .action(gtk::FileChooserAction::Open)
+            .accept_label("Open")
+            .cancel_label("Cancel")
+            .filter(&Project::file_filter())
+            .build();
+        
+        open_dialog.connect_response({
+            let file_chooser = RefCell::new(Some(open_dialog.clone()));
+            glib::clone!(@weak self as app, @weak window => move |_, response| {
+                if let Some(file_chooser) = file_chooser.take() {
+                    if response != gtk::ResponseType::Accept {
+                        return;
+                    }
+                    if let Some(file) = file_chooser.files().snapshot().into_iter().next() {
+                        let file: gio::File = file
+                            .downcast()
+                            .expect("unexpected type returned from file chooser");
+                        match Project::load_from(&file) {
+                            Ok(project) => dialogs::run(app, window, project, dialogs::import_project_modules),
+                            Err(err) => dialogs::run(app, window, format!("Error opening project: {err}"), dialogs::basic_error)
+                        }
+                    }
+                }
+                else {
+                    warn!("got file chooser response after window was freed");
+                }
+            })
+        });
+        
+        open_dialog.show();
+    }
+
     pub(super) fn close_current_file<F>(&self, after: F)
     where
         F: Fn(&str) + 'static,
@@ -445,19 +1539,143 @@ impl Application {
     }
 
     pub(super) fn save_as(&self, then: fn(&Application)) {
+        self.save_as_format("Save As", SaveFormat::Json, then);
+    }
+
+    fn save_as_format(&self, title: &str, save_format: SaveFormat, then: fn(&Application)) {
+        self.choose_save_file(title, Project::file_filter(), "new-project.lrsproj", move |app, file| {
+            app.imp().project().lock().set_save_format(save_format);
+            app.imp().set_file(file);
+            app.imp().save(then).unwrap_or_die();
+        });
+    }
+
+    pub(super) fn save_copy(&self) {
+        self.choose_save_file("Save a Copy", Project::file_filter(), "new-project.lrsproj", |app, file| {
+            // write the project without touching the current file or the dirty flag
+            if let Some(window) = app.imp().window().borrow().as_ref() {
+                window.panel().store_views();
+            }
+            let mut project = app.imp().project().lock();
+            if let Some(window) = app.imp().window().borrow().as_ref() {
+                project.set_tab_order(window.panel().tab_order());
+            }
+            project.stamp_save_time();
+            let result = project.write_to(&file);
+            drop(project);
+            if let Err(err) = result {
+                let message = format!("Error saving a copy to '{}': {}", file.path().map(|path| path.display().to_string()).unwrap_or_default(), err);
+                error!("{}", message);
+                if let Some(window) = app.active_window() {
+                    dialogs::run(app, window, message, dialogs::basic_error);
+                }
+            }
+        });
+    }
+
+    pub(crate) fn export_truth_table(&self, module_id: String, max_rows: Option<u128>) {
+        let title = format!("Export Truth Table of `{module_id}`");
+        let file_name = format!("{module_id}.{}", TruthTable::FILE_EXTENSION);
+        self.choose_save_file(&title, TruthTable::file_filter(), &file_name, move |app, file| {
+            let table = TruthTable::from_module(&app.imp().project().lock(), &module_id, max_rows);
+            if let Err(err) = table.and_then(|table| table.export(&file)) {
+                error!("Error exporting truth table of `{module_id}`: {err}");
+                if let Some(window) = app.active_window() {
+                    dialogs::run(app, window, err, dialogs::basic_error);
+                }
+            }
+        });
+    }
+
+    fn gaction_export_html(self, _: &gio::SimpleAction, _: Option<&glib::Variant>) {
+        let name = self.imp().file()
+            .and_then(|file| file.path())
+            .and_then(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| String::from("circuit"));
+
+        let file_name = format!("{name}.{}", HtmlViewer::FILE_EXTENSION);
+        self.choose_save_file("Export as HTML Viewer", HtmlViewer::file_filter(), &file_name, move |app, file| {
+            let (plot, net_colors) = {
+                let project = app.imp().project().lock();
+                (project.main_plot().clone(), project.net_colors())
+            };
+            let wire_style = app.imp().preferences().borrow().wire_style();
+            let value_labels = app.value_labels();
+
+            let title = name.clone();
+            app.run_in_background(
+                "Exporting HTML Viewer",
+                move || HtmlViewer::from_plot(plot, title, wire_style, net_colors, value_labels),
+                move |_, viewer| viewer.export(&file)
+            );
+        });
+    }
+
+    // runs `work` on a worker thread behind a cancellable progress dialog, then hands its result to
+    // `finish` on the main thread; a cancelled result is dropped without calling `finish`
+    fn run_in_background<T, W, F>(&self, heading: &str, work: W, finish: F)
+        where T: Send + 'static,
+              W: FnOnce() -> Result<T, String> + Send + 'static,
+              F: FnOnce(&Application, T) -> Result<(), String> + 'static
+    {
+        let window = self.active_window().unwrap();
+        let progress_dialog = adw::MessageDialog::builder()
+            .transient_for(&window)
+            .modal(true)
+            .heading(heading)
+            .body("This may take a while for large circuits.")
+            .extra_child(&gtk::Spinner::builder().spinning(true).build())
+            .close_response("Cancel")
+            .build();
+
+        progress_dialog.add_response("Cancel", "Cancel");
+        progress_dialog.present();
+
+        let cancelled = Rc::new(Cell::new(false));
+        progress_dialog.connect_response(None, glib::clone!(@strong cancelled => move |_, _| cancelled.set(true)));
+
+        let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+        std::thread::spawn(move || {
+            // the receiver is gone if the application quit in the meantime
+            let _ = sender.send(work());
+        });
+
+        let heading = heading.to_string();
+        let mut finish = Some(finish);
+        receiver.attach(None, glib::clone!(@weak self as app => @default-return glib::Continue(false), move |result| {
+            if cancelled.get() {
+                info!("Background task was cancelled");
+                return glib::Continue(false);
+            }
+            progress_dialog.close();
+
+            let result = result.and_then(|value| finish.take().map_or(Ok(()), |finish| finish(&app, value)));
+            if let Err(err) = result {
+                error!("{heading} failed: {err}");
+                if let Some(window) = app.active_window() {
+                    dialogs::run(app, window, err, dialogs::basic_error);
+                }
+            }
+            glib::Continue(false)
+        }));
+    }
+
+    fn choose_save_file<F>(&self, title: &str, filter: gtk::FileFilter, file_name: &str, accept: F)
+        where F: Fn(Application, gio::File) + 'static
+    {
         let window = self.active_window().unwrap();
 
         let save_dialog = gtk::FileChooserNative::builder()
             .transient_for(&window)
             .modal(true)
-            .title("Save As")
+            .title(title)
             .action(gtk::FileChooserAction::Save)
             .accept_label("Save")
-            .filter(&Project::file_filter())
+            .filter(&filter)
             .cancel_label("Cancel")
             .build();
         
-        save_dialog.set_current_name("new-project.lrsproj");
+        save_dialog.set_current_name(file_name);
         save_dialog.connect_response({
             let file_chooser = RefCell::new(Some(save_dialog.clone()));
             glib::clone!(@weak self as app => move |_, response| {
@@ -472,8 +1690,7 @@ impl Application {
                         if !file.query_exists(gio::Cancellable::NONE) {
                             file.create(gio::FileCreateFlags::NONE, gio::Cancellable::NONE).unwrap_or_die();
                         }
-                        app.imp().set_file(file);
-                        app.imp().save(then).unwrap_or_die();
+                        accept(app.clone(), file);
                     }
                 } else {
                     warn!("got file chooser response more than once");
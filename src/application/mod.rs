@@ -8,9 +8,9 @@ pub mod selection;
 use action::*;
 use std::cell::RefCell;
 use adw::traits::MessageDialogExt;
-use gtk::{prelude::*, subclass::prelude::*, gio, glib};
-use selection::SelectionField;
-use crate::{config, ui::dialogs, application::clipboard::Clipboard};
+use gtk::{prelude::*, subclass::prelude::*, gio, glib, gdk};
+use selection::{SelectionField, Selection};
+use crate::{config, project::Project, preferences::CloseBehavior, ui::dialogs, application::clipboard::Clipboard, renderer::{CairoRenderer, Renderer, DEFAULT_SCALE}};
 
 glib::wrapper! {
     pub struct Application(ObjectSubclass<template::ApplicationTemplate>)
@@ -35,21 +35,48 @@ impl Application {
         ])
     }
 
+    // replaces the empty project with the one at `path` before the window is created
+    pub fn load_project(&self, path: &std::path::Path) -> Result<(), String> {
+        let file = gio::File::for_path(path);
+        let project = Project::load_from(&file)?;
+        *self.imp().project().lock() = project;
+        self.imp().set_file(file);
+        Ok(())
+    }
+
     pub fn new_action(&self, action: Action) {
         self.imp().action_stack().borrow_mut().add(self, action);
+        self.edited();
+    }
+
+    // groups `actions` into one undo step called `name`
+    pub fn new_transaction(&self, name: &str, actions: Vec<Action>) {
+        if !actions.is_empty() {
+            self.new_action(Action::Transaction(name.to_string(), actions));
+        }
     }
 
     pub fn undo_action(&self) {
         self.imp().action_stack().borrow_mut().undo(self);
+        self.edited();
     }
 
     pub fn redo_action(&self) {
         self.imp().action_stack().borrow_mut().redo(self);
+        self.edited();
+    }
+
+    // keeps the net list and highlighted problems in step with edits
+    fn edited(&self) {
+        if let Some(window) = self.imp().window().borrow().as_ref() {
+            window.panel().update_nets();
+        }
+        self.check_problems();
     }
 
     pub fn apply_clipboard(&self, clipboard: Clipboard) {
         match clipboard {
-            Clipboard::Blocks(..) => {
+            Clipboard::Blocks(..) | Clipboard::Module(..) => {
                 let position = self.imp()
                     .current_circuit_view()
                     .map(|view| view.mouse_world_position())
@@ -61,7 +88,6 @@ impl Application {
                     Err(err) => dialogs::run(self.to_owned(), self.active_window().unwrap(), err, dialogs::basic_error)
                 }
             }
-            Clipboard::Module(_) => todo!(),
             Clipboard::Empty => {},
         }
     }
@@ -83,11 +109,32 @@ impl Application {
     pub fn cut_clipboard(&self, clipboard: Clipboard) {
         match clipboard {
             Clipboard::Blocks(blocks, connections) => self.new_action(Action::DeleteSelection(self.imp().current_plot().unwrap(), blocks, connections, vec![])),
-            Clipboard::Module(_) => todo!(),
+            Clipboard::Module(module, _) => self.imp().delete_module(module.name()),
             Clipboard::Empty => {}
         }
     }
 
+    // puts a module and the custom modules it uses on the clipboard, to be pasted as new modules with an instance placed
+    pub fn copy_module(&self, module_name: &String) {
+        let clipboard = {
+            let project = self.imp().project().lock();
+            let Some(module) = project.module(module_name).cloned() else {
+                return;
+            };
+
+            let mut dependencies = std::collections::HashMap::new();
+            project.collect_dependencies(module_name, &mut dependencies);
+            let mut dependencies = dependencies.into_values().collect::<Vec<_>>();
+            dependencies.sort_by(|a, b| a.name().cmp(b.name()));
+            Clipboard::Module(Box::new(module), dependencies)
+        };
+
+        match clipboard.serialize() {
+            Ok(serialized) => RootExt::display(&self.active_window().unwrap()).clipboard().set_text(&serialized),
+            Err(err) => warn!("Error serializing clipboard: {err}")
+        }
+    }
+
     pub fn copy_clipboard(&self, cut: bool) {
         let display = RootExt::display(&self.active_window().unwrap());
         let clipboard = self.imp().generate_clipboard();
@@ -103,8 +150,51 @@ impl Application {
         }
     }
 
+    // puts a picture of the selection, or of the whole plot if nothing is selected, on the clipboard
+    pub fn copy_image(&self, transparent: bool) {
+        let Some(plot_provider) = self.imp().current_plot() else {
+            return;
+        };
+
+        let wire_style = self.imp().preferences().borrow().wire_style();
+        let net_colors = self.imp().project().lock().net_colors();
+        let value_labels = self.value_labels();
+
+        let image = plot_provider.with(|plot| {
+            let mut plot = if plot.selected().is_empty() { plot.clone() } else { plot.selection_only() };
+            plot.unhighlight();
+            plot.set_selection(Selection::None);
+
+            let bounds = plot.content_bounds()?;
+            let mut renderer = CairoRenderer::new();
+            renderer.set_wire_style(wire_style);
+            renderer.set_net_colors(net_colors);
+            renderer.set_value_labels(value_labels);
+            Some(renderer.render_image(&plot, bounds, transparent, DEFAULT_SCALE).map_err(|err| err.to_string()))
+        }).flatten();
+
+        let surface = match image {
+            Some(Ok(surface)) => surface,
+            Some(Err(err)) => {
+                warn!("Error rendering image: {err}");
+                return;
+            }
+            None => return
+        };
+
+        let (width, height, stride) = (surface.width(), surface.height(), surface.stride() as usize);
+        match surface.take_data() {
+            Ok(data) => {
+                let texture = gdk::MemoryTexture::new(width, height, gdk::MemoryFormat::B8g8r8a8Premultiplied, &glib::Bytes::from_owned(data), stride);
+                let display = RootExt::display(&self.active_window().unwrap());
+                display.clipboard().set_texture(&texture);
+            }
+            Err(err) => warn!("Error copying image: {err}")
+        }
+    }
+
     pub fn quit(&self) {
-        self.close_current_file(glib::clone!(@weak self as app => move |response| {
+        let after = glib::clone!(@weak self as app => move |response: &str| {
             match response {
                 "Cancel" => {
                 },
@@ -122,7 +212,14 @@ impl Application {
                 }
                 _ => panic!("unexpected response \"{}\"", response)
             }
-        }));
+        });
+
+        let close_behavior = self.imp().preferences().borrow().close_behavior();
+        match close_behavior {
+            CloseBehavior::Prompt => self.close_current_file(after),
+            CloseBehavior::SaveAndQuit if self.imp().is_dirty() => after("Yes"),
+            CloseBehavior::SaveAndQuit | CloseBehavior::QuitWithoutSaving => after("No")
+        }
     }
 
     pub(self) fn setup_gactions(&self) {
@@ -137,3 +234,31 @@ impl Application {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::Module;
+
+    #[test]
+    fn loading_a_missing_project_fails() {
+        let app = Application::new();
+        let path = std::env::temp_dir().join(format!("logicrs-missing-{}.lrsproj", std::process::id()));
+        assert!(app.load_project(&path).is_err());
+        assert!(app.imp().file().is_none());
+    }
+
+    #[test]
+    fn loading_a_project_replaces_the_empty_one() {
+        let mut project = Project::default();
+        project.add_module(Module::new(String::from("Half Adder"), 2, 2));
+        let path = std::env::temp_dir().join(format!("logicrs-startup-{}.lrsproj", std::process::id()));
+        project.write_to(&gio::File::for_path(&path)).unwrap();
+
+        let app = Application::new();
+        app.load_project(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(app.imp().project().lock().module(&String::from("Half Adder")).is_some());
+        assert_eq!(app.imp().file().and_then(|file| file.path()), Some(path));
+    }
+}
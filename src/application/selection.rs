@@ -1,12 +1,13 @@
 use serde::{Serialize, Deserialize};
 
-use crate::{renderer::{Renderable, COLOR_THEME, vector::Vector2}, simulator::{Plot, Block, BlockID, SegmentID}};
-use std::cmp;
+use crate::{renderer::{Renderable, COLOR_THEME, vector::Vector2}, simulator::{Plot, Block, BlockID, ConnectionID, SegmentID, render_line}};
+use std::{cmp, f64};
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub enum Selectable {
     Block(BlockID),
-    Waypoint(SegmentID)
+    Waypoint(SegmentID),
+    Connection(ConnectionID)
 }
 
 impl Selectable {
@@ -16,12 +17,23 @@ impl Selectable {
             _ => None
         }
     }
+
+    // the connection a selected wire or waypoint belongs to
+    pub fn connection_id(&self) -> Option<ConnectionID> {
+        match self {
+            Self::Waypoint(waypoint) => Some(*waypoint.connection_id()),
+            Self::Connection(connection_id) => Some(*connection_id),
+            _ => None
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub enum ConnectionSource {
     Block(BlockID, u8),
-    Waypoint(SegmentID)
+    Waypoint(SegmentID),
+    // the end of an existing wire at an input pin, being dragged to another one
+    Endpoint(ConnectionID, BlockID, u8)
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -71,16 +83,16 @@ impl Renderable for Selection {
                     .stroke().map(|_| ())
             }
             Self::Connection(_, start, end) => {
-                let offset = Vector2(
-                    Vector2(start.0 + ((end.0 - start.0) as f32 * 0.7) as i32, start.1),
-                    Vector2(end.0 + ((start.0 - end.0) as f32 * 0.7) as i32, end.1),
-                );
+                render_line(false, *start, *end, renderer)?;
 
-                renderer.set_line_width(4.)
-                    .set_color(unsafe { &COLOR_THEME.disabled_bg_color })    
-                    .move_to(*start)
-                    .curve_to(offset.0, offset.1, *end)
-                    .stroke().map(|_| ())
+                // highlight the pin the connection snapped to
+                if data.get_input_near(*end, 0).is_some() {
+                    renderer.arc(*end, 9., 0., f64::consts::TAU)
+                        .set_line_width(2.)
+                        .set_color(unsafe { &COLOR_THEME.accent_fg_color })
+                        .stroke()?;
+                }
+                Ok(())
             }
             Self::MoveBlock(block) => block.render(renderer, data),
             _ => Ok(())
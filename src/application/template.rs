@@ -3,7 +3,7 @@ use adw::subclass::prelude::*;
 use std::cell::RefCell;
 use crate::{
     ui::{main_window::MainWindow, circuit_view::CircuitView, dialogs},
-    fatal::*, project::*, simulator::*, renderer::Theme,
+    fatal::*, project::*, simulator::*, renderer::Theme, preferences::Preferences,
 };
 
 use super::{action::*, clipboard::Clipboard, Application, selection::*};
@@ -15,15 +15,40 @@ pub struct ApplicationTemplate {
     simulator: RefCell<Option<Simulator>>,
     file: RefCell<Option<gio::File>>,
     action_stack: RefCell<ActionStack>,
+    preferences: RefCell<Preferences>,
 } 
 
 impl ApplicationTemplate {
     const CSS_RESOURCE: &'static str = "/style/style.css";
 
     fn start_simulation(&self) {
+        self.instance().check_problems();
+        if self.instance().validation_enabled() {
+            let diagnostics = validation::validate(&self.project.lock());
+            if let Some(window) = self.window.borrow().as_ref() {
+                diagnostics.iter().for_each(|diagnostic| window.panel().push_error_at(
+                    diagnostic.to_string(),
+                    diagnostic.location().map(|(plot, block_id)| (plot.to_provider(self.project.clone()), *block_id))
+                ));
+            }
+
+            if diagnostics.iter().any(|diagnostic| diagnostic.is_fatal()) {
+                warn!("simulation was not started due to validation errors");
+                if let Some(window) = self.window.borrow().as_ref() {
+                    window.panel().push_error(String::from("The simulation was not started. Fix the errors above or disable \"Validate Before Simulating\"."));
+                }
+                return;
+            }
+        }
+
         *self.simulator.borrow_mut() = Some(Simulator::new(self.project.clone(), self.window.clone()))
     }
 
+    pub(super) fn restart_simulation(&self) {
+        self.stop_simulation();
+        self.start_simulation();
+    }
+
     fn stop_simulation(&self) {
         if let Some(simulator) = self.simulator.replace(None) {
             simulator.join();
@@ -51,7 +76,14 @@ impl ApplicationTemplate {
 
     pub fn save(&self, then: fn(&Application)) -> Result<(), String> {
         if let Some(file) = self.file.borrow().as_ref() { 
-            let project = self.project.lock().unwrap();
+            if let Some(window) = self.window.borrow().as_ref() {
+                window.panel().store_views();
+            }
+            let mut project = self.project.lock();
+            if let Some(window) = self.window.borrow().as_ref() {
+                project.set_tab_order(window.panel().tab_order());
+            }
+            project.stamp_save_time();
             project.write_to(file)?;
             if let Some(window) = self.window.borrow().as_ref() {
                 window.set_subtitle(&self.file_name());
@@ -69,12 +101,13 @@ impl ApplicationTemplate {
     pub fn set_project(&self, project: Project, file: Option<gio::File>) {
         self.stop_simulation();
 
-        let mut old = self.project.lock().unwrap();
+        let mut old = self.project.lock();
         *old = project;
         drop(old);
         
         self.file.replace(file);
         self.action_stack.borrow_mut().reset();
+        self.instance().sync_project_gactions();
         if let Some(window) = self.window.borrow().as_ref() {
             window.reset_ui(&self.instance());
         }
@@ -92,6 +125,11 @@ impl ApplicationTemplate {
 
     pub fn set_file(&self, file: gio::File) {
         self.file.replace(Some(file));
+        self.instance().sync_project_gactions();
+    }
+
+    pub fn file(&self) -> Option<gio::File> {
+        self.file.borrow().clone()
     }
 
     pub fn reset(&self) {
@@ -142,6 +180,10 @@ impl ApplicationTemplate {
         self.window.borrow().as_ref().unwrap().panel().redo_button().to_owned()
     }
 
+    pub fn preferences(&self) -> &RefCell<Preferences> {
+        &self.preferences
+    }
+
     pub fn action_stack(&self) -> &RefCell<ActionStack> {
         &self.action_stack
     }
@@ -160,34 +202,15 @@ impl ApplicationTemplate {
     }
 
     pub fn delete_module(&self, module_name: &String) {
-        let mut locked = self.project.lock().unwrap();
-        if let Some(module) = locked.module(module_name) {
-            let owned_module = module.to_owned();
-            let remove_dependencies = |plot: &mut Plot| {
-                let delete = plot
-                    .blocks_mut()
-                    .iter()
-                    .filter(|(_, block)| block.module_id() == owned_module.name())
-                    .map(|(id, _)| *id)
-                    .collect::<Vec<BlockID>>();
-
-                delete.iter().for_each(|id| { plot.delete_block(*id); });
-            };
-
-            remove_dependencies(locked.main_plot_mut());
-            locked.modules_mut().iter_mut().for_each(|(_, module)|
-                if let Some(plot) = module.plot_mut() {
-                    remove_dependencies(plot);
-                }
-            );
-        
-            drop(locked);
-            self.instance().new_action(Action::DeleteModule(self.project.clone(), owned_module));
+        let module = self.project.lock().module(module_name).cloned();
+        if let Some(module) = module {
+            // the instances are removed by the action itself so that undoing brings them back
+            self.instance().new_action(Action::DeleteModule(self.project.clone(), module, vec![]));
         }
     }
 
     pub fn edit_module(&self, module_name: String) {
-        let project = self.project.lock().unwrap();
+        let project = self.project.lock();
         if let Some(module) = project.module(&module_name) {
             let module_name = module.name().clone();
             let provider = PlotProvider::Module(self.project.clone(), module_name);
@@ -207,9 +230,12 @@ impl ObjectSubclass for ApplicationTemplate {
 impl ObjectImpl for ApplicationTemplate {
     fn constructed(&self) {
         self.parent_constructed();
+        self.preferences.replace(Preferences::load());
 
         let obj = self.instance();
         obj.setup_gactions();
+        obj.sync_preference_gactions();
+        obj.sync_project_gactions();
     }
 }
 impl ApplicationImpl for ApplicationTemplate {
@@ -228,11 +254,12 @@ impl ApplicationImpl for ApplicationTemplate {
 
         match Project::load_from(file) {
             Ok(data) => {
-                let mut old_data = self.project.lock().unwrap();
+                let mut old_data = self.project.lock();
                 *old_data = data;
                 std::mem::drop(old_data);
 
                 self.file.replace(Some(file.to_owned()));
+                self.instance().sync_project_gactions();
                 self.create_window(&self.instance());
                 self.start_simulation();
             }
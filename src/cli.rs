@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+
+// command line arguments, read before gtk is started
+#[derive(Debug, Default)]
+pub struct Arguments {
+    // the project to open on launch
+    pub project: Option<PathBuf>,
+    // everything meant for gtk, starting with the program name
+    pub gtk_args: Vec<String>
+}
+
+impl Arguments {
+    // the first argument not starting with `-` (or any argument after `--`) is taken as the project
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut arguments = Self::default();
+        let mut positional_only = false;
+
+        for arg in args {
+            if arguments.gtk_args.is_empty() {
+                arguments.gtk_args.push(arg);
+            }
+            else if !positional_only && arg == "--" {
+                positional_only = true;
+            }
+            else if !positional_only && arg.starts_with('-') {
+                arguments.gtk_args.push(arg);
+            }
+            else if arguments.project.is_none() {
+                arguments.project = Some(PathBuf::from(arg));
+            }
+            else {
+                return Err(format!("unexpected argument `{arg}`; only one project can be opened"));
+            }
+        }
+
+        Ok(arguments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Arguments, String> {
+        Arguments::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn the_first_positional_argument_is_the_project() {
+        let arguments = parse(&["logicrs", "--gapplication-service", "adder.lrsproj"]).unwrap();
+        assert_eq!(arguments.project, Some(PathBuf::from("adder.lrsproj")));
+        assert_eq!(arguments.gtk_args, vec!["logicrs", "--gapplication-service"]);
+    }
+
+    #[test]
+    fn arguments_after_a_double_dash_are_positional() {
+        let arguments = parse(&["logicrs", "--", "-odd name.lrsproj"]).unwrap();
+        assert_eq!(arguments.project, Some(PathBuf::from("-odd name.lrsproj")));
+        assert_eq!(arguments.gtk_args, vec!["logicrs"]);
+    }
+
+    #[test]
+    fn only_one_project_can_be_given() {
+        assert!(parse(&["logicrs", "a.lrsproj", "b.lrsproj"]).is_err());
+        assert_eq!(parse(&["logicrs"]).unwrap().project, None);
+    }
+}
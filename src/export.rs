@@ -1,9 +1,16 @@
-use crate::{simulator::Module, project::Project, FileExtension, application::Application};
+use crate::{simulator::{Module, Plot, PinNames}, project::{Project, local_path}, FileExtension, application::{Application, selection::Selection, editor::WireStyle}, renderer::{CairoRenderer, Renderer, vector::Vector2}};
 
 use serde::{Serialize, Deserialize};
-use gtk::{gio, prelude::FileExt, subclass::prelude::ObjectSubclassIsExt};
+use gtk::{gio, glib, gdk, prelude::FileExt, subclass::prelude::ObjectSubclassIsExt};
 use std::{fs::{OpenOptions, File}, io::{Write, BufReader}, collections::HashMap};
 
+// `write` may stop short without an error, leaving a truncated file
+fn write_fully(mut writer: impl Write, data: &[u8]) -> Result<(), String> {
+    writer.write_all(data)
+        .and_then(|_| writer.flush())
+        .map_err(|err| err.to_string())
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ModuleFile {
     main_name: String,
@@ -35,42 +42,81 @@ impl ModuleFile {
         })
     }
 
+    // bundles the chosen modules of another project together with everything they depend on
+    pub fn from_project(project: &Project, mod_names: &[String]) -> Self {
+        let mut modules = HashMap::new();
+        for mod_name in mod_names {
+            if let Some(module) = project.module(mod_name).filter(|module| !module.builtin()) {
+                modules.insert(mod_name.clone(), module.clone());
+                project.collect_dependencies(mod_name, &mut modules);
+            }
+        }
+
+        Self {
+            main_name: mod_names.join(", "),
+            modules
+        }
+    }
+
+    pub fn module_names(&self) -> Vec<&String> {
+        let mut names = self.modules.keys().collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    // renames a bundled module and every block referring to it
+    pub fn rename_module(&mut self, from: &String, to: String) {
+        if let Some(mut module) = self.modules.remove(from) {
+            module.set_name(to.clone());
+            self.modules.insert(to.clone(), module);
+        }
+
+        self.modules.values_mut()
+            .filter_map(|module| module.plot_mut())
+            .flat_map(|plot| plot.blocks_mut().values_mut())
+            .filter(|block| block.module_id() == from)
+            .for_each(|block| block.set_module_id(to.clone()));
+    }
+
     pub fn export(&self, file: &gio::File) -> Result<(), String> {
-        info!("Exporting to `{}`...", file.path().unwrap().to_str().unwrap());
+        let path = local_path(file)?;
+        info!("Exporting to `{}`...", path.display());
         let mut f = OpenOptions::new()
             .write(true)
             .truncate(true)
-            .open(file.path().unwrap())
+            .open(&path)
             .map_err(|err| err.to_string())?;
 
         let serialized = serde_json::to_string(self)
             .map_err(|err| err.to_string())?;
-        let bytes_written = f.write(serialized.as_bytes())
-            .map_err(|err| err.to_string())?;
+        write_fully(&mut f, serialized.as_bytes())?;
 
-        info!("Wrote {bytes_written} bytes to `{}` successfully", file.path().unwrap().to_str().unwrap());
+        info!("Wrote {} bytes to `{}` successfully", serialized.len(), path.display());
         Ok(())
     }
 
     pub fn import(file: &gio::File) -> Result<Self, String> {
-        let f = File::open(file.path().unwrap())
+        let path = local_path(file)?;
+        let f = File::open(&path)
             .map_err(|err| err.to_string())?;
         let mod_file: Self = serde_json::from_reader(BufReader::new(f))
             .map_err(|err| err.to_string())?;
 
-        info!("Imported module `{}` from file `{}`", mod_file.main_name, file.path().unwrap().to_str().unwrap());
+        info!("Imported module `{}` from file `{}`", mod_file.main_name, path.display());
         Ok(mod_file)
     }
 
-    fn check_compat(&self, project: &mut Project) -> Vec<String> {
-        self.modules.keys()
+    pub fn check_compat(&self, project: &Project) -> Vec<String> {
+        let mut conflicting = self.modules.keys()
             .cloned()
             .filter(|name| project.module(name).is_some())
-            .collect::<Vec<_>>()
+            .collect::<Vec<_>>();
+        conflicting.sort();
+        conflicting
     }
 
     pub fn merge(self, app: &Application) -> Result<(), String> {
-        let project = &mut app.imp().project().lock().unwrap();
+        let project = &mut app.imp().project().lock();
         let window = app.imp().window().borrow();
         let window = window.as_ref().unwrap();
 
@@ -92,3 +138,451 @@ impl ModuleFile {
         Err(message)
     }
 }
+
+// a hand-picked set of modules with everything they depend on, shared without a whole project
+#[derive(Serialize, Deserialize)]
+pub struct LibraryFile {
+    name: String,
+    modules: HashMap<String, Module>
+}
+
+impl FileExtension for LibraryFile {
+    const FILE_EXTENSION: &'static str = "lrslib";
+    const FILE_PATTERN: &'static str = "*.lrslib";
+
+    fn file_filter() -> gtk::FileFilter {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some("LogicRs library files"));
+        filter.add_pattern(Self::FILE_PATTERN);
+        filter
+    }
+}
+
+impl LibraryFile {
+    pub fn from_project(project: &Project, name: String, mod_names: &[String]) -> Self {
+        Self {
+            name,
+            modules: ModuleFile::from_project(project, mod_names).modules
+        }
+    }
+
+    pub fn export(&self, file: &gio::File) -> Result<(), String> {
+        let path = local_path(file)?;
+        info!("Exporting library `{}` to `{}`...", self.name, path.display());
+        let serialized = serde_json::to_string(self)
+            .map_err(|err| err.to_string())?;
+        std::fs::write(&path, serialized)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn import(file: &gio::File) -> Result<Self, String> {
+        let path = local_path(file)?;
+        let f = File::open(&path)
+            .map_err(|err| err.to_string())?;
+        let library: Self = serde_json::from_reader(BufReader::new(f))
+            .map_err(|err| err.to_string())?;
+
+        if library.modules.values().any(|module| module.builtin()) {
+            return Err(format!("Library `{}` contains built-in modules.", library.name));
+        }
+
+        info!("Imported library `{}` with {} modules from file `{}`", library.name, library.modules.len(), path.display());
+        Ok(library)
+    }
+
+    // imports go through the same conflict handling as single modules
+    pub fn into_module_file(self) -> ModuleFile {
+        ModuleFile {
+            main_name: self.name,
+            modules: self.modules
+        }
+    }
+}
+
+pub struct TruthTable {
+    num_inputs: u8,
+    num_outputs: u8,
+    pin_names: PinNames,
+    rows: Vec<(u128, u128)>
+}
+
+impl FileExtension for TruthTable {
+    const FILE_EXTENSION: &'static str = "csv";
+    const FILE_PATTERN: &'static str = "*.csv";
+
+    fn file_filter() -> gtk::FileFilter {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some("CSV files"));
+        filter.add_pattern(Self::FILE_PATTERN);
+        filter
+    }
+}
+
+impl TruthTable {
+    pub fn from_module(project: &Project, mod_name: &String, max_rows: Option<u128>) -> Result<Self, String> {
+        let rows = project.truth_table(mod_name, max_rows)?;
+        let module = &project.modules()[mod_name];
+        Ok(Self {
+            num_inputs: module.get_num_inputs(),
+            num_outputs: module.get_num_outputs(),
+            pin_names: module.pin_names(),
+            rows
+        })
+    }
+
+    pub fn num_inputs(&self) -> u8 {
+        self.num_inputs
+    }
+
+    pub fn num_outputs(&self) -> u8 {
+        self.num_outputs
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    // columns are numbered like in the csv: all inputs first, then all outputs
+    pub fn cell(&self, row: usize, column: u8) -> bool {
+        let (inputs, outputs) = self.rows[row];
+        if column < self.num_inputs {
+            (inputs >> column) & 1 != 0
+        }
+        else {
+            (outputs >> (column - self.num_inputs)) & 1 != 0
+        }
+    }
+
+    pub fn column_name(&self, column: u8) -> String {
+        if column < self.num_inputs { self.input_name(column) } else { self.output_name(column - self.num_inputs) }
+    }
+
+    // the pin's assigned name, or `in<i>`/`out<i>` for unnamed pins
+    fn input_name(&self, i: u8) -> String {
+        self.pin_names.0.get(i as usize).cloned().flatten().unwrap_or_else(|| format!("in{i}"))
+    }
+
+    fn output_name(&self, i: u8) -> String {
+        self.pin_names.1.get(i as usize).cloned().flatten().unwrap_or_else(|| format!("out{i}"))
+    }
+
+    // one column per pin; column `in<i>` (or the pin's name) holds bit i of the input state
+    pub fn to_csv(&self) -> String {
+        let header = (0..self.num_inputs).map(|i| self.input_name(i))
+            .chain((0..self.num_outputs).map(|i| self.output_name(i)))
+            .map(|name| if name.contains([',', '"', '\n']) { format!("\"{}\"", name.replace('"', "\"\"")) } else { name })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let mut csv = header + "\n";
+        for (inputs, outputs) in self.rows.iter() {
+            let row = (0..self.num_inputs).map(|i| (inputs >> i) & 1)
+                .chain((0..self.num_outputs).map(|i| (outputs >> i) & 1))
+                .map(|bit| bit.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            csv.push_str(&row);
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn export(&self, file: &gio::File) -> Result<(), String> {
+        let path = local_path(file)?;
+        info!("Exporting truth table to `{}`...", path.display());
+        let mut f = OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(&path)
+            .map_err(|err| err.to_string())?;
+
+        let csv = self.to_csv();
+        write_fully(&mut f, csv.as_bytes())?;
+
+        info!("Wrote {} bytes to `{}` successfully", csv.len(), path.display());
+        Ok(())
+    }
+}
+
+// the main circuit as a standalone web page that can be panned and zoomed in any browser
+pub struct HtmlViewer {
+    title: String,
+    width: i32,
+    height: i32,
+    image: Vec<u8>
+}
+
+impl FileExtension for HtmlViewer {
+    const FILE_EXTENSION: &'static str = "html";
+    const FILE_PATTERN: &'static str = "*.html";
+
+    fn file_filter() -> gtk::FileFilter {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some("HTML files"));
+        filter.add_pattern(Self::FILE_PATTERN);
+        filter
+    }
+}
+
+impl HtmlViewer {
+    // the circuit is rendered at twice its size so it stays sharp when zoomed in
+    const IMAGE_SCALE: f64 = 2.;
+
+    const TEMPLATE: &'static str = include_str!("../content/viewer.html");
+
+    // does all of the drawing and encoding, so it can be run away from the main thread
+    pub fn from_plot(mut plot: Plot, title: String, wire_style: WireStyle, net_colors: bool, value_labels: bool) -> Result<Self, String> {
+        plot.unhighlight();
+        plot.set_selection(Selection::None);
+
+        let bounds = plot.content_bounds().ok_or_else(|| String::from("The main circuit is empty."))?;
+        let mut renderer = CairoRenderer::new();
+        renderer.set_wire_style(wire_style);
+        renderer.set_net_colors(net_colors);
+        renderer.set_value_labels(value_labels);
+        let surface = renderer.render_image(&plot, bounds, false, Self::IMAGE_SCALE)
+            .map_err(|err| err.to_string())?;
+
+        let (width, height) = (surface.width(), surface.height());
+        let image = gdk::pixbuf_get_from_surface(&surface, 0, 0, width, height)
+            .ok_or_else(|| String::from("Could not read the rendered image."))?
+            .save_to_bufferv("png", &[])
+            .map_err(|err| err.to_string())?;
+
+        Ok(Self {
+            title,
+            width,
+            height,
+            image
+        })
+    }
+
+    pub fn to_html(&self) -> String {
+        let title = self.title
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;");
+
+        Self::TEMPLATE
+            .replace("{{title}}", &title)
+            .replace("{{width}}", &((self.width as f64 / Self::IMAGE_SCALE) as i32).to_string())
+            .replace("{{height}}", &((self.height as f64 / Self::IMAGE_SCALE) as i32).to_string())
+            .replace("{{image}}", &glib::base64_encode(&self.image))
+    }
+
+    pub fn export(&self, file: &gio::File) -> Result<(), String> {
+        let path = local_path(file)?;
+        info!("Exporting HTML viewer to `{}`...", path.display());
+        let mut f = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .map_err(|err| err.to_string())?;
+
+        let html = self.to_html();
+        write_fully(&mut f, html.as_bytes())?;
+
+        info!("Wrote {} bytes to `{}` successfully", html.len(), path.display());
+        Ok(())
+    }
+}
+
+// a picture of the whole plot scaled down to fit into `max_size`, `None` for empty plots
+pub fn thumbnail(plot: &Plot, wire_style: WireStyle, net_colors: bool, lite: bool, max_size: Vector2<f64>) -> Option<gdk::MemoryTexture> {
+    let mut plot = plot.clone();
+    plot.unhighlight();
+    plot.set_selection(Selection::None);
+
+    let (start, end) = plot.content_bounds()?;
+    let size = end - start;
+    let scale = (max_size.0 / size.0.max(1) as f64).min(max_size.1 / size.1.max(1) as f64).clamp(0.05, 1.);
+
+    let mut renderer = CairoRenderer::new();
+    renderer.set_wire_style(wire_style);
+    renderer.set_net_colors(net_colors);
+    renderer.set_lite(lite);
+    let surface = renderer.render_image(&plot, (start, end), false, scale)
+        .map_err(|err| warn!("Error rendering thumbnail: {err}"))
+        .ok()?;
+
+    let (width, height, stride) = (surface.width(), surface.height(), surface.stride() as usize);
+    let data = surface.take_data().ok()?;
+    Some(gdk::MemoryTexture::new(width, height, gdk::MemoryFormat::B8g8r8a8Premultiplied, &glib::Bytes::from_owned(data), stride))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // accepts at most `limit` bytes per call, then none at all once `capacity` bytes are written
+    struct ShortWriter {
+        data: Vec<u8>,
+        limit: usize,
+        capacity: usize
+    }
+
+    impl Write for ShortWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(self.limit).min(self.capacity - self.data.len());
+            self.data.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn short_writes_are_continued() {
+        let mut writer = ShortWriter { data: Vec::new(), limit: 3, capacity: usize::MAX };
+        let data = "in0,in1,out0\n0,0,0\n".as_bytes();
+        assert_eq!(write_fully(&mut writer, data), Ok(()));
+        assert_eq!(writer.data, data);
+    }
+
+    #[test]
+    fn a_writer_that_stops_accepting_data_is_an_error() {
+        let mut writer = ShortWriter { data: Vec::new(), limit: 3, capacity: 5 };
+        assert!(write_fully(&mut writer, "in0,in1,out0\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn files_without_a_local_path_are_refused() {
+        let file = gio::File::for_uri("http://example.com/circuit.lrsmod");
+        let module_file = ModuleFile::from_project(&Project::default(), &[]);
+        assert_eq!(module_file.export(&file), Err(String::from("file has no local path")));
+        assert!(ModuleFile::import(&file).is_err());
+        assert!(LibraryFile::import(&file).is_err());
+    }
+
+    #[test]
+    fn a_library_round_trips_a_module_with_its_dependency() {
+        let mut project = Project::default();
+        let xor = project.subcircuit_module(&String::from("Xor")).unwrap();
+        let xor_name = xor.name().clone();
+        project.add_module(xor);
+
+        let mut outer = Module::new(String::from("Outer"), 2, 1);
+        let block = crate::simulator::Block::new(&&project.modules()[&xor_name], Vector2(200, 0), None);
+        outer.plot_mut().unwrap().add_block(block);
+        project.add_module(outer);
+
+        let path = std::env::temp_dir().join(format!("logicrs-library-{}.lrslib", std::process::id()));
+        let file = gio::File::for_path(&path);
+        LibraryFile::from_project(&project, String::from("Gates"), &[String::from("Outer")]).export(&file).unwrap();
+        let library = LibraryFile::import(&file);
+        let _ = std::fs::remove_file(&path);
+
+        let mod_file = library.unwrap().into_module_file();
+        assert_eq!(mod_file.module_names(), vec![&String::from("Outer"), &xor_name]);
+
+        let mut fresh = Project::default();
+        assert!(mod_file.check_compat(&fresh).is_empty());
+        mod_file.modules.into_values().for_each(|module| fresh.add_existing_module(module));
+        assert!(fresh.modules()[&xor_name].same_contents(&project.modules()[&xor_name]));
+        assert!(fresh.modules()["Outer"].plot().unwrap().blocks().values().any(|block| block.module_id() == &xor_name));
+    }
+
+    #[test]
+    fn importing_modules_from_another_project_brings_their_dependencies() {
+        let mut source = Project::default();
+        let xor = source.subcircuit_module(&String::from("Xor")).unwrap();
+        let xor_name = xor.name().clone();
+        source.add_module(xor);
+
+        let mut outer = Module::new(String::from("Outer"), 2, 1);
+        let block = crate::simulator::Block::new(&&source.modules()[&xor_name], Vector2(200, 0), None);
+        outer.plot_mut().unwrap().add_block(block);
+        source.add_module(outer);
+
+        let mod_file = ModuleFile::from_project(&source, &[String::from("Outer")]);
+        assert_eq!(mod_file.module_names(), vec![&String::from("Outer"), &xor_name]);
+
+        let mut fresh = Project::default();
+        assert!(mod_file.check_compat(&fresh).is_empty());
+
+        // a project with its own module of the dependency's name cannot take both
+        let own = fresh.subcircuit_module(&String::from("Xor")).unwrap();
+        assert_eq!(own.name(), &xor_name);
+        fresh.add_module(own);
+        assert_eq!(mod_file.check_compat(&fresh), vec![xor_name]);
+    }
+
+    #[test]
+    fn truth_table_csv_of_xor_circuit() {
+        let mut project = Project::default();
+        let module = project.subcircuit_module(&String::from("Xor")).unwrap();
+        let name = module.name().clone();
+        project.add_module(module);
+
+        let table = TruthTable::from_module(&project, &name, None).unwrap();
+        assert_eq!(table.num_rows(), 4);
+        assert_eq!(table.to_csv(), "in0,in1,out0\n0,0,0\n1,0,1\n0,1,1\n1,1,0\n");
+    }
+
+    #[test]
+    fn truth_table_csv_of_half_adder() {
+        use crate::simulator::{Block, Connection, builtin::BUILTINS};
+
+        let mut project = Project::default();
+        let name = String::from("Half Adder");
+        project.add_module(Module::new(name.clone(), 2, 2));
+
+        let module = project.module_mut(&name).unwrap();
+        let (input_block, output_block) = module.io_blocks().unwrap();
+        let (sum, carry) = (Block::new(&BUILTINS["Xor"].module(), Vector2(200, 50), None), Block::new(&BUILTINS["And"].module(), Vector2(200, 150), None));
+        let connections = [
+            Connection::new_basic(input_block, 0, sum.id(), 0),
+            Connection::new_basic(input_block, 1, sum.id(), 1),
+            Connection::new_basic(input_block, 0, carry.id(), 0),
+            Connection::new_basic(input_block, 1, carry.id(), 1),
+            Connection::new_basic(sum.id(), 0, output_block, 0),
+            Connection::new_basic(carry.id(), 0, output_block, 1)
+        ];
+        let plot = module.plot_mut().unwrap();
+        plot.add_block(sum);
+        plot.add_block(carry);
+        connections.into_iter().for_each(|connection| plot.add_connection(connection));
+
+        // the first output is the sum, the second one the carry
+        let table = TruthTable::from_module(&project, &name, None).unwrap();
+        assert_eq!(table.num_rows(), 4);
+        assert_eq!(table.to_csv(), "in0,in1,out0,out1\n0,0,0,0\n1,0,1,0\n0,1,1,0\n1,1,0,1\n");
+        let column = |column: u8| (0..table.num_rows()).map(|row| table.cell(row, column)).collect::<Vec<_>>();
+        assert_eq!(column(2), vec![false, true, true, false]);
+        assert_eq!(column(3), vec![false, false, false, true]);
+    }
+
+    #[test]
+    fn named_pins_are_shown_on_instances_and_exported() {
+        let mut project = Project::default();
+        let module = project.subcircuit_module(&String::from("Xor")).unwrap();
+        let name = module.name().clone();
+        project.add_module(module);
+        let instance = crate::simulator::Block::new(&&project.modules()[&name], Vector2(0, 0), None);
+        let instance_id = instance.id();
+        project.main_plot_mut().add_block(instance);
+
+        let names = (vec![Some(String::from("a")), Some(String::from("b"))], vec![Some(String::from("y"))]);
+        let previous = project.set_pin_names(&name, names.clone()).unwrap();
+        assert_eq!(previous, (vec![None, None], vec![None]));
+        assert_eq!(project.main_plot().get_block(instance_id).unwrap().pin_names(), &names);
+
+        let table = TruthTable::from_module(&project, &name, None).unwrap();
+        assert!(table.to_csv().starts_with("a,b,y\n"));
+    }
+
+    #[test]
+    fn truth_table_csv_quotes_pin_names() {
+        let table = TruthTable {
+            num_inputs: 1,
+            num_outputs: 1,
+            pin_names: (vec![Some(String::from("a, b"))], vec![None]),
+            rows: vec![(0, 1), (1, 0)]
+        };
+        assert_eq!(table.to_csv(), "\"a, b\",out0\n0,1\n1,0\n");
+    }
+}
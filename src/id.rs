@@ -1,5 +1,7 @@
 use std::hash::Hasher;
 use std::collections::hash_map::DefaultHasher;
+#[cfg(not(test))]
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 use serde::{Serialize, Deserialize};
 
@@ -8,17 +10,107 @@ use serde::{Serialize, Deserialize};
 #[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Id(u64);
 
+// seeded allocation: ids are derived from the seed and a counter instead of a random uuid
+#[cfg(not(test))]
+static DETERMINISTIC: AtomicBool = AtomicBool::new(false);
+#[cfg(not(test))]
+static SEED: AtomicU64 = AtomicU64::new(0);
+#[cfg(not(test))]
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// tests run on several threads at once, so each thread has a seed and counter of its own
+// to keep its sequence reproducible and leave the other tests on random ids
+#[cfg(test)]
+thread_local! {
+    static SEEDED: std::cell::Cell<Option<(u64, u64)>> = std::cell::Cell::new(None);
+}
+
+// the seed and the next index of the sequence, if ids are seeded
+#[cfg(not(test))]
+fn next_seeded() -> Option<(u64, u64)> {
+    DETERMINISTIC.load(Ordering::Relaxed).then(|| (SEED.load(Ordering::Relaxed), COUNTER.fetch_add(1, Ordering::Relaxed)))
+}
+
+#[cfg(test)]
+fn next_seeded() -> Option<(u64, u64)> {
+    SEEDED.with(|seeded| seeded.get().map(|(seed, index)| {
+        seeded.set(Some((seed, index + 1)));
+        (seed, index)
+    }))
+}
+
+#[cfg(not(test))]
+fn start_sequence(seed: u64) {
+    SEED.store(seed, Ordering::Relaxed);
+    COUNTER.store(0, Ordering::Relaxed);
+    DETERMINISTIC.store(true, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+fn start_sequence(seed: u64) {
+    SEEDED.with(|seeded| seeded.set(Some((seed, 0))));
+}
+
+#[cfg(not(test))]
+fn seeded() -> bool {
+    DETERMINISTIC.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+fn seeded() -> bool {
+    SEEDED.with(|seeded| seeded.get().is_some())
+}
+
 impl Id {
+    // environment variable that turns on seeded allocation at startup
+    pub const SEED_VARIABLE: &'static str = "LOGICRS_ID_SEED";
+
     #[inline]
     pub fn new() -> Self {
         #[allow(deprecated)]
         let mut hasher = DefaultHasher::default();
-        hasher.write(&uuid::Uuid::new_v4().into_bytes());
+        if let Some((seed, index)) = next_seeded() {
+            hasher.write_u64(seed);
+            hasher.write_u64(index);
+        }
+        else {
+            hasher.write(&uuid::Uuid::new_v4().into_bytes());
+        }
         Self(hasher.finish())
     }
 
+    // from now on the same sequence of operations allocates the same ids; restarts the sequence
+    pub fn seed(seed: u64) {
+        start_sequence(seed);
+    }
+
+    pub fn deterministic() -> bool {
+        seeded()
+    }
+
+    // reads `LOGICRS_ID_SEED`; a value that isn't a number is reported and ignored
+    pub fn seed_from_env() {
+        let Ok(value) = std::env::var(Self::SEED_VARIABLE) else {
+            return;
+        };
+
+        match value.trim().parse::<u64>() {
+            Ok(seed) => {
+                info!("Allocating ids deterministically with seed {seed}");
+                Self::seed(seed)
+            }
+            Err(err) => warn!("Ignoring `{}={value}`: {err}", Self::SEED_VARIABLE)
+        }
+    }
+
     #[inline]
     pub fn empty() -> Self {
         Self(0)
     }
+
+    // sequential ids, starting after `empty()`
+    #[inline]
+    pub fn from_index(index: usize) -> Self {
+        Self(index as u64 + 1)
+    }
 }
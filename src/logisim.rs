@@ -0,0 +1,484 @@
+use crate::{simulator::{*, builtin::BUILTINS}, project::Project, export::ModuleFile, renderer::vector::Vector2, FileExtension};
+
+use gtk::{gio, prelude::FileExt};
+use std::{fs, collections::{HashMap, BTreeMap}};
+
+type Point = (i32, i32);
+
+// a circuit file from Logisim, converted into one custom module per circuit
+pub struct LogisimFile {
+    module_file: ModuleFile,
+    skipped: Vec<String>
+}
+
+impl FileExtension for LogisimFile {
+    const FILE_EXTENSION: &'static str = "circ";
+    const FILE_PATTERN: &'static str = "*.circ";
+
+    fn file_filter() -> gtk::FileFilter {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some("Logisim circuit files"));
+        filter.add_pattern(Self::FILE_PATTERN);
+        filter
+    }
+}
+
+impl LogisimFile {
+    // Logisim's grid is much finer than the size of LogicRs's blocks
+    const SCALE: i32 = 3;
+
+    pub fn import(file: &gio::File) -> Result<Self, String> {
+        let path = crate::project::local_path(file)?;
+        let contents = fs::read_to_string(&path).map_err(|err| err.to_string())?;
+        let logisim_file = Self::from_xml(&contents)?;
+        info!("Imported {} circuit(s) from Logisim file `{}`, skipping {} component(s)", logisim_file.module_file.module_names().len(), path.display(), logisim_file.skipped.len());
+        Ok(logisim_file)
+    }
+
+    fn from_xml(contents: &str) -> Result<Self, String> {
+        let root = xml::parse(contents)?;
+        if root.name != "project" {
+            return Err(String::from("The file is not a Logisim project."));
+        }
+
+        // Logisim 2.x gates default to five inputs, later versions to two
+        let default_inputs = if root.attribute("source").map_or(false, |source| source.starts_with("2.")) { 5 } else { 2 };
+
+        let mut project = Project::default();
+        let mut names = Vec::new();
+        let mut skipped = Vec::new();
+        for circuit in root.children("circuit") {
+            let name = circuit.attribute("name").unwrap_or("Circuit").to_string();
+            import_circuit(&mut project, &name, circuit, default_inputs, &mut skipped)?;
+            names.push(name);
+        }
+
+        if names.is_empty() {
+            return Err(String::from("The file does not contain any circuits."));
+        }
+
+        Ok(Self {
+            module_file: ModuleFile::from_project(&project, &names),
+            skipped
+        })
+    }
+
+    // the modules to merge, and a description of everything that could not be converted
+    pub fn into_parts(self) -> (ModuleFile, Vec<String>) {
+        (self.module_file, self.skipped)
+    }
+}
+
+// what a Logisim component becomes
+enum Part {
+    // a builtin module, with the points its input pins and its output pin attach to
+    Block(&'static str, Vec<Point>, Option<Point>),
+    InputPin(Option<String>),
+    OutputPin(Option<String>),
+    Tunnel(String)
+}
+
+// a place where wires attach to a block in the new module
+struct Pin {
+    point: Point,
+    block: BlockID,
+    port: u8,
+    drives: bool,
+    label: Option<String>
+}
+
+// components that cannot be converted are left out rather than replaced by a placeholder block: a block needs a module,
+// and a made-up one would end up in the user's project; `skipped` lists them for the import to report instead
+fn import_circuit(project: &mut Project, name: &String, circuit: &xml::Element, default_inputs: u32, skipped: &mut Vec<String>) -> Result<(), String> {
+    let mut parts = Vec::new();
+    for component in circuit.children("comp") {
+        let kind = component.attribute("name").unwrap_or_default();
+        let Some(location) = component.attribute("loc").and_then(parse_point) else {
+            skipped.push(format!("A \"{kind}\" component without a location in \"{name}\""));
+            continue;
+        };
+
+        match classify(component, kind, location, default_inputs) {
+            Ok(Some(part)) => parts.push((location, part)),
+            Ok(None) => (),
+            Err(reason) => skipped.push(format!("{reason} at ({}, {}) in \"{name}\"", location.0, location.1))
+        }
+    }
+
+    // ports are numbered from top to bottom
+    let ordered = |output: bool| {
+        let mut points = parts.iter()
+            .filter(|(_, part)| matches!(part, Part::OutputPin(_)) == output && matches!(part, Part::InputPin(_) | Part::OutputPin(_)))
+            .map(|(location, _)| *location)
+            .collect::<Vec<_>>();
+        points.sort_by_key(|point| (point.1, point.0));
+        points
+    };
+    let (inputs, outputs) = (ordered(false), ordered(true));
+    if inputs.len().max(outputs.len()) > Block::MAX_CONNECTIONS as usize {
+        return Err(format!("\"{name}\" has more than {} input or output pins.", Block::MAX_CONNECTIONS));
+    }
+
+    project.add_module(Module::new(name.clone(), inputs.len() as u8, outputs.len() as u8));
+    let module = project.module_mut(name).unwrap();
+    let (input_block, output_block) = module.io_blocks().unwrap();
+    let plot = module.plot_mut().unwrap();
+
+    let mut pins = Vec::new();
+    let mut tunnels: HashMap<String, Vec<Point>> = HashMap::new();
+    for (location, part) in parts.iter() {
+        match part {
+            Part::Block(module_name, input_points, output_point) => {
+                let mut block = Block::new(&BUILTINS[module_name].module(), Vector2(0, 0), None);
+                let size = block.size();
+                block.set_position(Vector2(location.0 * LogisimFile::SCALE - size.0, location.1 * LogisimFile::SCALE - size.1 / 2));
+
+                pins.extend(input_points.iter().enumerate().map(|(port, point)| Pin { point: *point, block: block.id(), port: port as u8, drives: false, label: None }));
+                pins.extend(output_point.iter().map(|point| Pin { point: *point, block: block.id(), port: 0, drives: true, label: None }));
+                plot.add_block(block);
+            }
+            Part::InputPin(label) => if let Some(port) = inputs.iter().position(|point| point == location) {
+                pins.push(Pin { point: *location, block: input_block, port: port as u8, drives: true, label: label.clone() });
+            }
+            Part::OutputPin(label) => if let Some(port) = outputs.iter().position(|point| point == location) {
+                pins.push(Pin { point: *location, block: output_block, port: port as u8, drives: false, label: label.clone() });
+            }
+            Part::Tunnel(label) => tunnels.entry(label.clone()).or_default().push(*location)
+        }
+    }
+
+    // keep the input and output blocks clear of the imported circuit
+    if let (Some(left), Some(right), Some(top)) = (
+        parts.iter().map(|(location, _)| location.0).min(),
+        parts.iter().map(|(location, _)| location.0).max(),
+        parts.iter().map(|(location, _)| location.1).min()
+    ) {
+        if let Some(block) = plot.get_block_mut(input_block) {
+            block.set_position(Vector2(left * LogisimFile::SCALE - 300, top * LogisimFile::SCALE));
+        }
+        if let Some(block) = plot.get_block_mut(output_block) {
+            block.set_position(Vector2(right * LogisimFile::SCALE + 150, top * LogisimFile::SCALE));
+        }
+    }
+
+    // wires only connect at their ends, or where a pin or another wire's end touches them
+    let mut nets = Nets::default();
+    let wires = circuit.children("wire")
+        .filter_map(|wire| Some((wire.attribute("from").and_then(parse_point)?, wire.attribute("to").and_then(parse_point)?)))
+        .collect::<Vec<_>>();
+    for (from, to) in wires.iter() {
+        nets.union(*from, *to);
+    }
+    let touching = wires.iter().flat_map(|(from, to)| [*from, *to]).chain(pins.iter().map(|pin| pin.point)).collect::<Vec<_>>();
+    for point in touching {
+        if let Some((from, _)) = wires.iter().find(|(from, to)| on_segment(point, *from, *to)) {
+            nets.union(point, *from);
+        }
+    }
+    for points in tunnels.values() {
+        points.windows(2).for_each(|pair| nets.union(pair[0], pair[1]));
+    }
+
+    let mut grouped: BTreeMap<Point, Vec<&Pin>> = BTreeMap::new();
+    for pin in pins.iter() {
+        grouped.entry(nets.find(pin.point)).or_default().push(pin);
+    }
+
+    for (root, pins) in grouped {
+        let mut drivers = pins.iter().filter(|pin| pin.drives);
+        let Some(driver) = drivers.next() else {
+            continue;
+        };
+        if drivers.next().is_some() {
+            skipped.push(format!("A second driver of the wire at ({}, {}) in \"{name}\"", root.0, root.1));
+        }
+
+        for sink in pins.iter().filter(|pin| !pin.drives) {
+            plot.add_connection(Connection::new_basic(driver.block, driver.port, sink.block, sink.port));
+        }
+
+        let label = pins.iter().find_map(|pin| pin.label.clone());
+        let connection_id = plot.get_block(driver.block).and_then(|block| block.connection(Connector::Output(driver.port)));
+        if let Some(label) = label && let Some(connection) = connection_id.and_then(|id| plot.get_connection_mut(&id)) {
+            connection.set_name(Some(label));
+        }
+    }
+
+    Ok(())
+}
+
+fn classify(component: &xml::Element, kind: &str, location: Point, default_inputs: u32) -> Result<Option<Part>, String> {
+    let attribute = |name: &str| component.children("a")
+        .find(|a| a.attribute("name") == Some(name))
+        .and_then(|a| a.attribute("val"));
+    let facing = attribute("facing").unwrap_or("east");
+    // the point `depth` behind the output and `offset` to its side
+    let behind = |depth: i32, offset: i32| match facing {
+        "west" => (location.0 + depth, location.1 + offset),
+        "north" => (location.0 + offset, location.1 + depth),
+        "south" => (location.0 + offset, location.1 - depth),
+        _ => (location.0 - depth, location.1 + offset)
+    };
+
+    if let Some(width) = attribute("width").filter(|width| *width != "1") {
+        return Err(format!("A {width} bit wide \"{kind}\""));
+    }
+
+    let part = match kind {
+        "AND Gate" | "OR Gate" | "NAND Gate" | "NOR Gate" | "XOR Gate" | "XNOR Gate" => {
+            let inputs = attribute("inputs").and_then(|inputs| inputs.parse().ok()).unwrap_or(default_inputs);
+            if inputs != 2 {
+                return Err(format!("A \"{kind}\" with {inputs} inputs"));
+            }
+            if component.children("a").any(|a| a.attribute("name").map_or(false, |name| name.starts_with("negate")) && a.attribute("val") == Some("true")) {
+                return Err(format!("A \"{kind}\" with negated inputs"));
+            }
+
+            let (module, exclusive, negated) = match kind {
+                "AND Gate" => ("And", false, false),
+                "OR Gate" => ("Or", false, false),
+                "NAND Gate" => ("Nand", false, true),
+                "NOR Gate" => ("Nor", false, true),
+                "XOR Gate" => ("Xor", true, false),
+                _ => ("Xnor", true, true)
+            };
+            let size = attribute("size").and_then(|size| size.parse().ok()).unwrap_or(50);
+            // the curved back of exclusive gates and the output bubble both push the inputs back
+            let depth = size + if exclusive { 10 } else { 0 } + if negated { 10 } else { 0 };
+            let spread = if size < 40 { 10 } else { 20 };
+            Part::Block(module, vec![behind(depth, -spread), behind(depth, spread)], Some(location))
+        }
+        "NOT Gate" => {
+            let size = attribute("size").and_then(|size| size.parse().ok()).unwrap_or(30);
+            Part::Block("Not", vec![behind(size, 0)], Some(location))
+        }
+        "Constant" => {
+            let value = attribute("value").unwrap_or("0x1");
+            Part::Block(if value == "0x0" { "Low" } else { "High" }, vec![], Some(location))
+        }
+        "Button" => Part::Block("Button", vec![], Some(location)),
+        "LED" => Part::Block("Lamp", vec![location], None),
+        "Pin" => {
+            let label = attribute("label").filter(|label| !label.is_empty()).map(str::to_string);
+            match attribute("output") {
+                Some("true") => Part::OutputPin(label),
+                _ => Part::InputPin(label)
+            }
+        }
+        // tunnels without a label connect to nothing
+        "Tunnel" => match attribute("label").filter(|label| !label.is_empty()) {
+            Some(label) => Part::Tunnel(label.to_string()),
+            None => return Ok(None)
+        },
+        // labels have no effect on the circuit
+        "Text" => return Ok(None),
+        _ => return Err(format!("An unsupported \"{kind}\""))
+    };
+    Ok(Some(part))
+}
+
+// parses Logisim's "(x,y)" notation
+fn parse_point(text: &str) -> Option<Point> {
+    let (x, y) = text.trim().strip_prefix('(')?.strip_suffix(')')?.split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+// whether `point` lies inside the horizontal or vertical wire from `from` to `to`
+fn on_segment(point: Point, from: Point, to: Point) -> bool {
+    let within = |value: i32, a: i32, b: i32| a.min(b) < value && value < a.max(b);
+    (from.0 == to.0 && point.0 == from.0 && within(point.1, from.1, to.1)) ||
+    (from.1 == to.1 && point.1 == from.1 && within(point.0, from.0, to.0))
+}
+
+// points joined by wires, as a union-find
+#[derive(Default)]
+struct Nets {
+    parents: HashMap<Point, Point>
+}
+
+impl Nets {
+    fn find(&mut self, point: Point) -> Point {
+        let parent = *self.parents.get(&point).unwrap_or(&point);
+        if parent == point {
+            return point;
+        }
+        let root = self.find(parent);
+        self.parents.insert(point, root);
+        root
+    }
+
+    fn union(&mut self, a: Point, b: Point) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parents.insert(a.max(b), a.min(b));
+        }
+    }
+}
+
+// just enough of XML to read Logisim files: elements and attributes, without namespaces or text
+mod xml {
+    pub struct Element {
+        pub name: String,
+        attributes: Vec<(String, String)>,
+        children: Vec<Element>
+    }
+
+    impl Element {
+        pub fn attribute(&self, name: &str) -> Option<&str> {
+            self.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+        }
+
+        pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+            self.children.iter().filter(move |child| child.name == name)
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Element, String> {
+        let mut stack: Vec<Element> = Vec::new();
+        let mut rest = text;
+        let mut root = None;
+
+        while let Some(start) = rest.find('<') {
+            rest = &rest[start..];
+            if let Some(after) = rest.strip_prefix("<!--") {
+                rest = &after[after.find("-->").ok_or("Unterminated comment")? + 3..];
+            }
+            else if rest.starts_with("<?") || rest.starts_with("<!") {
+                rest = &rest[rest.find('>').ok_or("Unterminated declaration")? + 1..];
+            }
+            else if let Some(after) = rest.strip_prefix("</") {
+                let end = after.find('>').ok_or("Unterminated closing tag")?;
+                let name = after[..end].trim();
+                let element = stack.pop().filter(|element| element.name == name).ok_or_else(|| format!("Unexpected closing tag `{name}`"))?;
+                rest = &after[end + 1..];
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element)
+                }
+            }
+            else {
+                let (element, closed, after) = parse_tag(&rest[1..])?;
+                rest = after;
+                match (closed, stack.last_mut()) {
+                    (false, _) => stack.push(element),
+                    (true, Some(parent)) => parent.children.push(element),
+                    (true, None) => root = Some(element)
+                }
+            }
+        }
+
+        if let Some(open) = stack.last() {
+            return Err(format!("Unclosed tag `{}`", open.name));
+        }
+        root.ok_or_else(|| String::from("The file is empty."))
+    }
+
+    // reads a start tag up to its `>`, returning whether it closes itself
+    fn parse_tag(text: &str) -> Result<(Element, bool, &str), String> {
+        let name_end = text.find(|c: char| c.is_whitespace() || c == '>' || c == '/').ok_or("Unterminated tag")?;
+        let mut element = Element {
+            name: text[..name_end].to_string(),
+            attributes: Vec::new(),
+            children: Vec::new()
+        };
+
+        let mut rest = text[name_end..].trim_start();
+        loop {
+            if let Some(after) = rest.strip_prefix("/>") {
+                return Ok((element, true, after));
+            }
+            if let Some(after) = rest.strip_prefix('>') {
+                return Ok((element, false, after));
+            }
+
+            let (key, after) = rest.split_once('=').ok_or_else(|| format!("Malformed attribute in tag `{}`", element.name))?;
+            let after = after.trim_start();
+            let quote = after.chars().next().filter(|c| *c == '"' || *c == '\'').ok_or_else(|| format!("Unquoted attribute in tag `{}`", element.name))?;
+            let value_end = after[1..].find(quote).ok_or("Unterminated attribute value")?;
+            element.attributes.push((key.trim().to_string(), unescape(&after[1..value_end + 1])));
+            rest = after[value_end + 2..].trim_start();
+        }
+    }
+
+    fn unescape(text: &str) -> String {
+        text.replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // two pins feeding a Xor gate for the sum and an And gate for the carry; the wires to the gates' second inputs cross without touching
+    const HALF_ADDER: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<project source="3.8.0" version="1.0">
+  <circuit name="Half Adder">
+    <comp lib="0" loc="(100,90)" name="Pin"><a name="label" val="a"/></comp>
+    <comp lib="0" loc="(100,230)" name="Pin"><a name="label" val="b"/></comp>
+    <comp lib="1" loc="(250,110)" name="XOR Gate"/>
+    <comp lib="1" loc="(250,210)" name="AND Gate"/>
+    <comp lib="0" loc="(300,110)" name="Pin"><a name="output" val="true"/><a name="label" val="sum"/></comp>
+    <comp lib="0" loc="(300,210)" name="Pin"><a name="output" val="true"/><a name="label" val="carry"/></comp>
+    <comp lib="0" loc="(150,300)" name="Clock"/>
+    <wire from="(100,90)" to="(190,90)"/>
+    <wire from="(150,90)" to="(150,190)"/>
+    <wire from="(150,190)" to="(200,190)"/>
+    <wire from="(100,230)" to="(200,230)"/>
+    <wire from="(170,130)" to="(170,230)"/>
+    <wire from="(170,130)" to="(190,130)"/>
+    <wire from="(250,110)" to="(300,110)"/>
+    <wire from="(250,210)" to="(300,210)"/>
+  </circuit>
+</project>"#;
+
+    #[test]
+    fn a_half_adder_is_imported_with_its_wiring() {
+        let root = xml::parse(HALF_ADDER).unwrap();
+        let circuit = root.children("circuit").next().unwrap();
+        let name = String::from("Half Adder");
+        let mut project = Project::default();
+        let mut skipped = Vec::new();
+        import_circuit(&mut project, &name, circuit, 2, &mut skipped).unwrap();
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].starts_with("An unsupported \"Clock\""), "unexpected message: {}", skipped[0]);
+
+        let module = project.module(&name).unwrap();
+        assert_eq!((module.get_num_inputs(), module.get_num_outputs()), (2, 2));
+        let plot = module.plot().unwrap();
+        let mut gates = plot.blocks().values().map(|block| block.module_id().as_str()).filter(|id| !["Input", "Output"].contains(id)).collect::<Vec<_>>();
+        gates.sort();
+        assert_eq!(gates, vec!["And", "Xor"]);
+        assert_eq!(plot.connections().len(), 4);
+
+        // each input wire reaches both gates and keeps its pin's label
+        let (input_block, _) = module.io_blocks().unwrap();
+        for (port, label) in [(0, "a"), (1, "b")] {
+            let connection_id = plot.get_block(input_block).unwrap().connection(Connector::Output(port)).unwrap();
+            let connection = plot.get_connection(&connection_id).unwrap();
+            assert_eq!(connection.destinations().len(), 2);
+            assert_eq!(connection.name().as_deref(), Some(label));
+        }
+
+        let table = crate::export::TruthTable::from_module(&project, &name, None).unwrap();
+        assert_eq!(table.to_csv(), "in0,in1,out0,out1\n0,0,0,0\n1,0,1,0\n0,1,1,0\n1,1,0,1\n");
+    }
+
+    #[test]
+    fn a_half_adder_file_becomes_one_module() {
+        let (mod_file, skipped) = LogisimFile::from_xml(HALF_ADDER).unwrap().into_parts();
+        assert_eq!(mod_file.module_names(), vec![&String::from("Half Adder")]);
+        assert_eq!(skipped.len(), 1);
+    }
+
+    #[test]
+    fn malformed_xml_is_refused() {
+        assert!(LogisimFile::from_xml(r#"<project source="3.8.0"><circuit name="main"></project>"#).is_err());
+        assert!(LogisimFile::from_xml(r#"<project source="3.8.0"><circuit name="main">"#).is_err());
+        assert!(LogisimFile::from_xml(r#"<project source=3.8.0><circuit name="main"/></project>"#).is_err());
+        assert!(LogisimFile::from_xml(r#"<project source="3.8.0"><circuit name="main"/></project>"#).is_ok());
+    }
+}
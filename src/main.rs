@@ -12,6 +12,9 @@ mod config;
 mod fatal;
 mod project;
 mod export;
+mod logisim;
+mod preferences;
+mod cli;
 mod id;
 
 #[macro_use]
@@ -32,8 +35,19 @@ trait FileExtension {
 
 fn main() {
     env_logger::init();
+
+    let arguments = cli::Arguments::parse(std::env::args()).unwrap_or_else(|err| {
+        eprintln!("logicrs: {err}");
+        std::process::exit(2);
+    });
+
     info!("Starting up LogicRs...");    
+    id::Id::seed_from_env();
     
     let application = Application::new();
-    std::process::exit(application.run());
+    if let Some(path) = &arguments.project && let Err(err) = application.load_project(path) {
+        eprintln!("logicrs: could not open `{}`: {err}", path.display());
+        std::process::exit(1);
+    }
+    std::process::exit(application.run_with_args(&arguments.gtk_args));
 }
@@ -0,0 +1,161 @@
+use crate::{config, application::editor::WireStyle};
+
+use serde::{Serialize, Deserialize};
+use gtk::glib;
+use std::{fs, path::PathBuf};
+
+// what quitting does with unsaved changes
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CloseBehavior {
+    #[default]
+    Prompt = 0,
+    SaveAndQuit = 1,
+    QuitWithoutSaving = 2
+}
+
+impl From<u8> for CloseBehavior {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Prompt,
+            1 => Self::SaveAndQuit,
+            2 => Self::QuitWithoutSaving,
+            _ => panic!()
+        }
+    }
+}
+
+// settings that belong to the user rather than to a project, kept in the user's config directory
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Preferences {
+    #[serde(default)]
+    favorite_modules: Vec<String>,
+
+    // plots with more blocks and connections than this ask to be opened in lite mode
+    #[serde(default)]
+    complexity_threshold: Option<usize>,
+
+    #[serde(default)]
+    close_behavior: CloseBehavior,
+
+    // how wires are drawn in every project, only ever a matter of looks
+    #[serde(default)]
+    wire_style: WireStyle
+}
+
+impl Preferences {
+    const FILE_NAME: &'static str = "preferences.json";
+    pub const DEFAULT_COMPLEXITY_THRESHOLD: usize = 5000;
+
+    fn path() -> PathBuf {
+        glib::user_config_dir().join(config::APP_ID).join(Self::FILE_NAME)
+    }
+
+    // falls back to the defaults if the file is missing or unreadable
+    pub fn load() -> Self {
+        let path = Self::path();
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                warn!("Could not read preferences from `{}`: {err}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default()
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+
+        let serialized = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        fs::write(&path, serialized).map_err(|err| err.to_string())?;
+        info!("Wrote preferences to `{}`", path.display());
+        Ok(())
+    }
+
+    pub fn complexity_threshold(&self) -> usize {
+        self.complexity_threshold.unwrap_or(Self::DEFAULT_COMPLEXITY_THRESHOLD)
+    }
+
+    pub fn close_behavior(&self) -> CloseBehavior {
+        self.close_behavior
+    }
+
+    pub fn set_close_behavior(&mut self, close_behavior: CloseBehavior) {
+        self.close_behavior = close_behavior;
+    }
+
+    pub fn wire_style(&self) -> WireStyle {
+        self.wire_style
+    }
+
+    pub fn set_wire_style(&mut self, wire_style: WireStyle) {
+        self.wire_style = wire_style;
+    }
+
+    pub fn favorite_modules(&self) -> &Vec<String> {
+        &self.favorite_modules
+    }
+
+    pub fn is_favorite(&self, module_name: &String) -> bool {
+        self.favorite_modules.contains(module_name)
+    }
+
+    // returns whether the module is a favorite now
+    pub fn toggle_favorite(&mut self, module_name: &String) -> bool {
+        match self.favorite_modules.iter().position(|name| name == module_name) {
+            Some(index) => {
+                self.favorite_modules.remove(index);
+                false
+            }
+            None => {
+                self.favorite_modules.push(module_name.clone());
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggling_a_favorite_adds_and_removes_it() {
+        let mut preferences = Preferences::default();
+        let name = String::from("Half Adder");
+
+        assert!(preferences.toggle_favorite(&name));
+        assert!(preferences.is_favorite(&name));
+        assert_eq!(preferences.favorite_modules(), &vec![name.clone()]);
+
+        assert!(!preferences.toggle_favorite(&name));
+        assert!(!preferences.is_favorite(&name));
+        assert!(preferences.favorite_modules().is_empty());
+    }
+
+    #[test]
+    fn favorites_survive_saving() {
+        let mut preferences = Preferences::default();
+        preferences.toggle_favorite(&String::from("Half Adder"));
+
+        let loaded: Preferences = serde_json::from_str(&serde_json::to_string(&preferences).unwrap()).unwrap();
+        assert_eq!(loaded.favorite_modules(), preferences.favorite_modules());
+    }
+
+    #[test]
+    fn wire_style_survives_saving() {
+        let mut preferences = Preferences::default();
+        preferences.set_wire_style(WireStyle::Orthogonal);
+
+        let loaded: Preferences = serde_json::from_str(&serde_json::to_string(&preferences).unwrap()).unwrap();
+        assert_eq!(loaded.wire_style(), WireStyle::Orthogonal);
+    }
+
+    #[test]
+    fn older_preferences_fall_back_to_the_default_wire_style() {
+        let loaded: Preferences = serde_json::from_str(r#"{"favorite_modules": []}"#).unwrap();
+        assert_eq!(loaded.wire_style(), WireStyle::default());
+    }
+}
@@ -1,15 +1,75 @@
-use std::{collections::*, sync::*, fs::{OpenOptions, File}, io::{Write, BufReader}};
+use std::{collections::*, sync::*, fs::{self, OpenOptions}, io::Write, path::{Path, PathBuf}};
 use serde::{Serialize, Deserialize, ser::SerializeStruct};
-use gtk::{gio, prelude::FileExt};
-use crate::{simulator::{*, builtin::BUILTINS}, renderer::vector::Vector2, FileExtension};
+use gtk::{gio, glib, prelude::FileExt};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use crate::{simulator::{*, builtin::BUILTINS, breakpoint::Breakpoint, watch::Watch, stimulus::Stimulus, snapshot::Snapshot}, renderer::vector::Vector2, FileExtension, id::Id};
 
-pub type ProjectRef = Arc<Mutex<Project>>;
+// the project shared between the ui and the simulator thread
+#[derive(Clone, Default)]
+pub struct ProjectRef(Arc<Mutex<Project>>);
+
+impl ProjectRef {
+    // a thread that panicked while holding the lock poisons it; the project is still taken over
+    // instead of cascading the panic into every later caller
+    pub fn lock(&self) -> MutexGuard<'_, Project> {
+        self.0.lock().unwrap_or_else(|poisoned| {
+            static WARNED: atomic::AtomicBool = atomic::AtomicBool::new(false);
+            if !WARNED.swap(true, atomic::Ordering::Relaxed) {
+                warn!("Recovered the project from a poisoned lock; a thread panicked while holding it.");
+            }
+            poisoned.into_inner()
+        })
+    }
+}
+
+// how a project is written to disk; json stays the default since it can be diffed and merged
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SaveFormat {
+    #[default]
+    Json,
+    CompressedJson,
+    Binary
+}
+
+impl SaveFormat {
+    // binary files start with these bytes, json files never do
+    const BINARY_MAGIC: &'static [u8] = b"LRSB\x01";
+    const GZIP_MAGIC: &'static [u8] = &[0x1f, 0x8b];
+}
 
 #[derive(Deserialize)]
 pub struct Project {
     modules: HashMap<String, Module>,
     main_plot: Plot,
-    tps: i32
+    tps: i32,
+    #[serde(default)]
+    tab_order: Vec<String>,
+    #[serde(default)]
+    net_colors: bool,
+    // free-form notes about the project, like its author or when it was created
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
+    #[serde(default)]
+    stimulus: Option<Stimulus>,
+    #[serde(skip)]
+    connection_counts: bool,
+    #[serde(skip)]
+    recent_modules: Vec<String>,
+    #[serde(skip)]
+    auto_throttle: bool,
+    #[serde(skip)]
+    breakpoints: Vec<Breakpoint>,
+    #[serde(skip)]
+    watches: Vec<Watch>,
+    #[serde(skip)]
+    snapshots: Vec<Snapshot>,
+    #[serde(skip)]
+    ticks: u64,
+    // rounds of updates the last tick needed to settle and the most any tick needed since the simulation started
+    #[serde(skip)]
+    convergence_depth: (u32, u32),
+    #[serde(skip)]
+    save_format: SaveFormat
 }
 
 impl Default for Project {
@@ -22,10 +82,14 @@ impl Serialize for Project {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where
             S: serde::Serializer {
-        let mut state = serializer.serialize_struct("Project", 2)?;
+        let mut state = serializer.serialize_struct("Project", 7)?;
         state.serialize_field("modules", &HashMap::<&String, &Module>::from_iter(self.modules.iter().filter(|(_, module)| !module.builtin())))?;
         state.serialize_field("main_plot", &self.main_plot)?;
         state.serialize_field("tps", &self.tps)?;
+        state.serialize_field("tab_order", &self.tab_order)?;
+        state.serialize_field("net_colors", &self.net_colors)?;
+        state.serialize_field("metadata", &self.metadata)?;
+        state.serialize_field("stimulus", &self.stimulus.as_ref().filter(|stimulus| stimulus.saved()))?;
         state.end()
     }
 }
@@ -43,45 +107,103 @@ impl FileExtension for Project {
 }
 
 impl Project {
+    pub const MAX_TRUTH_TABLE_ROWS: u128 = 1 << 16;
+    pub const MAX_RECENT_MODULES: usize = 8;
+
     pub fn new(modules: Vec<Module>) -> Self {
         Self {
             modules: modules.iter().map(|module| (module.name().to_owned(), module.clone())).collect(),
             main_plot: Plot::new(),
-            tps: Simulator::DEFAULT_TICKS_PER_SECOND
+            tps: Simulator::DEFAULT_TICKS_PER_SECOND,
+            tab_order: Vec::new(),
+            net_colors: false,
+            metadata: BTreeMap::new(),
+            stimulus: None,
+            connection_counts: false,
+            recent_modules: Vec::new(),
+            auto_throttle: false,
+            breakpoints: Vec::new(),
+            watches: Vec::new(),
+            snapshots: Vec::new(),
+            ticks: 0,
+            convergence_depth: (0, 0),
+            save_format: SaveFormat::default()
         }
     }
 
     pub fn load_from(file: &gio::File) -> Result<Self, String> {
-        let f = File::open(file.path().unwrap())
-            .map_err(|err| err.to_string())?;
-        let mut project: Self = serde_json::from_reader(BufReader::new(f))
+        let path = local_path(file)?;
+        let bytes = fs::read(&path)
             .map_err(|err| err.to_string())?;
+        let mut project: Self = if let Some(data) = bytes.strip_prefix(SaveFormat::BINARY_MAGIC) {
+            let mut project: Self = bincode::deserialize(data).map_err(|err| err.to_string())?;
+            project.save_format = SaveFormat::Binary;
+            project
+        }
+        else if bytes.starts_with(SaveFormat::GZIP_MAGIC) {
+            let mut project: Self = serde_json::from_reader(GzDecoder::new(bytes.as_slice())).map_err(|err| err.to_string())?;
+            project.save_format = SaveFormat::CompressedJson;
+            project
+        }
+        else {
+            serde_json::from_slice(&bytes).map_err(|err| err.to_string())?
+        };
 
         BUILTINS.iter().for_each(|(_, builtin)| project.add_module(builtin.module().clone()));
 
-        info!("Loaded from file `{}`", file.path().unwrap().to_str().unwrap());
+        info!("Loaded from file `{}`", path.display());
 
         project.iter_plots_mut().for_each(|plot| plot.update_all_blocks());
         Ok(project)
     }
 
     pub fn write_to(&self, file: &gio::File) -> Result<(), String> {
-        info!("Writing to `{}` ...", file.path().unwrap().to_str().unwrap());
-        let mut f = OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(file.path().unwrap())
-            .map_err(|err| err.to_string())?;
+        let path = local_path(file)?;
+        info!("Writing to `{}` ...", path.display());
 
-        let serialized = serde_json::to_string(self)
-            .map_err(|err| err.to_string())?;
-        let bytes_written = f.write(serialized.as_bytes())
-            .map_err(|err| err.to_string())?;
+        let serialized = match self.save_format {
+            SaveFormat::Json => {
+                let mut data = Vec::new();
+                self.write_json(&mut data)?;
+                data
+            }
+            SaveFormat::CompressedJson => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                self.write_json(&mut encoder)?;
+                encoder.finish().map_err(|err| err.to_string())?
+            }
+            SaveFormat::Binary => {
+                let mut data = SaveFormat::BINARY_MAGIC.to_vec();
+                data.extend(bincode::serialize(self).map_err(|err| err.to_string())?);
+                data
+            }
+        };
+        write_atomically(&path, &serialized)?;
 
-        info!("Wrote {bytes_written} bytes to `{}` successfully", file.path().unwrap().to_str().unwrap());
+        info!("Wrote {} bytes to `{}` successfully", serialized.len(), path.display());
         Ok(())
     }
 
+    // with seeded ids, objects are written with sorted keys so that equal projects give identical bytes
+    fn write_json<W: Write>(&self, writer: W) -> Result<(), String> {
+        let result = if Id::deterministic() {
+            let value = serde_json::to_value(self).map_err(|err| err.to_string())?;
+            serde_json::to_writer(writer, &value)
+        }
+        else {
+            serde_json::to_writer(writer, self)
+        };
+        result.map_err(|err| err.to_string())
+    }
+
+    pub fn save_format(&self) -> SaveFormat {
+        self.save_format
+    }
+
+    pub fn set_save_format(&mut self, save_format: SaveFormat) {
+        self.save_format = save_format;
+    }
+
     pub fn module(&self, name: &String) -> Option<&Module> {
         self.modules.get(name)
     }
@@ -104,24 +226,87 @@ impl Project {
 
     pub fn add_module(&mut self, mut module: Module) {
         if module.plot().is_some() && !module.has_io_blocks() {
-            let num_inputs = module.get_num_inputs();
-            let num_outputs = module.get_num_outputs();
-            
-            let input_module = self.modules.get(&*builtin::INPUT_MODULE_NAME).unwrap();
-            let input_block = Block::new_sized(&input_module, Vector2(50, 50), true, num_inputs,  num_inputs, None);
-            
-            let output_module = self.modules.get(&*builtin::OUTPUT_MODULE_NAME).unwrap();
-            let output_block = Block::new_sized(&output_module, Vector2(400, 50), true, num_outputs, num_outputs, None);
+            self.add_io_blocks(&mut module);
+        }
 
-            module.set_io_blocks(input_block.id(), output_block.id());
+        self.modules.insert(module.name().clone(), module);
+    }
+
+    // generate Input/Output blocks inside a new module, returning their ids
+    fn add_io_blocks(&self, module: &mut Module) -> (BlockID, BlockID) {
+        let num_inputs = module.get_num_inputs();
+        let num_outputs = module.get_num_outputs();
+        
+        let input_module = self.modules.get(&*builtin::INPUT_MODULE_NAME).unwrap();
+        let input_block = Block::new_sized(&input_module, Vector2(50, 50), true, num_inputs,  num_inputs, None);
+        
+        let output_module = self.modules.get(&*builtin::OUTPUT_MODULE_NAME).unwrap();
+        let output_block = Block::new_sized(&output_module, Vector2(400, 50), true, num_outputs, num_outputs, None);
 
-            // generate Input/Output blocks inside the new module
-            let plot = module.plot_mut().unwrap();
-            plot.add_block(input_block);
-            plot.add_block(output_block);
+        let ids = (input_block.id(), output_block.id());
+        module.set_io_blocks(ids.0, ids.1);
+
+        let plot = module.plot_mut().unwrap();
+        plot.add_block(input_block);
+        plot.add_block(output_block);
+        ids
+    }
+
+    // a new custom module doing what the builtin gate does, built from And, Or and Not gates
+    pub fn subcircuit_module(&self, builtin_name: &String) -> Result<Module, String> {
+        let gates = builtin::equivalent_circuit(builtin_name).ok_or_else(|| format!("\"{builtin_name}\" has no equivalent subcircuit"))?;
+        let builtin = self.modules.get(builtin_name).ok_or_else(|| format!("Module \"{builtin_name}\" not found"))?;
+
+        let mut name = format!("{builtin_name} Circuit");
+        let mut suffix = 2;
+        while self.modules.contains_key(&name) {
+            name = format!("{builtin_name} Circuit {suffix}");
+            suffix += 1;
         }
 
-        self.modules.insert(module.name().clone(), module);
+        let mut module = Module::new(name, builtin.get_num_inputs(), builtin.get_num_outputs());
+        let (input_block, output_block) = self.add_io_blocks(&mut module);
+
+        // gates are placed in columns by how many gates lie between them and the inputs
+        let mut gate_ids: Vec<BlockID> = Vec::with_capacity(gates.len());
+        let mut depths: Vec<i32> = Vec::with_capacity(gates.len());
+        let mut rows: HashMap<i32, i32> = HashMap::new();
+        let mut connections = vec![];
+        for (gate_name, sources) in gates.iter() {
+            let gate_module = self.modules.get(&gate_name.to_string()).ok_or_else(|| format!("Module \"{gate_name}\" not found"))?;
+            let depth = sources.iter().map(|source| match source {
+                builtin::GateSource::Input(_) => 1,
+                builtin::GateSource::Gate(index) => depths[*index] + 1
+            }).max().unwrap_or(1);
+            let row = rows.entry(depth).or_default();
+            let block = Block::new(&gate_module, Vector2(50 + depth * 150, 50 + *row * 100), None);
+            *row += 1;
+
+            for (port, source) in sources.iter().enumerate() {
+                connections.push(match source {
+                    builtin::GateSource::Input(input) => Connection::new_basic(input_block, *input, block.id(), port as u8),
+                    builtin::GateSource::Gate(index) => Connection::new_basic(gate_ids[*index], 0, block.id(), port as u8)
+                });
+            }
+
+            depths.push(depth);
+            gate_ids.push(block.id());
+            module.plot_mut().unwrap().add_block(block);
+        }
+
+        let last_gate = *gate_ids.last().ok_or_else(|| format!("\"{builtin_name}\" has no equivalent subcircuit"))?;
+        connections.push(Connection::new_basic(last_gate, 0, output_block, 0));
+
+        let plot = module.plot_mut().unwrap();
+        let output_depth = depths.iter().copied().max().unwrap_or(0) + 1;
+        if let Some(block) = plot.get_block_mut(output_block) {
+            block.set_position(Vector2(50 + output_depth * 150, 50));
+        }
+        for connection in connections {
+            plot.add_connection(connection);
+        }
+
+        Ok(module)
     }
 
     pub fn remove_module(&mut self, module_name: &String) {
@@ -144,6 +329,20 @@ impl Project {
         self.modules.get_mut(module_name).and_then(|module| module.plot_mut())
     }
 
+    pub fn plot_by_descriptor(&self, descriptor: &PlotDescriptor) -> Option<&Plot> {
+        match descriptor {
+            PlotDescriptor::Main() => Some(&self.main_plot),
+            PlotDescriptor::Module(name) => self.plot(name)
+        }
+    }
+
+    pub fn plot_by_descriptor_mut(&mut self, descriptor: &PlotDescriptor) -> Option<&mut Plot> {
+        match descriptor {
+            PlotDescriptor::Main() => Some(&mut self.main_plot),
+            PlotDescriptor::Module(name) => self.plot_mut(name)
+        }
+    }
+
     pub fn iter_plots_mut(&mut self) -> impl Iterator<Item = &mut Plot> {
         self.modules.iter_mut()
             .filter_map(|(_, module)| module.plot_mut())
@@ -158,6 +357,555 @@ impl Project {
         self.tps = tps
     }
 
+    // lists every block across all plots that instantiates the given module
+    pub fn find_instances(&self, mod_name: &String) -> Vec<(PlotDescriptor, &Block)> {
+        let mut instances = self.main_plot.blocks().values()
+            .filter(|block| block.module_id() == mod_name)
+            .map(|block| (PlotDescriptor::Main(), block))
+            .collect::<Vec<_>>();
+
+        let mut modules = self.modules.values().filter(|module| !module.builtin()).collect::<Vec<_>>();
+        modules.sort_by_key(|module| module.name());
+        for module in modules {
+            if let Some(plot) = module.plot() {
+                instances.extend(plot.blocks().values()
+                    .filter(|block| block.module_id() == mod_name)
+                    .map(|block| (PlotDescriptor::Module(module.name().clone()), block))
+                );
+            }
+        }
+
+        instances
+    }
+
+    pub fn check_replace_module(&self, from: &String, to: &String) -> Result<(), String> {
+        let (Some(old), Some(new)) = (self.modules.get(from), self.modules.get(to)) else {
+            return Err(format!("Module \"{from}\" or \"{to}\" does not exist."));
+        };
+
+        if from == to {
+            return Err(String::from("Cannot replace a module with itself."));
+        }
+
+        if old.get_num_inputs() != new.get_num_inputs() || old.get_num_outputs() != new.get_num_outputs() {
+            return Err(format!(
+                "\"{to}\" ({} inputs, {} outputs) is not pin-compatible with \"{from}\" ({} inputs, {} outputs).",
+                new.get_num_inputs(), new.get_num_outputs(), old.get_num_inputs(), old.get_num_outputs()
+            ));
+        }
+
+        if new.plot().is_some() && self.find_instances(from).iter().any(|(plot, _)| plot == &PlotDescriptor::Module(to.clone())) {
+            return Err(format!("\"{to}\" uses \"{from}\" itself; replacing it would cause recursion."));
+        }
+
+        Ok(())
+    }
+
+    // like `check_replace_module`, but for a single block inside `plot`
+    pub fn check_change_block_module(&self, plot: &PlotDescriptor, block_id: BlockID, to: &String) -> Result<(), String> {
+        let Some(block) = self.plot_by_descriptor(plot).and_then(|plot| plot.get_block(block_id)) else {
+            return Err(String::from("The block does not exist anymore."));
+        };
+        let from = block.module_id();
+        let (Some(old), Some(new)) = (self.modules.get(from), self.modules.get(to)) else {
+            return Err(format!("Module \"{from}\" or \"{to}\" does not exist."));
+        };
+
+        if from == to {
+            return Err(format!("The block already is a \"{to}\"."));
+        }
+
+        if old.get_num_inputs() != new.get_num_inputs() || old.get_num_outputs() != new.get_num_outputs() {
+            return Err(format!(
+                "\"{to}\" ({} inputs, {} outputs) is not pin-compatible with \"{from}\" ({} inputs, {} outputs).",
+                new.get_num_inputs(), new.get_num_outputs(), old.get_num_inputs(), old.get_num_outputs()
+            ));
+        }
+
+        if let PlotDescriptor::Module(parent) = plot && (parent == to || validation::contains_module(self, to, parent, &mut HashSet::new())) {
+            return Err(format!("\"{to}\" uses \"{parent}\" itself; placing it there would cause recursion."));
+        }
+
+        Ok(())
+    }
+
+    // swaps every instance of `from` with `to` and returns the original blocks
+    pub fn replace_module(&mut self, from: &String, to: &String) -> Result<Vec<(PlotDescriptor, Block)>, String> {
+        self.check_replace_module(from, to)?;
+
+        let new = self.modules[to].clone();
+        let instances = self.find_instances(from)
+            .into_iter()
+            .map(|(plot, block)| (plot, block.clone()))
+            .collect::<Vec<_>>();
+
+        for (descriptor, block) in instances.iter() {
+            if let Some(plot) = self.plot_by_descriptor_mut(descriptor) {
+                if let Some(instance) = plot.get_block_mut(block.id()) {
+                    instance.swap_module(&new);
+                }
+                plot.add_block_to_update(block.id());
+            }
+        }
+
+        Ok(instances)
+    }
+
+    // names the pins of a module, its placed instances and its io blocks; returns the previous names
+    pub fn set_pin_names(&mut self, mod_name: &String, pin_names: PinNames) -> Result<PinNames, String> {
+        let module = self.modules.get_mut(mod_name).ok_or_else(|| format!("no module named \"{mod_name}\""))?;
+        let previous = module.pin_names();
+        module.set_pin_names(pin_names);
+        let pin_names = module.pin_names();
+
+        if let Some((input_block, output_block)) = module.io_blocks() && let Some(plot) = module.plot_mut() {
+            if let Some(block) = plot.get_block_mut(input_block) {
+                block.set_pin_names((vec![], pin_names.0.clone()));
+            }
+            if let Some(block) = plot.get_block_mut(output_block) {
+                block.set_pin_names((pin_names.1.clone(), vec![]));
+            }
+        }
+
+        for plot in self.iter_plots_mut() {
+            plot.blocks_mut().values_mut()
+                .filter(|block| block.module_id() == mod_name)
+                .for_each(|block| block.set_pin_names(pin_names.clone()));
+        }
+
+        Ok(previous)
+    }
+
+    // the first locked module among the given plots, whose circuits must not be changed
+    pub fn locked_among<'a>(&self, plots: impl IntoIterator<Item = &'a PlotDescriptor>) -> Option<String> {
+        plots.into_iter().find_map(|descriptor| match descriptor {
+            PlotDescriptor::Module(name) if self.modules.get(name).map_or(false, |module| module.locked()) => Some(name.clone()),
+            _ => None
+        })
+    }
+
+    // deletes every placed instance of a module, returning them together with the wires removed alongside
+    pub fn remove_instances(&mut self, mod_name: &String) -> Vec<(PlotDescriptor, Block, Vec<Connection>)> {
+        let instances = self.find_instances(mod_name)
+            .into_iter()
+            .map(|(plot, block)| (plot, block.clone()))
+            .collect::<Vec<_>>();
+
+        instances.into_iter()
+            .filter_map(|(descriptor, mut block)| {
+                let connections = self.plot_by_descriptor_mut(&descriptor)?.delete_block(block.id());
+                block.set_highlighted(false);
+                Some((descriptor, block, connections))
+            })
+            .collect()
+    }
+
+    pub fn restore_instances(&mut self, instances: &[(PlotDescriptor, Block, Vec<Connection>)]) {
+        // place all blocks first, a wire may run between two removed instances
+        for (descriptor, block, _) in instances {
+            if let Some(plot) = self.plot_by_descriptor_mut(descriptor) {
+                plot.add_block(block.clone());
+            }
+        }
+
+        for (descriptor, _, connections) in instances {
+            if let Some(plot) = self.plot_by_descriptor_mut(descriptor) {
+                connections.iter().for_each(|connection| plot.restore_connection(connection.clone()));
+            }
+        }
+    }
+
+    // number of rows of the module's truth table, if it can be computed at all
+    pub fn truth_table_rows(&self, mod_name: &String) -> Result<u128, String> {
+        let module = self.modules.get(mod_name)
+            .ok_or_else(|| format!("Module \"{mod_name}\" does not exist."))?;
+        if module.plot().is_none() {
+            return Err(format!("\"{mod_name}\" is a builtin module and has no circuit."));
+        }
+
+        let mut dependencies = HashMap::new();
+        self.collect_dependencies(mod_name, &mut dependencies);
+        if dependencies.contains_key(mod_name) {
+            return Err(format!("Recursion detected; module \"{mod_name}\" contains itself."));
+        }
+
+        let sequential = std::iter::once(module)
+            .chain(dependencies.values())
+            .filter_map(|module| module.plot())
+            .flat_map(|plot| plot.blocks().values())
+            .filter_map(|block| self.modules.get(block.module_id()))
+            .find(|module| matches!(module.category(), Category::Latch | Category::FlipFlop));
+        if let Some(sequential) = sequential {
+            return Err(format!("\"{mod_name}\" is not combinational; it contains a \"{}\".", sequential.name()));
+        }
+
+        Ok(1u128.checked_shl(module.get_num_inputs() as u32).unwrap_or(u128::MAX))
+    }
+
+    // simulates the module for every input combination, stopping after `max_rows` rows
+    pub fn truth_table(&self, mod_name: &String, max_rows: Option<u128>) -> Result<Vec<(u128, u128)>, String> {
+        let num_rows = self.truth_table_rows(mod_name)?;
+        let num_rows = max_rows.map_or(num_rows, |max_rows| num_rows.min(max_rows));
+
+        // simulate on a copy so the running project stays untouched
+        let mut dependencies = HashMap::new();
+        self.collect_dependencies(mod_name, &mut dependencies);
+        let mut scratch = Self::new(self.modules.values()
+            .filter(|module| module.builtin())
+            .cloned()
+            .chain(dependencies.into_values())
+            .collect()
+        );
+        let mut module = self.modules[mod_name].clone();
+        let output_mask = 1u128.checked_shl(module.get_num_outputs() as u32).map_or(u128::MAX, |bit| bit - 1);
+
+        let mut rows = Vec::new();
+        for inputs in 0..num_rows {
+            let mut instance = Block::new(&&module, Vector2::default(), None);
+            scratch.iter_plots_mut().for_each(|plot| plot.update_all_blocks());
+            module.plot_mut().unwrap().update_all_blocks();
+
+            let outputs = module.simulate(inputs, &mut instance, &mut scratch, &mut HashSet::new())?;
+            rows.push((inputs, outputs & output_mask));
+        }
+
+        Ok(rows)
+    }
+
+    pub fn net_colors(&self) -> bool {
+        self.net_colors
+    }
+
+    pub fn set_net_colors(&mut self, net_colors: bool) {
+        self.net_colors = net_colors
+    }
+
+    pub fn describe_net(&self, plot: &PlotDescriptor, connection_id: &ConnectionID) -> String {
+        let net = self.plot_by_descriptor(plot)
+            .and_then(|plot| plot.get_connection(connection_id))
+            .map(|connection| match connection.name() {
+                Some(name) => format!("Net \"{name}\""),
+                None => String::from("Unnamed net")
+            })
+            .unwrap_or_else(|| String::from("Missing net"));
+        format!("{net} in {plot}")
+    }
+
+    pub fn connection_counts(&self) -> bool {
+        self.connection_counts
+    }
+
+    pub fn set_connection_counts(&mut self, connection_counts: bool) {
+        self.connection_counts = connection_counts
+    }
+
+    // whether the simulator may lower the tick speed when it can't keep up
+    pub fn auto_throttle(&self) -> bool {
+        self.auto_throttle
+    }
+
+    pub fn set_auto_throttle(&mut self, auto_throttle: bool) {
+        self.auto_throttle = auto_throttle
+    }
+
+    // the most recently placed modules, newest first
+    pub fn recent_modules(&self) -> &Vec<String> {
+        &self.recent_modules
+    }
+
+    pub fn add_recent_module(&mut self, name: &String) {
+        self.recent_modules.retain(|recent| recent != name);
+        self.recent_modules.insert(0, name.clone());
+        self.recent_modules.truncate(Self::MAX_RECENT_MODULES);
+    }
+
+    pub fn activity_tracking(&self) -> bool {
+        self.main_plot.activity().is_some()
+    }
+
+    pub fn set_activity_tracking(&mut self, enabled: bool) {
+        self.iter_plots_mut().for_each(|plot| plot.set_activity_tracking(enabled));
+    }
+
+    // marks the blocks the diagnostics point at with their worst severity, clearing all earlier marks
+    pub fn flag_problems(&mut self, diagnostics: &[validation::Diagnostic]) {
+        let mut flagged: HashMap<PlotDescriptor, HashMap<BlockID, validation::Severity>> = HashMap::new();
+        for diagnostic in diagnostics {
+            if let Some((plot, block_id)) = diagnostic.location() {
+                let severity = flagged.entry(plot.clone()).or_default().entry(*block_id).or_insert(diagnostic.severity());
+                *severity = (*severity).max(diagnostic.severity());
+            }
+        }
+
+        self.iter_plots_mut().for_each(|plot| plot.set_flagged(HashMap::new()));
+        for (descriptor, blocks) in flagged {
+            if let Some(plot) = self.plot_by_descriptor_mut(&descriptor) {
+                plot.set_flagged(blocks);
+            }
+        }
+    }
+
+    pub fn mark_drive_conflicts(&mut self) {
+        self.iter_plots_mut().for_each(|plot| plot.mark_drive_conflicts());
+    }
+
+    // the blocks whose outputs changed most often within a single tick, most active first
+    pub fn activity_report(&self, limit: usize) -> Vec<(PlotDescriptor, BlockID, u32)> {
+        let plots = self.modules.values()
+            .filter_map(|module| module.plot().map(|plot| (PlotDescriptor::Module(module.name().clone()), plot)))
+            .chain(std::iter::once((PlotDescriptor::Main(), &self.main_plot)));
+
+        let mut report = plots
+            .flat_map(|(descriptor, plot)| plot.activity().into_iter()
+                .flat_map(|activity| activity.iter())
+                .filter(move |(block_id, _)| plot.get_block(**block_id).is_some())
+                .map(move |(block_id, count)| (descriptor.clone(), *block_id, *count))
+            )
+            .collect::<Vec<_>>();
+        report.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.to_string().cmp(&b.0.to_string())));
+        report.truncate(limit);
+        report
+    }
+
+    pub fn breakpoints(&self) -> &Vec<Breakpoint> {
+        &self.breakpoints
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) {
+        self.breakpoints.retain(|other| other != breakpoint);
+    }
+
+    pub fn watches(&self) -> &Vec<Watch> {
+        &self.watches
+    }
+
+    pub fn add_watch(&mut self, plot: PlotDescriptor, connection_id: ConnectionID) {
+        if !self.watches.iter().any(|watch| watch.watches(&plot, &connection_id)) {
+            let mut watch = Watch::new(plot, connection_id);
+            watch.update(self, self.ticks);
+            self.watches.push(watch);
+        }
+    }
+
+    pub fn remove_watch(&mut self, plot: &PlotDescriptor, connection_id: &ConnectionID) {
+        self.watches.retain(|watch| !watch.watches(plot, connection_id));
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn convergence_depth(&self) -> u32 {
+        self.convergence_depth.0
+    }
+
+    pub fn max_convergence_depth(&self) -> u32 {
+        self.convergence_depth.1
+    }
+
+    pub fn record_convergence_depth(&mut self, depth: u32) {
+        self.convergence_depth = (depth, self.convergence_depth.1.max(depth));
+    }
+
+    pub fn reset_convergence_depth(&mut self) {
+        self.convergence_depth = (0, 0);
+    }
+
+    pub fn snapshots(&self) -> &Vec<Snapshot> {
+        &self.snapshots
+    }
+
+    pub fn capture_snapshot(&mut self, name: String) {
+        let snapshot = Snapshot::capture(name, self);
+        self.snapshots.push(snapshot);
+    }
+
+    pub fn restore_snapshot(&mut self, index: usize) -> Result<(), String> {
+        let snapshot = self.snapshots.get(index).cloned().ok_or_else(|| String::from("The snapshot does not exist anymore."))?;
+        snapshot.restore(self);
+        info!("Restored snapshot \"{}\" taken at tick {}", snapshot.name(), snapshot.ticks());
+        Ok(())
+    }
+
+    pub fn remove_snapshot(&mut self, index: usize) {
+        if index < self.snapshots.len() {
+            self.snapshots.remove(index);
+        }
+    }
+
+    pub fn stimulus(&self) -> Option<&Stimulus> {
+        self.stimulus.as_ref()
+    }
+
+    pub fn stimulus_mut(&mut self) -> Option<&mut Stimulus> {
+        self.stimulus.as_mut()
+    }
+
+    pub fn set_stimulus(&mut self, stimulus: Option<Stimulus>) {
+        self.stimulus = stimulus;
+    }
+
+    // called by the simulator after every tick
+    pub fn advance_tick(&mut self) {
+        self.ticks += 1;
+        if let Some(stimulus) = self.stimulus.as_mut() {
+            stimulus.advance(self.ticks, &mut self.main_plot);
+        }
+        let mut watches = std::mem::take(&mut self.watches);
+        watches.iter_mut().for_each(|watch| watch.update(self, self.ticks));
+        self.watches = watches;
+    }
+
+    // renumbers the ids in every circuit in a fixed order, so saving an unchanged circuit gives the same file
+    pub fn normalize_ids(&mut self) {
+        let main = self.main_plot.normalize_ids();
+        let modules = self.modules.iter_mut()
+            .filter_map(|(name, module)| Some((name.clone(), module.plot_mut()?.normalize_ids())))
+            .collect::<HashMap<_, _>>();
+
+        // the state of a module's instance refers to blocks inside the module, which may themselves be instances
+        let block_modules = self.modules.iter()
+            .filter_map(|(name, module)| Some((
+                name.clone(),
+                module.plot()?.blocks().iter().map(|(id, block)| (*id, block.module_id().clone())).collect::<HashMap<_, _>>()
+            )))
+            .collect::<HashMap<_, _>>();
+
+        let plots = std::iter::once(&mut self.main_plot)
+            .chain(self.modules.values_mut().filter_map(|module| module.plot_mut()));
+        for plot in plots {
+            for block in plot.blocks_mut().values_mut() {
+                let mut state = block.state().clone();
+                remap_state(&mut state, block.module_id(), &modules, &block_modules);
+                block.set_state(state);
+            }
+        }
+
+        let connection_map = |descriptor: &PlotDescriptor| match descriptor {
+            PlotDescriptor::Main() => Some(&main.1),
+            PlotDescriptor::Module(name) => modules.get(name).map(|(_, connections)| connections)
+        };
+        self.breakpoints.iter_mut().for_each(|breakpoint| if let Some(id) = connection_map(breakpoint.plot()).and_then(|map| map.get(&breakpoint.connection())) {
+            breakpoint.set_connection(*id);
+        });
+        self.watches.iter_mut().for_each(|watch| if let Some(id) = connection_map(watch.plot()).and_then(|map| map.get(&watch.connection())) {
+            watch.set_connection(*id);
+        });
+        if let Some(stimulus) = self.stimulus.as_mut() {
+            stimulus.remap_inputs(&main.0);
+        }
+
+        let main_blocks = self.main_plot.blocks().iter().map(|(id, block)| (*id, block.module_id().clone())).collect::<HashMap<_, _>>();
+        for snapshot in self.snapshots.iter_mut() {
+            for (descriptor, state) in snapshot.plots_mut() {
+                let (mapping, blocks) = match descriptor {
+                    PlotDescriptor::Main() => (Some(&main), Some(&main_blocks)),
+                    PlotDescriptor::Module(name) => (modules.get(name), block_modules.get(name))
+                };
+                if let Some((block_ids, connection_ids)) = mapping {
+                    state.remap_ids(block_ids, connection_ids);
+                }
+                for (id, inner) in state.blocks_mut() {
+                    if let Some(module) = blocks.and_then(|blocks| blocks.get(id)) {
+                        remap_state(inner, module, &modules, &block_modules);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+
+    // sets a metadata entry, removing it if the value is empty
+    pub fn set_metadata(&mut self, key: &str, value: String) {
+        if value.is_empty() {
+            self.metadata.remove(key);
+        }
+        else {
+            self.metadata.insert(key.to_string(), value);
+        }
+    }
+
+    // records the time of saving, and of the first save if the project has never been saved before
+    pub fn stamp_save_time(&mut self) {
+        match glib::DateTime::now_local().and_then(|now| now.format_iso8601()) {
+            Ok(now) => {
+                self.metadata.entry(String::from("created")).or_insert_with(|| now.to_string());
+                self.metadata.insert(String::from("modified"), now.to_string());
+            }
+            Err(err) => warn!("Could not get the current time: {err}")
+        }
+    }
+
+    pub fn tab_order(&self) -> &Vec<String> {
+        &self.tab_order
+    }
+
+    pub fn set_tab_order(&mut self, tab_order: Vec<String>) {
+        self.tab_order = tab_order
+    }
+
+    // the modules pasting a copied module adds, dependencies first, and the name to place an instance of:
+    // modules of the same name and contents are reused, differing ones make the copy get a new name
+    pub fn paste_module(&self, module: &Module, dependencies: &[Module]) -> Result<(String, Vec<Module>), String> {
+        let mut renames = HashMap::new();
+        let mut added: Vec<Module> = Vec::new();
+        for copied in dependencies.iter().chain(std::iter::once(module)) {
+            let name = copied.name();
+            // a copy using a renamed dependency differs from the existing module, which uses the project's own one
+            let uses_renamed = copied.plot().map_or(false, |plot| plot.blocks().values().any(|block| renames.contains_key(block.module_id())));
+            if let Some(existing) = self.modules.get(name) && !uses_renamed && existing.same_contents(copied) {
+                continue;
+            }
+            if copied.builtin() {
+                return Err(format!("The builtin module \"{name}\" differs from the copied one."));
+            }
+
+            let mut new_name = name.clone();
+            let mut suffix = 2;
+            while self.modules.contains_key(&new_name) || added.iter().any(|module| module.name() == &new_name) {
+                new_name = format!("{name} {suffix}");
+                suffix += 1;
+            }
+            if &new_name != name {
+                renames.insert(name.clone(), new_name.clone());
+            }
+
+            let mut copied = copied.clone();
+            copied.set_name(new_name);
+            added.push(copied);
+        }
+
+        // point the copies at the renamed modules and make sure everything else they use exists
+        let added_names = added.iter().map(|module| module.name().clone()).collect::<HashSet<_>>();
+        let mut missing = HashSet::new();
+        for block in added.iter_mut().filter_map(|module| module.plot_mut()).flat_map(|plot| plot.blocks_mut().values_mut()) {
+            if let Some(new_name) = renames.get(block.module_id()) {
+                block.set_module_id(new_name.clone());
+            }
+            else if !self.modules.contains_key(block.module_id()) && !added_names.contains(block.module_id()) {
+                missing.insert(block.module_id().clone());
+            }
+        }
+
+        if !missing.is_empty() {
+            let mut missing = missing.into_iter().collect::<Vec<_>>();
+            missing.sort();
+            return Err(format!("\"{}\" uses modules missing from this project:\n\t{}", module.name(), missing.join(",\n\t")));
+        }
+
+        let name = renames.get(module.name()).cloned().unwrap_or_else(|| module.name().clone());
+        Ok((name, added))
+    }
+
     pub fn collect_dependencies(&self, mod_name: &String, modules: &mut HashMap<String, Module>) {
         if let Some(plot) = self.modules.get(mod_name).and_then(|module| module.plot()) {
             plot.blocks()
@@ -171,3 +919,382 @@ impl Project {
         }
     }
 }
+
+// files chosen in a dialog can be remote uris, which can't be read or written directly
+pub(crate) fn local_path(file: &gio::File) -> Result<PathBuf, String> {
+    file.path().ok_or_else(|| String::from("file has no local path"))
+}
+
+// writes a temporary file next to `path` and renames it over `path` once it is on disk,
+// so a crash while saving leaves either the old or the new file, never a partial one
+pub(crate) fn write_atomically(path: &Path, data: &[u8]) -> Result<(), String> {
+    let file_name = path.file_name().ok_or_else(|| format!("`{}` is not a file", path.display()))?;
+    let temp_path = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+
+    let result = (|| {
+        let mut f = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&temp_path)?;
+        // keep the permissions of the file being replaced
+        if let Ok(metadata) = fs::metadata(path) {
+            f.set_permissions(metadata.permissions())?;
+        }
+        f.write_all(data)?;
+        f.flush()?;
+        f.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+
+    if let Err(err) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(err.to_string());
+    }
+
+    // make the rename itself durable
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) && let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+// follows a renumbered module into the saved state of one of its instances
+fn remap_state(state: &mut State, module: &String, modules: &HashMap<String, IdMapping>, block_modules: &HashMap<String, HashMap<BlockID, String>>) {
+    let (State::Inherit(plot_state), Some((blocks, connections))) = (state, modules.get(module)) else {
+        return;
+    };
+
+    plot_state.remap_ids(blocks, connections);
+    for (id, state) in plot_state.blocks_mut() {
+        if let Some(inner) = block_modules.get(module).and_then(|blocks| blocks.get(id)) {
+            remap_state(state, inner, modules, block_modules);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::editor::WireStyle;
+
+    // every wire as its driving pin and the pins it reaches, each pin given by its block's position
+    fn wiring(plot: &Plot) -> Vec<((Vector2<i32>, u8), Vec<(Vector2<i32>, u8)>)> {
+        let pin = |port: Port| (plot.get_block(port.block_id()).unwrap().position(), port.index());
+        let mut wiring = plot.connections().values()
+            .map(|connection| {
+                let mut destinations = connection.destinations().into_iter().map(pin).collect::<Vec<_>>();
+                destinations.sort();
+                (pin(connection.origin()), destinations)
+            })
+            .collect::<Vec<_>>();
+        wiring.sort();
+        wiring
+    }
+
+    // every wire is linked from the pins it starts and ends at
+    fn links_consistent(plot: &Plot) -> bool {
+        plot.connections().values().all(|connection| {
+            std::iter::once(connection.origin()).chain(connection.destinations())
+                .all(|port| plot.get_block(port.block_id()).and_then(|block| block.connection(port.into())) == Some(connection.id()))
+        })
+    }
+
+    fn xor_project() -> (Project, String) {
+        let mut project = Project::default();
+        let module = project.subcircuit_module(&String::from("Xor")).unwrap();
+        let name = module.name().clone();
+        project.add_module(module);
+
+        let first = Block::new(&&project.modules()[&name], Vector2(0, 0), None);
+        let second = Block::new(&&project.modules()[&name], Vector2(300, 0), None);
+        let connection = Connection::new_basic(first.id(), 0, second.id(), 1);
+        project.main_plot_mut().add_block(first);
+        project.main_plot_mut().add_block(second);
+        project.main_plot_mut().add_connection(connection);
+        (project, name)
+    }
+
+    #[test]
+    fn files_without_a_local_path_are_refused() {
+        let file = gio::File::for_uri("http://example.com/project.lrsproj");
+        assert_eq!(Project::default().write_to(&file), Err(String::from("file has no local path")));
+        assert_eq!(Project::load_from(&file).err(), Some(String::from("file has no local path")));
+    }
+
+    #[test]
+    fn the_project_survives_a_thread_panicking_with_the_lock() {
+        let project = ProjectRef::default();
+        let shared = project.clone();
+        let result = std::thread::spawn(move || {
+            let _guard = shared.lock();
+            panic!("simulated panic while holding the project");
+        }).join();
+        assert!(result.is_err());
+
+        project.lock().set_tps(5);
+        assert_eq!(project.lock().tps(), 5);
+    }
+
+    #[test]
+    fn a_failed_save_leaves_the_original_intact() {
+        let dir = std::env::temp_dir().join(format!("logicrs-atomic-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("project.lrsproj");
+        fs::write(&path, "old").unwrap();
+
+        let saved = write_atomically(&path, b"new");
+        let after_save = fs::read_to_string(&path).unwrap();
+        let temp_left = dir.join(".project.lrsproj.tmp").exists();
+
+        // a directory where the temporary file should go makes the save fail before the rename
+        fs::create_dir(dir.join(".project.lrsproj.tmp")).unwrap();
+        let failed = write_atomically(&path, b"newer");
+        let after_failure = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(saved, Ok(()));
+        assert_eq!(after_save, "new");
+        assert!(!temp_left);
+        assert!(failed.is_err());
+        assert_eq!(after_failure, "new");
+    }
+
+    #[test]
+    fn seeded_runs_save_identical_bytes() {
+        let run = |seed: u64| {
+            Id::seed(seed);
+            let (project, _) = xor_project();
+            let mut data = Vec::new();
+            project.write_json(&mut data).unwrap();
+            data
+        };
+
+        // the hash maps of each run iterate in an order of their own, so equal bytes also rely on the sorted keys
+        assert_eq!(run(42), run(42));
+        assert_ne!(run(42), run(43));
+    }
+
+    // the module with one of the blocks of its circuit moved
+    fn altered(module: &Module) -> Module {
+        let mut module = module.clone();
+        let plot = module.plot_mut().unwrap();
+        let block_id = *plot.blocks().keys().next().unwrap();
+        let block = plot.get_block_mut(block_id).unwrap();
+        block.set_position(block.position() + Vector2(20, 0));
+        module
+    }
+
+    #[test]
+    fn pasting_an_equal_module_reuses_it() {
+        let (project, name) = xor_project();
+        let copied = project.modules()[&name].clone();
+        assert_eq!(project.paste_module(&copied, &[]), Ok((name, vec![])));
+    }
+
+    #[test]
+    fn pasting_a_differing_module_renames_the_copy() {
+        let (project, name) = xor_project();
+        let copied = altered(&project.modules()[&name]);
+
+        let (pasted, added) = project.paste_module(&copied, &[]).unwrap();
+        assert_eq!(pasted, format!("{name} 2"));
+        assert_eq!(added.iter().map(|module| module.name().clone()).collect::<Vec<_>>(), vec![pasted]);
+    }
+
+    #[test]
+    fn pasting_a_module_with_a_differing_dependency_renames_both() {
+        let (mut project, name) = xor_project();
+        let outer = String::from("Outer");
+        let mut module = Module::new(outer.clone(), 2, 1);
+        module.plot_mut().unwrap().add_block(Block::new(&&project.modules()[&name], Vector2(100, 100), None));
+        project.add_module(module.clone());
+
+        let dependency = altered(&project.modules()[&name]);
+        let (pasted, added) = project.paste_module(&module, &[dependency]).unwrap();
+        assert_eq!(pasted, format!("{outer} 2"));
+
+        let pasted_outer = added.iter().find(|module| module.name() == &pasted).unwrap();
+        let used = pasted_outer.plot().unwrap().blocks().values().map(|block| block.module_id().clone()).collect::<Vec<_>>();
+        assert_eq!(used, vec![format!("{name} 2")]);
+    }
+
+    // a project with nothing but the circuit of a Xor gate, simulated in isolation
+    fn isolated_xor() -> (Project, String) {
+        let mut project = Project::default();
+        let mut module = project.subcircuit_module(&String::from("Xor")).unwrap();
+        let name = module.name().clone();
+        module.set_isolated(true);
+        project.add_module(module);
+        (project, name)
+    }
+
+    #[test]
+    fn an_isolated_module_follows_its_truth_table() {
+        let (mut project, name) = isolated_xor();
+        let table = project.truth_table(&name, None).unwrap();
+        let (input_block, output_block) = project.modules()[&name].io_blocks().unwrap();
+
+        for (inputs, outputs) in table {
+            let plot = project.module_mut(&name).unwrap().plot_mut().unwrap();
+            plot.get_block_mut(input_block).unwrap().set_test_inputs(Some(inputs));
+            plot.add_block_to_update(input_block);
+
+            Simulator::settle(&mut project, 100).unwrap();
+            let plot = project.modules()[&name].plot().unwrap();
+            assert_eq!(plot.get_block(output_block).unwrap().bytes() & 1, outputs, "inputs {inputs:#b}");
+        }
+    }
+
+    #[test]
+    fn isolation_is_not_saved() {
+        let (project, name) = isolated_xor();
+        assert!(project.modules()[&name].isolated());
+
+        let mut data = Vec::new();
+        project.write_json(&mut data).unwrap();
+        let loaded: Project = serde_json::from_slice(&data).unwrap();
+        assert!(!loaded.modules()[&name].isolated());
+    }
+
+    #[test]
+    fn normalizing_ids_keeps_wiring_and_behavior() {
+        let (mut project, name) = xor_project();
+        let main_wiring = wiring(project.main_plot());
+        let module_wiring = wiring(project.modules()[&name].plot().unwrap());
+        let table = project.truth_table(&name, None).unwrap();
+
+        project.normalize_ids();
+        assert_eq!(wiring(project.main_plot()), main_wiring);
+        assert_eq!(wiring(project.modules()[&name].plot().unwrap()), module_wiring);
+        assert!(links_consistent(project.main_plot()));
+        assert!(links_consistent(project.modules()[&name].plot().unwrap()));
+        assert_eq!(project.truth_table(&name, None).unwrap(), table);
+    }
+
+    #[test]
+    fn normalizing_ids_twice_changes_nothing() {
+        let (mut project, _) = xor_project();
+        project.normalize_ids();
+        let ids = project.main_plot().blocks().keys().copied().collect::<BTreeSet<_>>();
+
+        project.normalize_ids();
+        assert_eq!(project.main_plot().blocks().keys().copied().collect::<BTreeSet<_>>(), ids);
+    }
+
+    #[test]
+    fn port_names_follow_the_nets_of_the_io_blocks() {
+        let (mut project, name) = xor_project();
+        let module = project.module_mut(&name).unwrap();
+        let (input_block, _) = module.io_blocks().unwrap();
+        let plot = module.plot_mut().unwrap();
+        let net = plot.get_block(input_block).unwrap().outputs()[0].unwrap();
+        plot.get_connection_mut(&net).unwrap().set_name(Some(String::from("a")));
+
+        assert_eq!(project.modules()[&name].port_names(), (vec![Some(String::from("a")), None], vec![None]));
+        assert_eq!(project.modules()[&String::from("And")].port_names(), (vec![None, None], vec![None]));
+    }
+
+    // writes the project in `format` to a temporary file and loads it back
+    fn round_trip(project: &mut Project, format: SaveFormat) -> Project {
+        project.set_save_format(format);
+        // tests run in parallel, each on a thread of its own
+        let thread = format!("{:?}", std::thread::current().id()).replace(|c: char| !c.is_ascii_digit(), "");
+        let path = std::env::temp_dir().join(format!("logicrs-{format:?}-{}-{thread}.lrsproj", std::process::id()));
+        let file = gio::File::for_path(&path);
+        project.write_to(&file).unwrap();
+        let loaded = Project::load_from(&file).unwrap();
+        let _ = fs::remove_file(&path);
+        loaded
+    }
+
+    #[test]
+    fn binary_files_load_back_as_they_were_saved() {
+        let (mut project, _) = xor_project();
+        let loaded = round_trip(&mut project, SaveFormat::Binary);
+        assert_eq!(loaded.save_format(), SaveFormat::Binary);
+        assert_eq!(serde_json::to_value(&loaded).unwrap(), serde_json::to_value(&project).unwrap());
+    }
+
+    #[test]
+    fn compressed_files_load_back_as_they_were_saved() {
+        let (mut project, _) = xor_project();
+        let loaded = round_trip(&mut project, SaveFormat::CompressedJson);
+        assert_eq!(loaded.save_format(), SaveFormat::CompressedJson);
+        assert_eq!(serde_json::to_value(&loaded).unwrap(), serde_json::to_value(&project).unwrap());
+    }
+
+    #[test]
+    fn uncompressed_files_still_load() {
+        let (mut project, _) = xor_project();
+        let loaded = round_trip(&mut project, SaveFormat::Json);
+        assert_eq!(loaded.save_format(), SaveFormat::Json);
+        assert_eq!(serde_json::to_value(&loaded).unwrap(), serde_json::to_value(&project).unwrap());
+    }
+
+    #[test]
+    fn instances_of_a_module_that_gained_a_pin_are_flagged_on_load() {
+        let mut project = Project::default();
+        let name = String::from("Half Adder");
+        project.add_module(Module::new(name.clone(), 2, 2));
+        let instance = Block::new(&&project.modules()[&name], Vector2(100, 100), None);
+        let instance_id = instance.id();
+        project.main_plot_mut().add_block(instance);
+
+        // the module as it was changed elsewhere, with a carry input
+        project.add_existing_module(Module::new(name.clone(), 3, 2));
+        let mut loaded = round_trip(&mut project, SaveFormat::Json);
+
+        assert_eq!(loaded.main_plot().pin_mismatches(&loaded), vec![(instance_id, 3, 2)]);
+        assert!(validation::validate(&loaded).iter().any(|diagnostic| diagnostic.is_fatal() && diagnostic.location() == Some(&(PlotDescriptor::Main(), instance_id))));
+
+        loaded.main_plot_mut().fit_pins(instance_id, 3, 2);
+        assert_eq!(loaded.main_plot().get_block(instance_id).unwrap().inputs().len(), 3);
+        assert!(loaded.main_plot().pin_mismatches(&loaded).is_empty());
+    }
+
+    // drops every `key` from the json, as in files saved before the field existed
+    fn without_key(value: &mut serde_json::Value, key: &str) {
+        match value {
+            serde_json::Value::Object(map) => {
+                map.remove(key);
+                map.values_mut().for_each(|value| without_key(value, key));
+            }
+            serde_json::Value::Array(values) => values.iter_mut().for_each(|value| without_key(value, key)),
+            _ => ()
+        }
+    }
+
+    #[test]
+    fn styled_wires_round_trip_byte_for_byte() {
+        Id::seed(7);
+        let (mut project, _) = xor_project();
+        let connection_id = *project.main_plot().connections().keys().next().unwrap();
+        let plot = project.main_plot_mut();
+        let segments = routing::Router::new(plot).route_connection(plot, plot.get_connection(&connection_id).unwrap());
+        let connection = plot.get_connection_mut(&connection_id).unwrap();
+        if let Some(segments) = segments {
+            connection.set_segments(segments);
+        }
+        connection.set_style(Some(WireStyle::Orthogonal));
+        connection.set_color(Some((0.8, 0.2, 0.2, 1.)));
+        connection.set_name(Some(String::from("carry")));
+
+        let mut saved = Vec::new();
+        project.write_json(&mut saved).unwrap();
+        let loaded: Project = serde_json::from_slice(&saved).unwrap();
+        let mut saved_again = Vec::new();
+        loaded.write_json(&mut saved_again).unwrap();
+        assert_eq!(saved, saved_again);
+
+        let connection = loaded.main_plot().get_connection(&connection_id).unwrap();
+        assert_eq!(connection.style(), Some(WireStyle::Orthogonal));
+        assert_eq!(connection.color(), &Some((0.8, 0.2, 0.2, 1.)));
+
+        let mut older = serde_json::from_slice::<serde_json::Value>(&saved).unwrap();
+        without_key(&mut older, "style");
+        let loaded: Project = serde_json::from_value(older).unwrap();
+        assert_eq!(loaded.main_plot().get_connection(&connection_id).unwrap().style(), None);
+    }
+}
@@ -4,9 +4,13 @@ use super::*;
 use gtk::cairo::{
     Context,
     Antialias,
-    Error, FontFace
+    Error, FontFace,
+    ImageSurface, Format
 };
 
+// empty space left around the circuit when rendering to an image
+const IMAGE_PADDING: i32 = 20;
+
 pub struct CairoRenderer {
     size: Vector2<i32>,
     scale: f64,
@@ -14,7 +18,16 @@ pub struct CairoRenderer {
     original_translation: Vector2<f64>,
     font: FontFace,
     context: Option<Context>,
-    editor_mode: EditorMode
+    editor_mode: EditorMode,
+    wire_style: WireStyle,
+    net_colors: bool,
+    connection_counts: bool,
+    // the logic value of every net written next to it, for exported snapshots
+    value_labels: bool,
+    // no signal values and only straight wires, for huge circuits
+    lite: bool,
+    // everything but the selection and its neighbors faded
+    focus: bool
 }
 
 impl CairoRenderer {
@@ -26,6 +39,12 @@ impl CairoRenderer {
             original_translation: Vector2::default(),
             context: None,
             editor_mode: EditorMode::default(),
+            wire_style: WireStyle::default(),
+            net_colors: false,
+            connection_counts: false,
+            value_labels: false,
+            lite: false,
+            focus: false,
             font: FontFace::toy_create("Cascadia Code", gtk::cairo::FontSlant::Normal, gtk::cairo::FontWeight::Normal).unwrap()
         }
     }
@@ -46,6 +65,42 @@ impl CairoRenderer {
         self.original_translation = self.translation;
         self
     }
+
+    // draws the area between `start` and `end` of the plot onto a new image, `scale` pixels per unit
+    pub fn render_image(&mut self, plot: &Plot, (start, end): (Vector2<i32>, Vector2<i32>), transparent: bool, scale: f64) -> Result<ImageSurface, Error> {
+        let area = end - start + Vector2(IMAGE_PADDING * 2, IMAGE_PADDING * 2);
+        let size = Vector2((area.0 as f64 * scale).ceil() as i32, (area.1 as f64 * scale).ceil() as i32);
+        let surface = ImageSurface::create(Format::ARgb32, size.0, size.1)?;
+        let context = Context::new(&surface)?;
+
+        self.set_size(size).set_scale(scale);
+        self.translate(Vector2((IMAGE_PADDING - start.0) as f64 * scale, (IMAGE_PADDING - start.1) as f64 * scale));
+        self.set_context(Some(context.clone()));
+
+        context.set_antialias(Antialias::Default);
+        context.translate(self.translation.x(), self.translation.y());
+        context.scale(scale, scale);
+        context.set_font_face(&self.font);
+        context.set_font_size(DEFAULT_FONT_SIZE);
+
+        if !transparent {
+            paint_background(&context, plot)?;
+        }
+
+        let result = plot.render(self, plot);
+        self.set_context(None);
+        drop(context);
+        result?;
+
+        surface.flush();
+        Ok(surface)
+    }
+}
+
+fn paint_background(context: &Context, plot: &Plot) -> Result<(), Error> {
+    let (r, g, b, _) = plot.background().unwrap_or(unsafe { COLOR_THEME.bg_color });
+    context.set_source_rgb(r as f64, g as f64, b as f64);
+    context.paint()
 }
 
 impl Default for CairoRenderer {
@@ -72,9 +127,7 @@ impl Renderer for CairoRenderer {
         context.set_font_size(DEFAULT_FONT_SIZE);
 
         // fill background
-        let (bg_color_r, bg_color_g, bg_color_b, _) = unsafe { COLOR_THEME.bg_color };
-        context.set_source_rgb(bg_color_r as f64, bg_color_g as f64, bg_color_b as f64);
-        context.paint()?;
+        paint_background(context, plot)?;
 
         // draw the editor grid if enabled
         mode.render(self, plot)?;
@@ -228,4 +281,64 @@ impl Renderer for CairoRenderer {
     fn editor_mode(&self) -> EditorMode {
         self.editor_mode
     }
+
+    #[inline]
+    fn set_wire_style(&mut self, style: WireStyle) {
+        self.wire_style = style;
+    }
+
+    #[inline]
+    fn wire_style(&self) -> WireStyle {
+        self.wire_style
+    }
+
+    #[inline]
+    fn set_net_colors(&mut self, enabled: bool) {
+        self.net_colors = enabled;
+    }
+
+    #[inline]
+    fn net_colors(&self) -> bool {
+        self.net_colors
+    }
+
+    #[inline]
+    fn set_connection_counts(&mut self, enabled: bool) {
+        self.connection_counts = enabled;
+    }
+
+    #[inline]
+    fn connection_counts(&self) -> bool {
+        self.connection_counts
+    }
+
+    #[inline]
+    fn set_value_labels(&mut self, enabled: bool) {
+        self.value_labels = enabled;
+    }
+
+    #[inline]
+    fn value_labels(&self) -> bool {
+        self.value_labels
+    }
+
+    #[inline]
+    fn set_lite(&mut self, enabled: bool) {
+        self.lite = enabled;
+    }
+
+    #[inline]
+    fn lite(&self) -> bool {
+        self.lite
+    }
+
+    #[inline]
+    fn set_focus(&mut self, enabled: bool) {
+        self.focus = enabled;
+    }
+
+    #[inline]
+    fn focus(&self) -> bool {
+        self.focus
+    }
 }
@@ -32,6 +32,31 @@ pub const fn hex_to_color(hex: Hex) -> (f32, f32, f32, f32) {
 
 pub static mut COLOR_THEME: Theme = Theme::DARK;
 
+// palette used to color named nets
+pub const NET_COLORS: [Color; 8] = [
+    hex_to_color(0xff3584e4),
+    hex_to_color(0xffe66100),
+    hex_to_color(0xff2ec27e),
+    hex_to_color(0xffe01b24),
+    hex_to_color(0xff9141ac),
+    hex_to_color(0xfff5c211),
+    hex_to_color(0xff1a5fb4),
+    hex_to_color(0xff865e3c),
+];
+
+// tints laid over blocks with validation problems
+pub const ERROR_TINT: Color = hex_to_color(0x59e01b24);
+pub const WARNING_TINT: Color = hex_to_color(0x59e5a50a);
+
+// wires and driver markers of nets with more than one driver
+pub const CONFLICT_COLOR: Color = hex_to_color(0xffe01b24);
+
+// picks a stable palette color for a net name (FNV-1a, so it doesn't change between runs)
+pub fn net_color(name: &str) -> Color {
+    let hash = name.bytes().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    NET_COLORS[(hash % NET_COLORS.len() as u64) as usize]
+}
+
 #[derive(Copy, Clone)]
 pub struct Theme {
     // base colors
@@ -5,7 +5,7 @@ pub mod color;
 pub mod vector;
 
 pub use {cairo::*, color::*};
-use crate::{simulator::Plot, application::editor::EditorMode};
+use crate::{simulator::Plot, application::editor::{EditorMode, WireStyle}};
 
 use self::vector::*;
 
@@ -40,6 +40,18 @@ pub trait Renderer: Default {
     fn set_font_size(&self, size: f64) -> &Self;
     fn set_editor_mode(&mut self, mode: EditorMode);
     fn editor_mode(&self) -> EditorMode;
+    fn set_wire_style(&mut self, style: WireStyle);
+    fn wire_style(&self) -> WireStyle;
+    fn set_net_colors(&mut self, enabled: bool);
+    fn net_colors(&self) -> bool;
+    fn set_connection_counts(&mut self, enabled: bool);
+    fn connection_counts(&self) -> bool;
+    fn set_value_labels(&mut self, enabled: bool);
+    fn value_labels(&self) -> bool;
+    fn set_lite(&mut self, enabled: bool);
+    fn lite(&self) -> bool;
+    fn set_focus(&mut self, enabled: bool);
+    fn focus(&self) -> bool;
 
     fn screen_space(&self) -> ScreenSpace {
         Vector2(
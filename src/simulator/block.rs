@@ -32,7 +32,20 @@ pub struct Block {
     output_state: u128,
     
     decoration: Decoration,
-    color: Option<Color>
+    color: Option<Color>,
+
+    // disabled blocks stay in the plot but are not simulated and leave their outputs undriven
+    #[serde(default)]
+    disabled: bool,
+
+    // copied from the module so instances can label their pins
+    #[serde(default)]
+    pin_names: PinNames,
+
+    // set on a module's input block while the module is simulated in isolation, one bit per pin;
+    // not saved, so a loaded module takes its inputs from its instances again
+    #[serde(skip)]
+    test_inputs: Option<u128>
 }
 
 impl Identifiable for Block {
@@ -41,6 +54,7 @@ impl Identifiable for Block {
 
 impl Block {
     pub const MAX_CONNECTIONS: u8 = 128;
+    pub const PIN_RADIUS: i32 = 6;
 
     pub fn new_sized(module: &&Module, position: Vector2<i32>, unique: bool, num_inputs: u8, num_outputs: u8, color: Option<Color>) -> Self {
         let name = module.name().clone();
@@ -60,7 +74,10 @@ impl Block {
             state: if module.builtin() { State::Direct(0) } else { State::Inherit(PlotState::default()) },
             decoration: module.decoration().clone(),
             color,
-            output_state: 0
+            output_state: 0,
+            disabled: false,
+            pin_names: module.pin_names(),
+            test_inputs: None
         }
     }
 
@@ -68,6 +85,18 @@ impl Block {
         Self::new_sized(module, position, false, module.get_num_inputs(), module.get_num_outputs(), color)
     }
 
+    // switches the block to a pin-compatible module, keeping its id, position and connections
+    pub fn swap_module(&mut self, module: &Module) {
+        let mut block = Self::new(&module, self.position, self.color.clone());
+        block.id = self.id;
+        block.unique = self.unique;
+        block.highlighted = self.highlighted;
+        block.disabled = self.disabled;
+        block.inputs = std::mem::take(&mut self.inputs);
+        block.outputs = std::mem::take(&mut self.outputs);
+        *self = block;
+    }
+
     pub fn set_color(&mut self, mut color: Option<Color>) {
         if let Some(c) = color && c == unsafe { COLOR_THEME.border_color } {
             color = None
@@ -88,6 +117,14 @@ impl Block {
         self.unique
     }
 
+    pub fn disabled(&self) -> bool {
+        self.disabled
+    }
+
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.disabled = disabled;
+    }
+
     pub fn id(&self) -> BlockID {
         self.id
     }
@@ -100,18 +137,34 @@ impl Block {
         &self.name
     }
 
+    pub fn set_module_id(&mut self, module_id: String) {
+        self.name = module_id;
+    }
+
+    // the area the block covers on screen: its body plus the pins sticking out of either side
+    pub fn bounds(&self) -> (Vector2<i32>, Vector2<i32>) {
+        let left = if self.inputs.is_empty() { 0 } else { Self::PIN_RADIUS };
+        let right = if self.outputs.is_empty() { 0 } else { Self::PIN_RADIUS };
+        (
+            Vector2(self.position.0 - left, self.position.1),
+            Vector2(self.position.0 + self.size.0 + right, self.position.1 + self.size.1)
+        )
+    }
+
     pub fn is_in_area(&self, area: &Vector2<Vector2<f64>>) -> bool {
+        let (min, max) = self.bounds();
         !(
-            self.position.0 > area.1.0 as i32 || 
-            self.position.1 > area.1.1 as i32 ||
-            self.position.0 + self.size.0 < area.0.0 as i32 || 
-            self.position.1 + self.size.1 < area.0.1 as i32
+            min.0 > area.1.0 as i32 || 
+            min.1 > area.1.1 as i32 ||
+            max.0 < area.0.0 as i32 || 
+            max.1 < area.0.1 as i32
         )
     }
 
     pub fn touches(&self, point: Vector2<i32>) -> bool {
-        point.0 > self.position.0 - 3 && point.0 < self.position.0 + self.size.0 + 3 &&
-        point.1 > self.position.1 - 3 && point.1 < self.position.1 + self.size.1 + 3
+        let (min, max) = self.bounds();
+        point.0 > min.0 - 3 && point.0 < max.0 + 3 &&
+        point.1 > min.1 - 3 && point.1 < max.1 + 3
     }
 
     pub fn set_highlighted(&mut self, highlighted: bool) {
@@ -149,6 +202,21 @@ impl Block {
         }
     }
 
+    // grows or shrinks the pins to match a changed module, dropping the references of removed pins
+    pub fn set_pin_counts(&mut self, num_inputs: u8, num_outputs: u8) {
+        self.inputs.resize(num_inputs as usize, None);
+        self.outputs.resize(num_outputs as usize, None);
+        self.size.1 = cmp::max(num_inputs, num_outputs) as i32 * 25 + 50;
+    }
+
+    pub fn pin_names(&self) -> &PinNames {
+        &self.pin_names
+    }
+
+    pub fn set_pin_names(&mut self, pin_names: PinNames) {
+        self.pin_names = pin_names;
+    }
+
     pub fn set_connection(&mut self, connector: Connector, connection: Option<ConnectionID>) -> &mut Self {
         match connector {
             Connector::Input(index) => self.inputs[index as usize] = connection,
@@ -188,6 +256,11 @@ impl Block {
         self.decoration.set_active(is_active)
     }
 
+    // everything about the block that changes while simulating: its output connectors and decoration
+    pub fn displayed_state(&self) -> (u128, bool) {
+        (self.output_state, self.is_active())
+    }
+
     #[inline(always)]
     pub fn state(&self) -> &State {
         &self.state
@@ -219,7 +292,21 @@ impl Block {
         self.passthrough
     }
 
+    pub fn test_inputs(&self) -> Option<u128> {
+        self.test_inputs
+    }
+
+    pub fn set_test_inputs(&mut self, test_inputs: Option<u128>) {
+        self.test_inputs = test_inputs;
+    }
+
     pub fn on_mouse_press(&mut self, mut position: Vector2<i32>) -> bool {
+        // the input pins turn into toggles while their module is simulated in isolation
+        if let Some(bits) = self.test_inputs && let Some(i) = self.position_on_connection(position, true) {
+            self.test_inputs = Some(bits ^ (1 << i));
+            return true;
+        }
+
         position -= self.position;
         if position.0 > 15 && position.1 > 25 && 
             position.0 < self.size.0 - 15 && position.1 < self.size.1 - 10 {
@@ -234,6 +321,10 @@ impl Block {
         self.decoration.on_mouse_release();
     }
 
+    pub fn clickable(&self) -> bool {
+        self.decoration.clickable()
+    }
+
     pub fn position_on_connection(&self, position: Vector2<i32>, is_input: bool) -> Option<u8> {
         if is_input {
             for i in 0..self.inputs.len() {
@@ -254,38 +345,44 @@ impl Block {
         None
     }
 
-    pub fn simulate(&mut self, connections: &mut HashMap<ConnectionID, Connection>, to_update: &mut HashSet<BlockID>, queued: &mut HashSet<BlockID>, project: &mut Project, call_stack: &mut HashSet<String>) -> SimResult<()> {
+    // simulates the block and returns if its outputs changed
+    pub fn simulate(&mut self, connections: &mut HashMap<ConnectionID, Connection>, to_update: &mut HashSet<BlockID>, queued: &mut HashSet<BlockID>, project: &mut Project, call_stack: &mut HashSet<String>) -> SimResult<bool> {
         // collect input states
         let inputs = self.inputs.collect(connections);
+        let previous_state = self.output_state;
     
         let mut_ref_ptr = project as *mut Project;
-        if let Some(module) = project.module_mut(&self.name) {
+        if self.disabled {
+            self.output_state = 0;
+        }
+        else if let Some(module) = project.module_mut(&self.name) {
             // simulate the block
             self.output_state = module.simulate(inputs, self, unsafe { &mut *mut_ref_ptr }, call_stack)?;
+        }
+        else {
+            error!("no module named {} found", self.name);
+            return Ok(false);
+        }
 
-            // dissect output state
-            for (i, connection_id) in self.outputs.iter().enumerate() {
-                if let Some(connection) = connection_id.map(|connection_id| connections.get_mut(&connection_id)).flatten() {
-                    let active = (self.output_state >> i as u128) & 1 != 0;
-                    if active != connection.is_active() {
-                        for dest_id in connection.destinations().iter().map(|dest| dest.block_id()) {
-                            if dest_id == self.id {
-                                queued.insert(dest_id);
-                            }
-                            else {
-                                to_update.insert(dest_id);
-                            }
+        // dissect output state
+        for (i, connection_id) in self.outputs.iter().enumerate() {
+            if let Some(connection) = connection_id.map(|connection_id| connections.get_mut(&connection_id)).flatten() {
+                let active = (self.output_state >> i as u128) & 1 != 0;
+                if active != connection.is_active() {
+                    for dest_id in connection.destinations().iter().map(|dest| dest.block_id()) {
+                        if dest_id == self.id {
+                            queued.insert(dest_id);
+                        }
+                        else {
+                            to_update.insert(dest_id);
                         }
-                        connection.set_active(active);
                     }
+                    connection.set_active(active);
                 }
             }
         }
-        else {
-            error!("no module named {} found", self.name);
-        }
 
-        Ok(())
+        Ok(self.output_state != previous_state)
     }
 }
 
@@ -318,7 +415,7 @@ impl Renderable for Block {
         let show_suggestion = plot.selection().connecting();
         let connector = |position, is_input, is_active|
             renderer
-                .arc(position, 6., 0., f64::consts::TAU)
+                .arc(position, Self::PIN_RADIUS as f64, 0., f64::consts::TAU)
                 .set_color(unsafe {if show_suggestion && is_input { &COLOR_THEME.suggestion_fg_color } else if is_active { &COLOR_THEME.enabled_fg_color } else { &COLOR_THEME.disabled_fg_color }} )
                 .fill_preserve()?
                 .set_color(unsafe {if self.highlighted { &COLOR_THEME.accent_fg_color } else { border_color }})
@@ -330,9 +427,77 @@ impl Renderable for Block {
         }
 
         for (i, _) in self.outputs.iter().enumerate().filter(|(_, c)| c.is_none()) {
-            connector(Vector2(self.position.0 + self.size.0, self.position.1 + 25 * i as i32 + 50), false, (self.output_state >> i as u128) & 1 != 0)?;
+            connector(Vector2(self.position.0 + self.size.0, self.position.1 + 25 * i as i32 + 50), false, !renderer.lite() && (self.output_state >> i as u128) & 1 != 0)?;
+        }
+
+        if let Some(bits) = self.test_inputs {
+            for i in 0..self.inputs.len() {
+                let on = !renderer.lite() && (bits >> i) & 1 != 0;
+                renderer.rounded_rect(Vector2(self.position.0 - 6, self.position.1 + 25 * i as i32 + 44), Vector2(12, 12), 3)
+                    .set_color(unsafe { if on { &COLOR_THEME.enabled_fg_color } else { &COLOR_THEME.disabled_fg_color } })
+                    .fill_preserve()?
+                    .set_color(unsafe { if self.highlighted { &COLOR_THEME.accent_fg_color } else { border_color } })
+                    .stroke()?;
+            }
+        }
+
+        self.decoration.render(renderer, self)?;
+
+        if self.pin_names.0.iter().chain(self.pin_names.1.iter()).any(Option::is_some) {
+            renderer.set_font_size(11.).set_color(unsafe { &COLOR_THEME.block_fg_color });
+            for (i, name) in self.pin_names.0.iter().enumerate().take(self.inputs.len()) {
+                if let Some(name) = name {
+                    renderer.move_to(Vector2(self.position.0 + 10, self.position.1 + 25 * i as i32 + 54))
+                        .show_text(name)?;
+                }
+            }
+            for (i, name) in self.pin_names.1.iter().enumerate().take(self.outputs.len()) {
+                if let Some(name) = name {
+                    renderer.move_to(Vector2(self.position.0 + self.size.0 - 10 - name.len() as i32 * 7, self.position.1 + 25 * i as i32 + 54))
+                        .show_text(name)?;
+                }
+            }
+            renderer.set_font_size(DEFAULT_FONT_SIZE);
+        }
+
+        // grey out disabled blocks
+        if self.disabled {
+            let mut shade = unsafe { COLOR_THEME.bg_color };
+            shade.3 = 0.6;
+            renderer.rounded_rect(self.position, self.size, 5)
+                .set_color(&shade)
+                .fill()?;
+        }
+
+        if let Some(severity) = plot.flagged(self.id) {
+            renderer.rounded_rect(self.position, self.size, 5)
+                .set_color(match severity {
+                    validation::Severity::Error => &ERROR_TINT,
+                    validation::Severity::Warning => &WARNING_TINT
+                })
+                .fill()?;
         }
 
-        self.decoration.render(renderer, self).map(|_| ())
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_cover_the_body_and_the_pins() {
+        let mut block = Block::new(&builtin::BUILTINS["And"].module(), Vector2(100, 100), None);
+        let size = block.size();
+        assert_eq!(block.bounds(), (Vector2(100 - Block::PIN_RADIUS, 100), Vector2(100 + size.0 + Block::PIN_RADIUS, 100 + size.1)));
+        assert!(block.touches(Vector2(100 - Block::PIN_RADIUS + 1, 150)));
+
+        // without inputs nothing sticks out on the left
+        block.set_pin_counts(0, 1);
+        assert_eq!(block.bounds(), (Vector2(100, 100), Vector2(100 + size.0 + Block::PIN_RADIUS, 175)));
+
+        block.set_pin_counts(4, 1);
+        assert_eq!(block.bounds(), (Vector2(100 - Block::PIN_RADIUS, 100), Vector2(100 + size.0 + Block::PIN_RADIUS, 250)));
     }
-}
\ No newline at end of file
+}
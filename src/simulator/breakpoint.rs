@@ -0,0 +1,79 @@
+use crate::project::Project;
+use super::*;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    #[default]
+    Rising,
+    Falling,
+    Change
+}
+
+impl Condition {
+    pub const ALL: [Self; 3] = [Self::Rising, Self::Falling, Self::Change];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Rising => "goes high",
+            Self::Falling => "goes low",
+            Self::Change => "changes"
+        }
+    }
+
+    fn holds(&self, before: bool, after: bool) -> bool {
+        match self {
+            Self::Rising => !before && after,
+            Self::Falling => before && !after,
+            Self::Change => before != after
+        }
+    }
+}
+
+// pauses the simulation once a net meets its condition
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakpoint {
+    plot: PlotDescriptor,
+    connection: ConnectionID,
+    condition: Condition
+}
+
+impl Breakpoint {
+    pub fn new(plot: PlotDescriptor, connection: ConnectionID, condition: Condition) -> Self {
+        Self {
+            plot,
+            connection,
+            condition
+        }
+    }
+
+    pub fn plot(&self) -> &PlotDescriptor {
+        &self.plot
+    }
+
+    pub fn connection(&self) -> ConnectionID {
+        self.connection
+    }
+
+    pub fn set_connection(&mut self, connection: ConnectionID) {
+        self.connection = connection;
+    }
+
+    pub fn condition(&self) -> Condition {
+        self.condition
+    }
+
+    // current value of the watched net, `None` if it no longer exists
+    pub fn state(&self, project: &Project) -> Option<bool> {
+        project.plot_by_descriptor(&self.plot)
+            .and_then(|plot| plot.get_connection(&self.connection))
+            .map(|connection| connection.is_active())
+    }
+
+    pub fn triggered(&self, before: Option<bool>, after: Option<bool>) -> bool {
+        matches!((before, after), (Some(before), Some(after)) if self.condition.holds(before, after))
+    }
+
+    pub fn describe(&self, project: &Project) -> String {
+        format!("{} {}", project.describe_net(&self.plot, &self.connection), self.condition.label())
+    }
+}
@@ -97,7 +97,12 @@ lazy_static! {
 
         builtins.insert("Input", Builtin::new(
             Module::new_builtin("Input", Category::Hidden, Block::MAX_CONNECTIONS, Block::MAX_CONNECTIONS, Decoration::Label("|>".to_string())),
-            |input, instance| if instance.passthrough() { input } else { instance.bytes() }
+            |input, instance| match instance.test_inputs() {
+                // simulated on its own in isolation, driven by the toggles
+                Some(bits) if instance.passthrough() => bits,
+                _ if instance.passthrough() => input,
+                _ => instance.bytes()
+            }
         ));
 
         builtins.insert("Output", Builtin::new(
@@ -125,6 +130,11 @@ lazy_static! {
             t_flip_flop
         ));
 
+        builtins.insert("T Flip-Flop with Set/Reset", Builtin::new(
+            Module::new_builtin("T Flip-Flop with Set/Reset", Category::FlipFlop, 3, 1, Decoration::Label("T SR".to_string())),
+            t_flip_flop_set_reset
+        ));
+
         builtins
     };
 }
@@ -179,3 +189,95 @@ fn t_flip_flop(input: u128, instance: &mut Block) -> u128 {
     instance.set_bytes((instance.bytes() & !0b10) | (input << 1));
     instance.bytes() & 1
 }
+
+// like `t_flip_flop`, with set (S) and reset (R) inputs that act at once, whatever the clock does
+fn t_flip_flop_set_reset(input: u128, instance: &mut Block) -> u128 {
+    let clock = input & 1;
+
+    // reset wins over set, and both win over the clock
+    if input & 0b100 > 0 {
+        instance.set_bytes(0);
+    }
+    else if input & 0b10 > 0 {
+        instance.set_bytes(1);
+    }
+    else if clock > 0 && instance.bytes() & 0b10 == 0 {
+        instance.set_bytes(instance.bytes() ^ 1);
+    }
+
+    // the clock is still tracked while forced, so releasing set or reset does not look like an edge
+    instance.set_bytes((instance.bytes() & 1) | (clock << 1));
+    instance.bytes() & 1
+}
+
+// where a gate of an equivalent circuit takes one of its inputs from
+#[derive(Debug, Clone, Copy)]
+pub enum GateSource {
+    Input(u8),
+    Gate(usize)
+}
+
+// the gate as And, Or and Not gates, each taking its inputs from the circuit's inputs or earlier gates;
+// the last gate drives the output
+pub fn equivalent_circuit(name: &str) -> Option<Vec<(&'static str, Vec<GateSource>)>> {
+    use GateSource::*;
+
+    let xor = vec![
+        ("Or", vec![Input(0), Input(1)]),
+        ("And", vec![Input(0), Input(1)]),
+        ("Not", vec![Gate(1)]),
+        ("And", vec![Gate(0), Gate(2)])
+    ];
+
+    Some(match name {
+        "And" => vec![("And", vec![Input(0), Input(1)])],
+        "Or" => vec![("Or", vec![Input(0), Input(1)])],
+        "Not" => vec![("Not", vec![Input(0)])],
+        "Nand" => vec![("And", vec![Input(0), Input(1)]), ("Not", vec![Gate(0)])],
+        "Nor" => vec![("Or", vec![Input(0), Input(1)]), ("Not", vec![Gate(0)])],
+        "Xor" => xor,
+        "Xnor" => {
+            let mut xnor = xor;
+            xnor.push(("Not", vec![Gate(3)]));
+            xnor
+        }
+        _ => return None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::vector::Vector2;
+
+    const CLOCK: u128 = 0b001;
+    const SET: u128 = 0b010;
+    const RESET: u128 = 0b100;
+
+    fn flip_flop() -> Block {
+        Block::new(&BUILTINS["T Flip-Flop with Set/Reset"].module(), Vector2(0, 0), None)
+    }
+
+    #[test]
+    fn reset_clears_q_without_a_clock_edge() {
+        let mut instance = flip_flop();
+        assert_eq!(t_flip_flop_set_reset(CLOCK, &mut instance), 1);
+
+        // the clock stays high, there is no edge to toggle on
+        assert_eq!(t_flip_flop_set_reset(CLOCK | RESET, &mut instance), 0);
+        assert_eq!(t_flip_flop_set_reset(CLOCK, &mut instance), 0);
+    }
+
+    #[test]
+    fn set_drives_q_high_without_a_clock_edge() {
+        let mut instance = flip_flop();
+        assert_eq!(t_flip_flop_set_reset(SET, &mut instance), 1);
+        assert_eq!(t_flip_flop_set_reset(0, &mut instance), 1);
+    }
+
+    #[test]
+    fn reset_wins_over_set() {
+        let mut instance = flip_flop();
+        assert_eq!(t_flip_flop_set_reset(SET | RESET, &mut instance), 0);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{renderer::{*, vector::*}, id::Id, application::editor::EditorMode};
+use crate::{renderer::{*, vector::*}, id::Id, application::editor::{EditorMode, WireStyle}};
 use super::*;
 use serde::{Serialize, Deserialize};
 use std::f64;
@@ -121,21 +121,66 @@ impl Segment {
         *self = Self::Block(block_id, port)
     }
 
-    fn render<R>(&self, active: bool, start: Vector2<i32>, renderer: &R, plot: &Plot) -> Result<(), R::Error>
+    // where the line leading into this segment ends
+    fn end_position(&self, plot: &Plot) -> Option<Vector2<i32>> {
+        match self {
+            Self::Block(block_id, port) => plot.get_block(*block_id).map(|block| block.get_connector_pos(Connector::Input(*port))),
+            Self::Waypoint(_, position, _) => Some(*position)
+        }
+    }
+
+    // drops waypoints that lie on a straight line between their neighbours, returns how many were removed
+    fn simplify(segments: &mut HashMap<Id, Segment>, start: Vector2<i32>, plot: &Plot) -> usize {
+        let mut removed = 0;
+        let ids = segments.keys().copied().collect::<Vec<_>>();
+        for id in ids {
+            while let Some(Self::Waypoint(children, position, _)) = segments.get(&id) && children.len() == 1 {
+                let child = children.values().next().unwrap();
+                match child.end_position(plot) {
+                    Some(end) if is_collinear(start, *position, end) => {
+                        let child = child.clone();
+                        segments.insert(id, child);
+                        removed += 1;
+                    }
+                    _ => break
+                }
+            }
+
+            if let Some(Self::Waypoint(children, position, _)) = segments.get_mut(&id) {
+                let position = *position;
+                removed += Self::simplify(children, position, plot);
+            }
+        }
+        removed
+    }
+
+    // summed straight-line length of this segment and everything branching off it
+    fn length(&self, start: Vector2<i32>, plot: &Plot) -> f64 {
+        let Some(end) = self.end_position(plot) else {
+            return 0.;
+        };
+        let own = (((end.0 - start.0) as f64).powi(2) + ((end.1 - start.1) as f64).powi(2)).sqrt();
+        match self {
+            Self::Waypoint(segments, ..) => own + segments.values().map(|segment| segment.length(end, plot)).sum::<f64>(),
+            _ => own
+        }
+    }
+
+    fn render<R>(&self, active: bool, color: &Color, style: WireStyle, start: Vector2<i32>, renderer: &R, plot: &Plot) -> Result<(), R::Error>
         where R: Renderer
     {
         match self {
             Self::Block(block_id, port) => {
                 let end_block = plot.get_block(*block_id).unwrap();
                 let end = end_block.get_connector_pos(Connector::Input(*port));
-                render_line(active, start, end, renderer)?;
+                render_colored_line(color, style, start, end, renderer)?;
                 render_block_connector(end, active, end_block.highlighted(), renderer)
             },
             Self::Waypoint(segments, position, highlighted) => {
-                render_line(active, start, *position, renderer)?;
+                render_colored_line(color, style, start, *position, renderer)?;
 
                 for segment in segments.values() {
-                    segment.render(active, *position, renderer, plot)?;
+                    segment.render(active, color, style, *position, renderer, plot)?;
                 }
 
                 render_waypoint(*position, active, *highlighted, renderer)
@@ -179,6 +224,25 @@ impl Segment {
         }
     }
 
+    // renumbers a set of branches in a fixed order, pointing them at the renumbered blocks
+    fn remap_ids(segments: HashMap<Id, Segment>, blocks: &HashMap<BlockID, BlockID>) -> HashMap<Id, Segment> {
+        let mut segments = segments.into_values()
+            .map(|segment| match segment {
+                Self::Block(block_id, port) => Self::Block(blocks.get(&block_id).copied().unwrap_or(block_id), port),
+                Self::Waypoint(branches, position, _) => Self::Waypoint(Self::remap_ids(branches, blocks), position, false)
+            })
+            .collect::<Vec<_>>();
+        segments.sort_by_key(|segment| match segment {
+            Self::Block(block_id, port) => (Some((*block_id, *port)), None),
+            Self::Waypoint(_, position, _) => (None, Some((position.0, position.1)))
+        });
+
+        segments.into_iter()
+            .enumerate()
+            .map(|(index, segment)| (Id::from_index(index), segment))
+            .collect()
+    }
+
     fn touches(&self, point: Vector2<i32>) -> bool {
         match self {
             Self::Waypoint(_, position, _) => {
@@ -189,6 +253,18 @@ impl Segment {
         }
     }
 
+    // checks if the line leading into this segment, or any line after it, passes close to `point`
+    fn line_touches(&self, start: Vector2<i32>, point: Vector2<i32>, style: WireStyle, plot: &Plot) -> bool {
+        let Some(end) = self.end_position(plot) else {
+            return false;
+        };
+
+        line_touches(style, start, end, point) || match self {
+            Self::Waypoint(segments, position, _) => segments.values().any(|segment| segment.line_touches(*position, point, style, plot)),
+            _ => false
+        }
+    }
+
     fn waypoint_at(&self, point: Vector2<i32>, location: &mut SegmentLocation) -> bool {
         match self {
             Self::Waypoint(..) if self.touches(point) => true,
@@ -266,7 +342,19 @@ pub struct Connection {
     id: ConnectionID,
     active: bool,
     origin: Port,
-    segments: HashMap<Id, Segment>
+    segments: HashMap<Id, Segment>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    color: Option<Color>,
+    // drawn as its name at the origin and at every destination instead of as a wire
+    #[serde(default)]
+    labeled: bool,
+    // drawn in this style instead of the project's wire style
+    #[serde(default)]
+    style: Option<WireStyle>,
+    #[serde(skip)]
+    highlighted: bool
 }
 
 impl Identifiable for Connection {
@@ -279,7 +367,12 @@ impl Connection {
             id: Id::new(),
             active: false,
             origin,
-            segments: segments.into_iter().map(|segment| (Id::new(), segment)).collect()
+            segments: segments.into_iter().map(|segment| (Id::new(), segment)).collect(),
+            name: None,
+            color: None,
+            labeled: false,
+            style: None,
+            highlighted: false
         }
     }
 
@@ -292,7 +385,12 @@ impl Connection {
                 let mut segments = HashMap::new();
                 segments.insert(Id::new(), Segment::Block(destination_block, destination_port));
                 segments
-            }
+            },
+            name: None,
+            color: None,
+            labeled: false,
+            style: None,
+            highlighted: false
         }
     }
 
@@ -312,6 +410,51 @@ impl Connection {
         self.active = is_active;
     }
 
+    pub fn name(&self) -> &Option<String> {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
+    }
+
+    pub fn color(&self) -> &Option<Color> {
+        &self.color
+    }
+
+    pub fn set_color(&mut self, color: Option<Color>) {
+        self.color = color;
+    }
+
+    pub fn labeled(&self) -> bool {
+        self.labeled
+    }
+
+    pub fn set_labeled(&mut self, labeled: bool) {
+        self.labeled = labeled;
+    }
+
+    pub fn style(&self) -> Option<WireStyle> {
+        self.style
+    }
+
+    pub fn set_style(&mut self, style: Option<WireStyle>) {
+        self.style = style;
+    }
+
+    // the custom color of this net, or one derived from its name
+    pub fn net_color(&self) -> Option<Color> {
+        self.color.or_else(|| self.name.as_deref().map(net_color))
+    }
+
+    pub fn highlighted(&self) -> bool {
+        self.highlighted
+    }
+
+    pub fn set_highlighted(&mut self, highlighted: bool) {
+        self.highlighted = highlighted;
+    }
+
     pub fn origin(&self) -> Port {
         self.origin
     }
@@ -330,6 +473,18 @@ impl Connection {
         self.segments.is_empty()
     }
 
+    // total length of the drawn wire in plot units, labeled wires have none
+    pub fn length(&self, plot: &Plot) -> f64 {
+        let Some(origin) = plot.get_block(self.origin.block_id()) else {
+            return 0.;
+        };
+        if self.labeled {
+            return 0.;
+        }
+        let start = origin.get_connector_pos(self.origin.into());
+        self.segments.values().map(|segment| segment.length(start, plot)).sum()
+    }
+
     pub fn remove_unselected_branches(&mut self, selected: &Vec<Id>) -> bool {
         self.segments.retain(|_, segment| !segment.remove_unselected_branches(selected));
         self.segments.is_empty()
@@ -344,7 +499,18 @@ impl Connection {
         }
     }
 
+    pub fn remap_ids(&mut self, blocks: &HashMap<BlockID, BlockID>) {
+        if let Some(block_id) = blocks.get(&self.origin.block_id()) {
+            self.origin.set_block_id(*block_id);
+        }
+        self.segments = Segment::remap_ids(std::mem::take(&mut self.segments), blocks);
+    }
+
     pub fn waypoint_at(&self, position: Vector2<i32>) -> Option<SegmentID> {
+        if self.labeled {
+            return None;
+        }
+
         let mut location = vec![Id::empty()];
         self.segments.iter().any(|(id, segment)| {
             location[0] = *id;
@@ -352,10 +518,61 @@ impl Connection {
         }).then_some(SegmentID::new(self.id, location))
     }
 
+    pub fn touches(&self, position: Vector2<i32>, view: WireView, plot: &Plot) -> bool {
+        // labeled wires aren't drawn, so they can only be reached through their blocks
+        let Some(origin_block) = plot.get_block(self.origin.block_id()).filter(|_| !self.labeled) else {
+            return false;
+        };
+
+        let start = origin_block.get_connector_pos(self.origin.into());
+        let style = view.resolve(self.style);
+        self.segments.values().any(|segment| segment.line_touches(start, position, style, plot))
+    }
+
     pub fn add_segment(&mut self, segment: Segment) {
         self.segments.insert(Id::new(), segment);
     }
 
+    pub fn set_segments(&mut self, segments: HashMap<Id, Segment>) {
+        self.segments = segments;
+    }
+
+    // removes a single waypoint and attaches its branches to the segment before it
+    pub fn remove_waypoint(&mut self, location: &SegmentLocation) -> bool {
+        let Some((id, parent_location)) = location.split_last() else {
+            return false;
+        };
+
+        let parent = if parent_location.is_empty() {
+            &mut self.segments
+        }
+        else {
+            match self.get_segment_mut(&parent_location.to_vec()) {
+                Some(Segment::Waypoint(segments, ..)) => segments,
+                _ => return false
+            }
+        };
+
+        match parent.remove(id) {
+            Some(Segment::Waypoint(children, ..)) => {
+                parent.extend(children);
+                true
+            }
+            Some(segment) => {
+                parent.insert(*id, segment);
+                false
+            }
+            None => false
+        }
+    }
+
+    // the segments of this connection without redundant collinear waypoints, `None` if there are none to remove
+    pub fn simplified_segments(&self, plot: &Plot) -> Option<HashMap<Id, Segment>> {
+        let start = plot.get_block(self.origin.block_id())?.get_connector_pos(self.origin.into());
+        let mut segments = self.segments.clone();
+        (Segment::simplify(&mut segments, start, plot) > 0).then_some(segments)
+    }
+
     pub fn segments(&self) -> &HashMap<Id, Segment> {
         &self.segments
     }
@@ -397,6 +614,13 @@ impl Connection {
     }
 }
 
+// checks if `b` lies on the straight line from `a` to `c`
+fn is_collinear(a: Vector2<i32>, b: Vector2<i32>, c: Vector2<i32>) -> bool {
+    let (ab, ac) = (b - a, c - a);
+    let between = (a.0.min(c.0)..=a.0.max(c.0)).contains(&b.0) && (a.1.min(c.1)..=a.1.max(c.1)).contains(&b.1);
+    between && ab.0 as i64 * ac.1 as i64 == ab.1 as i64 * ac.0 as i64
+}
+
 fn render_waypoint<R>(position: Vector2<i32>, active: bool, highlighted: bool, renderer: &R) -> Result<(), R::Error>
     where R: Renderer
 {
@@ -425,30 +649,156 @@ fn render_block_connector<R>(position: Vector2<i32>, active: bool, highlighted:
         .map(|_| ())
 }
 
-fn render_line<R>(active: bool, start: Vector2<i32>, end: Vector2<i32>, renderer: &R) -> Result<(), R::Error>
+// a warning sign just above an output pin that drives a net together with another one
+fn render_conflict_marker<R>(position: Vector2<i32>, renderer: &R) -> Result<(), R::Error>
     where R: Renderer
 {
-    renderer.set_color(unsafe { if active { &COLOR_THEME.enabled_bg_color } else { &COLOR_THEME.disabled_bg_color } })
+    let center = position + Vector2(10, -14);
+    renderer.arc(center, 7., 0., f64::consts::TAU)
+        .set_color(&CONFLICT_COLOR)
+        .fill()?;
+    renderer.move_to(center + Vector2(-2, 5))
+        .set_color(unsafe { &COLOR_THEME.block_fg_color })
+        .show_text("!")?;
+    Ok(())
+}
+
+fn wire_color(active: bool) -> &'static Color {
+    unsafe { if active { &COLOR_THEME.enabled_bg_color } else { &COLOR_THEME.disabled_bg_color } }
+}
+
+pub(crate) fn render_line<R>(active: bool, start: Vector2<i32>, end: Vector2<i32>, renderer: &R) -> Result<(), R::Error>
+    where R: Renderer
+{
+    render_colored_line(wire_color(active), resolve_wire_style(renderer.wire_style(), renderer.editor_mode()), start, end, renderer)
+}
+
+const NET_LABEL_STUB: i32 = 20;
+
+// a short wire off a pin with the net's name at its end, standing in for a labeled connection;
+// a negative stub points left, away from an input pin
+fn render_net_label<R>(color: &Color, pin: Vector2<i32>, stub: i32, name: &str, renderer: &R) -> Result<(), R::Error>
+    where R: Renderer
+{
+    let end = pin + Vector2(stub, 0);
+    renderer.set_color(color)
+        .set_line_width(4.)
+        .move_to(pin)
+        .line_to(end)
+        .stroke()?;
+
+    let width = 7 * name.chars().count() as i32;
+    let x = if stub < 0 { end.0 - width - 4 } else { end.0 + 4 };
+    renderer.set_font_size(11.)
+        .move_to(Vector2(x, pin.1 + 4))
+        .show_text(name)?;
+    renderer.set_font_size(DEFAULT_FONT_SIZE);
+    Ok(())
+}
+
+fn render_colored_line<R>(color: &Color, style: WireStyle, start: Vector2<i32>, end: Vector2<i32>, renderer: &R) -> Result<(), R::Error>
+    where R: Renderer
+{
+    renderer.set_color(color)
         .set_line_width(4.);
-    
-    match renderer.editor_mode() {
-        EditorMode::Normal => {
-            let offset = (
-                Vector2(start.0 + ((end.0 - start.0) as f32 * 0.7) as i32, start.1),
-                Vector2(end.0 + ((start.0 - end.0) as f32 * 0.7) as i32, end.1),
-            );
+
+    match style {
+        WireStyle::Straight | WireStyle::Auto => {
             renderer.move_to(start)
-                .curve_to(offset.0, offset.1, end)
+                .line_to(end)
                 .stroke()
         }
-        EditorMode::Grid => {
+        WireStyle::Orthogonal => {
+            let mid = start.0 + (end.0 - start.0) / 2;
             renderer.move_to(start)
+                .line_to(Vector2(mid, start.1))
+                .line_to(Vector2(mid, end.1))
                 .line_to(end)
                 .stroke()
         }
+        WireStyle::Bezier => {
+            let offset = bezier_offset(start, end);
+            renderer.move_to(start)
+                .curve_to(offset.0, offset.1, end)
+                .stroke()
+        }
     }.map(|_| ())
 }
 
+// how a view draws its wires, so that they are hit-tested the way they are drawn
+#[derive(Debug, Clone, Copy)]
+pub struct WireView {
+    style: WireStyle,
+    mode: EditorMode,
+    lite: bool
+}
+
+impl WireView {
+    pub fn of<R: Renderer>(renderer: &R) -> Self {
+        Self {
+            style: renderer.wire_style(),
+            mode: renderer.editor_mode(),
+            lite: renderer.lite()
+        }
+    }
+
+    // the style a wire is drawn in: its own one unless lite mode draws every wire straight
+    pub fn resolve(&self, own: Option<WireStyle>) -> WireStyle {
+        let style = own.filter(|_| !self.lite).unwrap_or(self.style);
+        resolve_wire_style(style, self.mode)
+    }
+}
+
+// the style wires are actually drawn in, `Auto` depends on the editor mode
+pub fn resolve_wire_style(style: WireStyle, mode: EditorMode) -> WireStyle {
+    match (style, mode) {
+        (WireStyle::Auto, EditorMode::Normal) => WireStyle::Bezier,
+        (WireStyle::Auto, EditorMode::Grid) => WireStyle::Straight,
+        (style, _) => style
+    }
+}
+
+fn bezier_offset(start: Vector2<i32>, end: Vector2<i32>) -> (Vector2<i32>, Vector2<i32>) {
+    (
+        Vector2(start.0 + ((end.0 - start.0) as f32 * 0.7) as i32, start.1),
+        Vector2(end.0 + ((start.0 - end.0) as f32 * 0.7) as i32, end.1),
+    )
+}
+
+// checks if `point` lies on the wire from `start` to `end` as it is drawn in the resolved `style`
+fn line_touches(style: WireStyle, start: Vector2<i32>, end: Vector2<i32>, point: Vector2<i32>) -> bool {
+    const CURVE_STEPS: usize = 16;
+
+    let points = match style {
+        WireStyle::Orthogonal => {
+            let mid = start.0 + (end.0 - start.0) / 2;
+            vec![start, Vector2(mid, start.1), Vector2(mid, end.1), end]
+        }
+        WireStyle::Bezier => {
+            let (c1, c2) = bezier_offset(start, end);
+            (0..=CURVE_STEPS).map(|i| {
+                let t = i as f64 / CURVE_STEPS as f64;
+                let u = 1. - t;
+                let coord = |p0: i32, p1: i32, p2: i32, p3: i32|
+                    (u * u * u * p0 as f64 + 3. * u * u * t * p1 as f64 + 3. * u * t * t * p2 as f64 + t * t * t * p3 as f64) as i32;
+                Vector2(coord(start.0, c1.0, c2.0, end.0), coord(start.1, c1.1, c2.1, end.1))
+            }).collect()
+        }
+        _ => vec![start, end]
+    };
+
+    points.windows(2).any(|line| distance_to_line(point, line[0], line[1]) <= Segment::HITBOX_SIZE as f64)
+}
+
+fn distance_to_line(point: Vector2<i32>, a: Vector2<i32>, b: Vector2<i32>) -> f64 {
+    let (point, a, b): (Vector2<f64>, Vector2<f64>, Vector2<f64>) = (Vector2::cast(point), Vector2::cast(a), Vector2::cast(b));
+    let (ab, ap) = (b - a, point - a);
+    let length = ab.0 * ab.0 + ab.1 * ab.1;
+    let t = if length == 0. { 0. } else { ((ap.0 * ab.0 + ap.1 * ab.1) / length).clamp(0., 1.) };
+    let closest = Vector2(a.0 + ab.0 * t, a.1 + ab.1 * t);
+    ((point.0 - closest.0).powi(2) + (point.1 - closest.1).powi(2)).sqrt()
+}
+
 impl Renderable for Connection {
     fn render<R>(&self, renderer: &R, plot: &Plot) -> Result<(), R::Error>
         where R: Renderer
@@ -459,11 +809,147 @@ impl Renderable for Connection {
         }
         let origin_block = origin_block.unwrap();
         let origin_pos = origin_block.get_connector_pos(self.origin.into());
+        // lite mode draws every wire as inactive
+        let active = self.active && !renderer.lite();
+
+        // nets that hit a breakpoint use the accent color, named nets keep their own color
+        // and inactive ones are drawn faded
+        let conflicting = plot.in_drive_conflict(self.id);
+        let color = match self.net_color() {
+            _ if self.highlighted => unsafe { COLOR_THEME.accent_fg_color },
+            _ if conflicting => CONFLICT_COLOR,
+            Some(mut color) if renderer.net_colors() => {
+                if !active {
+                    color.3 *= 0.4;
+                }
+                color
+            }
+            _ => *wire_color(active)
+        };
+
+        if self.labeled {
+            let name = self.name.as_deref().unwrap_or_default();
+            render_net_label(&color, origin_pos, NET_LABEL_STUB, name, renderer)?;
+            for port in self.destinations() {
+                if let Some(block) = plot.get_block(port.block_id()) {
+                    let position = block.get_connector_pos(port.into());
+                    render_net_label(&color, position, -NET_LABEL_STUB, name, renderer)?;
+                    render_block_connector(position, active, block.highlighted(), renderer)?;
+                }
+            }
+        }
+        else {
+            let style = WireView::of(renderer).resolve(self.style);
+            for segment in self.segments.values() {
+                segment.render(active, &color, style, origin_pos, renderer, plot)?
+            }
+        }
 
-        for segment in self.segments.values() {
-            segment.render(self.active, origin_pos, renderer, plot)?
+        render_block_connector(origin_pos, active, origin_block.highlighted(), renderer)?;
+
+        if conflicting && plot.is_conflicting_driver(self.origin.block_id(), self.origin.index()) {
+            render_conflict_marker(origin_pos, renderer)?;
         }
+        Ok(())
+    }
+}
 
-        render_block_connector(origin_pos, self.active, origin_block.highlighted(), renderer)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // an And gate wired to a Not gate through a waypoint halfway along the first leg and a corner
+    fn bent_wire() -> (Plot, Connection, Vector2<i32>, Id) {
+        let source = Block::new(&builtin::BUILTINS["And"].module(), Vector2(0, 0), None);
+        let sink = Block::new(&builtin::BUILTINS["Not"].module(), Vector2(400, 200), None);
+        let start = source.get_connector_pos(Connector::Output(0));
+        let halfway = Vector2(start.0 + 100, start.1);
+        let corner = Vector2(start.0 + 200, start.1);
+
+        let halfway_id = Id::new();
+        let to_sink = Segment::Block(sink.id(), 0);
+        let to_corner = Segment::Waypoint(HashMap::from([(Id::new(), to_sink)]), corner, false);
+        let connection = Connection {
+            segments: HashMap::from([(halfway_id, Segment::Waypoint(HashMap::from([(Id::new(), to_corner)]), halfway, false))]),
+            ..Connection::new(Port::Output(source.id(), 0), vec![])
+        };
+
+        let mut plot = Plot::new();
+        plot.add_block(source);
+        plot.add_block(sink);
+        (plot, connection, corner, halfway_id)
+    }
+
+    fn lines(segments: &HashMap<Id, Segment>) -> usize {
+        segments.values().map(|segment| match segment {
+            Segment::Block(..) => 1,
+            Segment::Waypoint(children, ..) => 1 + lines(children)
+        }).sum()
+    }
+
+    #[test]
+    fn simplify_drops_collinear_waypoints() {
+        let (plot, connection, corner, _) = bent_wire();
+        assert_eq!(lines(connection.segments()), 3);
+
+        let simplified = connection.simplified_segments(&plot).unwrap();
+        assert_eq!(lines(&simplified), 2);
+        assert_eq!(simplified.values().next().and_then(|segment| segment.position()), Some(&corner));
+    }
+
+    #[test]
+    fn simplify_keeps_corners() {
+        let (plot, connection, _, _) = bent_wire();
+        let simplified = Connection { segments: connection.simplified_segments(&plot).unwrap(), ..connection };
+        assert!(simplified.simplified_segments(&plot).is_none());
+    }
+
+    #[test]
+    fn remove_waypoint_reattaches_its_branches() {
+        let (_, mut connection, corner, halfway_id) = bent_wire();
+        assert!(connection.remove_waypoint(&vec![halfway_id]));
+        assert_eq!(lines(connection.segments()), 2);
+        assert_eq!(connection.segments().values().next().and_then(|segment| segment.position()), Some(&corner));
+    }
+
+    #[test]
+    fn wires_are_hit_where_they_are_drawn() {
+        let source = Block::new(&builtin::BUILTINS["And"].module(), Vector2(0, 0), None);
+        let sink = Block::new(&builtin::BUILTINS["Not"].module(), Vector2(300, 200), None);
+        let (start, end) = (source.get_connector_pos(Connector::Output(0)), sink.get_connector_pos(Connector::Input(0)));
+        let mut connection = Connection::new_basic(source.id(), 0, sink.id(), 0);
+
+        let mut plot = Plot::new();
+        plot.add_block(source);
+        plot.add_block(sink);
+
+        // a fifth of the way along the straight line, where a curve still runs close to the start's height
+        let on_straight = Vector2(start.0 + (end.0 - start.0) / 5, start.1 + (end.1 - start.1) / 5);
+        let view = |style, mode, lite| WireView { style, mode, lite };
+        assert!(connection.touches(on_straight, view(WireStyle::Auto, EditorMode::Grid, false), &plot));
+        assert!(!connection.touches(on_straight, view(WireStyle::Auto, EditorMode::Normal, false), &plot));
+
+        // lite mode draws a wire's own style straight as well
+        connection.set_style(Some(WireStyle::Bezier));
+        assert!(!connection.touches(on_straight, view(WireStyle::Straight, EditorMode::Normal, false), &plot));
+        assert!(connection.touches(on_straight, view(WireStyle::Straight, EditorMode::Normal, true), &plot));
+    }
+
+    #[test]
+    fn labeled_wires_are_not_hit_along_their_path() {
+        let source = Block::new(&builtin::BUILTINS["And"].module(), Vector2(0, 0), None);
+        let sink = Block::new(&builtin::BUILTINS["Not"].module(), Vector2(300, 0), None);
+        let (start, end) = (source.get_connector_pos(Connector::Output(0)), sink.get_connector_pos(Connector::Input(0)));
+        let mut connection = Connection::new_basic(source.id(), 0, sink.id(), 0);
+
+        let mut plot = Plot::new();
+        plot.add_block(source);
+        plot.add_block(sink);
+
+        let view = WireView { style: WireStyle::Straight, mode: EditorMode::Normal, lite: false };
+        let halfway = Vector2((start.0 + end.0) / 2, start.1);
+        assert!(connection.touches(halfway, view, &plot));
+        connection.set_labeled(true);
+        assert!(!connection.touches(halfway, view, &plot));
     }
 }
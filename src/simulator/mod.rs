@@ -5,11 +5,18 @@ pub mod decoration;
 pub mod builtin;
 pub mod modules;
 pub mod state;
+pub mod validation;
+pub mod breakpoint;
+pub mod watch;
+pub mod stimulus;
+pub mod routing;
+pub mod snapshot;
 
 pub use {block::*, connection::*, plot::*, decoration::*, modules::*, state::*};
 use std::{
     thread::{self, JoinHandle},
     time::{Duration, Instant},
+    panic::{self, AssertUnwindSafe},
     sync::{Arc, atomic::{AtomicBool, Ordering}, mpsc::{Receiver, Sender, self}}, cell::RefCell, collections::{HashMap, HashSet}
 };
 use gtk::{subclass::prelude::ObjectSubclassIsExt, prelude::Cast};
@@ -28,7 +35,12 @@ thread_local! {
 
 pub enum UICallback {
     Redraw,
-    Error(String)
+    Error(String),
+    Breakpoint(breakpoint::Breakpoint),
+    // the simulation panicked or hit a drive conflict and was halted
+    Halted(String),
+    // achieved and requested ticks per second, `None` while halted
+    Performance(Option<(f64, i32)>)
 }
 
 impl UICallback {
@@ -46,6 +58,9 @@ impl UICallback {
     fn exec(&self, window: &RefCell<Option<MainWindow>>) {
         match self {
             Self::Redraw => {
+                if let Some(window) = window.borrow().as_ref() {
+                    window.panel().update_watches();
+                }
                 if let Some(view) = window.borrow().as_ref()
                     .and_then(|window| window.imp().circuit_panel.imp().view.selected_page())
                     .and_then(|page| page.child().downcast::<CircuitView>().ok()) {
@@ -58,12 +73,70 @@ impl UICallback {
                         panel.push_error(err.clone());
                 }              
             }
+            Self::Breakpoint(breakpoint) => {
+                if let Some(window) = window.borrow().as_ref() {
+                    window.panel().breakpoint_hit(breakpoint);
+                }
+            }
+            Self::Halted(err) => {
+                if let Some(window) = window.borrow().as_ref() {
+                    window.panel().simulation_halted(err.clone());
+                }
+            }
+            Self::Performance(rate) => {
+                if let Some(window) = window.borrow().as_ref() {
+                    window.panel().update_performance(*rate);
+                }
+            }
         }
     }
 }
 
 pub type SimResult<T> = Result<T, String>;
 
+// counts the ticks simulated over a time window to find the actually achieved tick rate
+struct TickRate {
+    start: Instant,
+    ticks: u32,
+    slow_windows: u32
+}
+
+impl TickRate {
+    const WINDOW: Duration = Duration::from_secs(1);
+    const MAX_SLOW_WINDOWS: u32 = 3;
+
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            ticks: 0,
+            slow_windows: 0
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    // counts a tick, returning the achieved rate once a window is complete
+    fn tick(&mut self, tps: i32) -> Option<f64> {
+        self.ticks += 1;
+        let elapsed = self.start.elapsed();
+        if elapsed < Self::WINDOW {
+            return None;
+        }
+
+        let achieved = self.ticks as f64 / elapsed.as_secs_f64();
+        self.slow_windows = if achieved < tps as f64 * Simulator::LAGGING_FACTOR { self.slow_windows + 1 } else { 0 };
+        self.start = Instant::now();
+        self.ticks = 0;
+        Some(achieved)
+    }
+
+    fn sustained_slow(&self) -> bool {
+        self.slow_windows >= Self::MAX_SLOW_WINDOWS
+    }
+}
+
 pub struct Simulator {
     running: Arc<AtomicBool>,
     thread: JoinHandle<()>
@@ -71,10 +144,21 @@ pub struct Simulator {
 
 impl Simulator {
     pub const DEFAULT_TICKS_PER_SECOND: i32 = 10;
+    pub const MAX_SETTLE_TICKS: u32 = 1000;
+    // the speeds offered in the menu, slowest first
+    pub const TICK_SPEEDS: [i32; 4] = [1, 10, 100, 1000];
+    // below this fraction of the requested tick rate the simulation counts as lagging
+    pub const LAGGING_FACTOR: f64 = 0.8;
 
     pub fn new(project: ProjectRef, window: RefCell<Option<MainWindow>>) -> Self {
         info!("starting simulation...");
 
+        {
+            let mut project = project.lock();
+            project.reset_convergence_depth();
+            project.mark_drive_conflicts();
+        }
+
         let (tx, rx) = mpsc::channel();
         UI_CALLBACK.with(|ui_callback| *ui_callback.borrow_mut() = Some((window, rx)));
 
@@ -102,22 +186,51 @@ impl Simulator {
     }
 
     fn schedule(running: Arc<AtomicBool>, project: ProjectRef, tx: Sender<UICallback>) {
+        let mut rate = TickRate::new();
+        let mut halted = false;
+
         while running.load(Ordering::Relaxed) {
             let start = Instant::now();
 
-            let mut project = project.lock().unwrap();
+            let mut project = project.lock();
             let tps = project.tps();
             
             // if we halt the simulation, check again in 0.5 seconds
             if tps == 0 {
                 drop(project);
+                if !halted {
+                    halted = true;
+                    UICallback::Performance(None).handle(&tx);
+                }
                 thread::sleep(Duration::from_millis(500));
                 continue;
             }
+            if halted {
+                halted = false;
+                rate.reset();
+            }
 
             let wait_time = Duration::from_secs_f64(1.0 / tps as f64);
 
-            Self::simulate(&mut project, &tx);
+            Self::simulate(&mut project, &mut |callback| callback.handle(&tx));
+
+            if let Some(achieved) = rate.tick(tps) {
+                if rate.sustained_slow() && project.auto_throttle() {
+                    let throttled = Self::TICK_SPEEDS.into_iter()
+                        .filter(|speed| *speed < tps && *speed as f64 <= achieved)
+                        .last()
+                        .unwrap_or(Self::TICK_SPEEDS[0]);
+                    if throttled < tps {
+                        warn!("throttling simulation from {tps} to {throttled} ticks per second");
+                        project.set_tps(throttled);
+                        rate.reset();
+                        UICallback::Error(format!(
+                            "The simulation only reached {achieved:.0} of {tps} ticks per second and was slowed down to {throttled}."
+                        )).handle(&tx);
+                    }
+                }
+                UICallback::Performance(Some((achieved, project.tps()))).handle(&tx);
+            }
             drop(project);
 
             let runtime = start.elapsed();
@@ -127,27 +240,121 @@ impl Simulator {
         }
     }
 
-    fn simulate(project: &mut Project, tx: &Sender<UICallback>) {
+    // runs single ticks until nothing is left to update, returning the number of ticks it took
+    pub fn settle(project: &mut Project, max_ticks: u32) -> SimResult<u32> {
+        project.mark_drive_conflicts();
+        for tick in 1..=max_ticks {
+            let mut error = None;
+            let mut breakpoint = None;
+            Self::simulate(project, &mut |callback| match callback {
+                UICallback::Error(err) | UICallback::Halted(err) => error = Some(err),
+                UICallback::Breakpoint(hit) => breakpoint = Some(hit),
+                UICallback::Redraw | UICallback::Performance(_) => ()
+            });
+
+            if let Some(err) = error {
+                return Err(err);
+            }
+            if let Some(breakpoint) = breakpoint {
+                return Err(format!("Breakpoint hit after {tick} ticks: {}.", breakpoint.describe(project)));
+            }
+            if project.iter_plots_mut().all(|plot| plot.frozen() || plot.is_stable()) {
+                return Ok(tick);
+            }
+        }
+
+        Err(format!("The circuit did not settle within {max_ticks} ticks; it may be oscillating."))
+    }
+
+    // runs one tick; a panic inside it halts the simulation instead of taking down the thread with the project lock held
+    fn simulate(project: &mut Project, report: &mut dyn FnMut(UICallback)) {
+        let depths = project.iter_plots_mut().map(|plot| plot.state_depth()).collect::<Vec<_>>();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| Self::simulate_tick(project, report)));
+
+        if let Err(payload) = result {
+            let reason = payload.downcast_ref::<&str>().map(|reason| reason.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| String::from("unknown error"));
+            error!("simulation panicked: {reason}");
+
+            // the tick stopped halfway, drop the states it pushed
+            project.iter_plots_mut().zip(depths).for_each(|(plot, depth)| plot.truncate_states(depth));
+            project.set_tps(0);
+            report(UICallback::Halted(format!("The simulation was halted after an internal error: {reason}")));
+        }
+    }
+
+    fn simulate_tick(project: &mut Project, report: &mut dyn FnMut(UICallback)) {
+        // with two outputs on one input the result would depend on the order blocks are evaluated in,
+        // so nothing is simulated until the conflict is resolved; edits keep the marked conflicts current
+        let conflicts = project.iter_plots_mut().filter(|plot| !plot.frozen() && plot.has_drive_conflicts()).count();
+        if conflicts > 0 {
+            project.set_tps(0);
+            report(UICallback::Halted(format!(
+                "The simulation was halted: {conflicts} circuit(s) contain inputs driven by multiple outputs. The conflicting wires are marked."
+            )));
+            return;
+        }
+
         let mut_ref_ptr = project as *mut Project;
         let mut call_stack = HashSet::new();
         let mut changes = false;
 
+        // remember the watched nets to detect edges after this tick
+        let watched = Self::clear_breakpoint_hits(project);
+
+        // the most rounds of updates any plot needed to settle within this tick; read right after each plot ran,
+        // since module instances simulated inside other plots reuse the module's plot
+        let mut depth = 0;
         project.iter_plots_mut().for_each(|plot| plot.push_state());
-        project.iter_plots_mut().for_each(|plot| {
+        project.iter_plots_mut().filter(|plot| !plot.frozen()).for_each(|plot| {
             plot.pop_state();
-            match plot.simulate(unsafe { &mut *mut_ref_ptr }, &mut call_stack) {
-                Ok(c) => if c { changes = true },
-                Err(err) => UICallback::Error(err).handle(tx)
+            if let Err(err) = plot.simulate(unsafe { &mut *mut_ref_ptr }, &mut call_stack) {
+                report(UICallback::Error(err));
             }
+            depth = depth.max(plot.passes());
             plot.push_state();
         });
         project.iter_plots_mut().for_each(|plot| plot.pop_state());
+        project.record_convergence_depth(depth);
+
+        // only redraw if a block shows something different than before, idle circuits don't get redrawn every tick
+        project.iter_plots_mut().for_each(|plot| if plot.take_redraw() { changes = true });
 
         assert!(call_stack.is_empty(), "callstack wasn't empty: {call_stack:?}");
+        project.advance_tick();
+
+        let hit = project.breakpoints().iter()
+            .zip(watched)
+            .find(|(breakpoint, before)| breakpoint.triggered(*before, breakpoint.state(project)))
+            .map(|(breakpoint, _)| breakpoint.clone());
+        if let Some(breakpoint) = hit {
+            info!("hit breakpoint: {}", breakpoint.describe(project));
+            project.set_tps(0);
+            if let Some(connection) = project.plot_by_descriptor_mut(breakpoint.plot())
+                .and_then(|plot| plot.get_connection_mut(&breakpoint.connection())) {
+                connection.set_highlighted(true);
+            }
+            report(UICallback::Breakpoint(breakpoint));
+            changes = true;
+        }
+
         if changes {
-            UICallback::Redraw.handle(tx)
+            report(UICallback::Redraw)
         }
     }
+
+    fn clear_breakpoint_hits(project: &mut Project) -> Vec<Option<bool>> {
+        let breakpoints = project.breakpoints().clone();
+        breakpoints.iter().map(|breakpoint| {
+            let connection = project.plot_by_descriptor_mut(breakpoint.plot())
+                .and_then(|plot| plot.get_connection_mut(&breakpoint.connection()));
+            connection.map(|connection| {
+                connection.set_highlighted(false);
+                connection.is_active()
+            })
+        }).collect()
+    }
 }
 
 pub trait Collect<T, D> {
@@ -165,3 +372,215 @@ impl Collect<u128, HashMap<ConnectionID, Connection>> for Vec<Option<ConnectionI
         inputs
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::vector::Vector2;
+
+    // two And gates wired to the single input of a Not gate
+    fn conflicting_project() -> (Project, BlockID, BlockID, BlockID) {
+        let mut project = Project::default();
+        let and = project.module(&String::from("And")).unwrap().clone();
+        let not = project.module(&String::from("Not")).unwrap().clone();
+
+        let first = Block::new(&&and, Vector2(0, 0), None);
+        let second = Block::new(&&and, Vector2(0, 100), None);
+        let sink = Block::new(&&not, Vector2(200, 50), None);
+        let (first_id, second_id, sink_id) = (first.id(), second.id(), sink.id());
+
+        let plot = project.main_plot_mut();
+        plot.add_block(first);
+        plot.add_block(second);
+        plot.add_block(sink);
+        plot.add_connection(Connection::new_basic(first_id, 0, sink_id, 0));
+        plot.add_connection(Connection::new_basic(second_id, 0, sink_id, 0));
+        (project, first_id, second_id, sink_id)
+    }
+
+    #[test]
+    fn multiple_drivers_halt_the_simulation() {
+        let (mut project, first_id, second_id, sink_id) = conflicting_project();
+
+        let conflicts = project.main_plot().multiple_drivers();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].0.block_id(), sink_id);
+
+        let err = Simulator::settle(&mut project, 10).unwrap_err();
+        assert!(err.contains("multiple outputs"), "unexpected error: {err}");
+        assert!(project.main_plot().is_conflicting_driver(first_id, 0));
+        assert!(project.main_plot().is_conflicting_driver(second_id, 0));
+    }
+
+    #[test]
+    fn multiple_drivers_are_reported_at_both_drivers() {
+        let (project, first_id, second_id, sink_id) = conflicting_project();
+
+        let located = validation::validate(&project).into_iter()
+            .filter(|diagnostic| diagnostic.is_fatal() && diagnostic.message().contains("driv"))
+            .filter_map(|diagnostic| diagnostic.location().map(|(_, block_id)| *block_id))
+            .collect::<HashSet<_>>();
+        assert_eq!(located, HashSet::from([first_id, second_id, sink_id]));
+    }
+
+    #[test]
+    fn fixing_a_conflict_clears_its_highlight() {
+        let (mut project, first_id, second_id, sink_id) = conflicting_project();
+
+        project.flag_problems(&validation::validate(&project));
+        for block_id in [first_id, second_id, sink_id] {
+            assert_eq!(project.main_plot().flagged(block_id), Some(validation::Severity::Error));
+        }
+
+        project.main_plot_mut().delete_block(second_id);
+        project.flag_problems(&validation::validate(&project));
+        assert_eq!(project.main_plot().flagged(first_id), None);
+        assert_eq!(project.main_plot().flagged(sink_id), None);
+    }
+
+    // `count` Not gates, each driving the next; the last drives the first if `ring`
+    fn not_gates(count: i32, ring: bool) -> (Project, Vec<BlockID>) {
+        let mut project = Project::default();
+        let not = project.module(&String::from("Not")).unwrap().clone();
+        let blocks = (0..count).map(|i| Block::new(&&not, Vector2(200 * i, 0), None)).collect::<Vec<_>>();
+        let ids = blocks.iter().map(|block| block.id()).collect::<Vec<_>>();
+
+        let plot = project.main_plot_mut();
+        blocks.into_iter().for_each(|block| plot.add_block(block));
+        let wires = if ring { ids.len() } else { ids.len() - 1 };
+        for (i, id) in ids.iter().enumerate().take(wires) {
+            plot.add_connection(Connection::new_basic(*id, 0, ids[(i + 1) % ids.len()], 0));
+        }
+        (project, ids)
+    }
+
+    #[test]
+    fn an_inverter_ring_is_reported_as_active() {
+        // an odd number of inverters in a ring never settles
+        let (mut project, ids) = not_gates(3, true);
+
+        project.set_activity_tracking(true);
+        Simulator::simulate(&mut project, &mut |_| ());
+        let active = project.activity_report(10).into_iter()
+            .filter(|(_, _, count)| *count >= 10)
+            .map(|(_, block_id, _)| block_id)
+            .collect::<HashSet<_>>();
+        assert_eq!(active, ids.into_iter().collect::<HashSet<_>>());
+    }
+
+    #[test]
+    fn a_settling_chain_is_not_reported_as_active() {
+        let (mut project, _) = not_gates(2, false);
+
+        project.set_activity_tracking(true);
+        Simulator::simulate(&mut project, &mut |_| ());
+        assert!(project.activity_report(10).iter().all(|(_, _, count)| *count <= 2));
+    }
+
+    #[test]
+    fn settling_stops_once_nothing_changes() {
+        let (mut project, _) = not_gates(3, false);
+        let ticks = Simulator::settle(&mut project, Simulator::MAX_SETTLE_TICKS).unwrap();
+        assert!(ticks <= 2, "took {ticks} ticks");
+        assert!(project.main_plot().is_stable());
+    }
+
+    #[test]
+    fn settling_an_oscillator_gives_up() {
+        let (mut project, _) = not_gates(3, true);
+        let err = Simulator::settle(&mut project, 5).unwrap_err();
+        assert!(err.contains("did not settle"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn combinational_logic_settles_within_its_depth() {
+        let (mut project, ids) = not_gates(3, false);
+        Simulator::settle(&mut project, Simulator::MAX_SETTLE_TICKS).unwrap();
+        assert!(project.max_convergence_depth() <= ids.len() as u32 + 1, "took {} rounds", project.max_convergence_depth());
+    }
+
+    #[test]
+    fn feedback_needs_more_rounds_than_combinational_logic() {
+        let (mut chain, _) = not_gates(3, false);
+        Simulator::simulate(&mut chain, &mut |_| ());
+        let (mut ring, _) = not_gates(3, true);
+        Simulator::simulate(&mut ring, &mut |_| ());
+
+        assert!(ring.convergence_depth() > chain.convergence_depth());
+        assert_eq!(ring.max_convergence_depth(), ring.convergence_depth());
+        ring.reset_convergence_depth();
+        assert_eq!(ring.max_convergence_depth(), 0);
+    }
+
+    #[test]
+    fn a_panicking_tick_halts_the_simulation() {
+        let mut project = Project::default();
+        project.set_tps(10);
+        let flip_flop = project.module(&String::from("T Flip-Flop with Set/Reset")).unwrap().clone();
+        let mut block = Block::new(&&flip_flop, Vector2(0, 0), None);
+        // the flip-flop expects its bit to be stored directly and panics on anything else
+        block.set_state(State::None);
+        project.main_plot_mut().add_block(block);
+        let depth = project.main_plot().state_depth();
+
+        let mut halted = None;
+        Simulator::simulate(&mut project, &mut |callback| if let UICallback::Halted(err) = callback { halted = Some(err) });
+        let err = halted.expect("the simulation was not halted");
+        assert!(err.contains("internal error"), "unexpected error: {err}");
+        assert_eq!(project.tps(), 0);
+        assert_eq!(project.main_plot().state_depth(), depth);
+    }
+
+    #[test]
+    fn a_rising_breakpoint_pauses_on_the_clock_edge() {
+        // the inverter ring never settles, so ticks keep coming while the clock below is low
+        let (mut project, _) = not_gates(3, true);
+        let switch = Block::new(&&project.module(&String::from("Switch")).unwrap().clone(), Vector2(0, 200), None);
+        let lamp = Block::new(&&project.module(&String::from("Lamp")).unwrap().clone(), Vector2(200, 200), None);
+        let clock = Connection::new_basic(switch.id(), 0, lamp.id(), 0);
+        let (switch_id, clock_id) = (switch.id(), clock.id());
+        let plot = project.main_plot_mut();
+        plot.add_block(switch);
+        plot.add_block(lamp);
+        plot.add_connection(clock);
+
+        // low for three ticks, then high for three; the switch goes high after tick 3, so the wire does in tick 4
+        project.set_stimulus(Some(stimulus::Stimulus::new(vec![switch_id], vec![vec![false], vec![true]], 3, true, false)));
+        project.stimulus_mut().unwrap().play(0);
+        project.add_breakpoint(breakpoint::Breakpoint::new(PlotDescriptor::Main(), clock_id, breakpoint::Condition::Rising));
+
+        let err = Simulator::settle(&mut project, Simulator::MAX_SETTLE_TICKS).unwrap_err();
+        assert!(err.starts_with("Breakpoint hit after 4 ticks"), "unexpected error: {err}");
+        assert_eq!(project.ticks(), 4);
+        assert!(project.main_plot().get_connection(&clock_id).unwrap().is_active());
+    }
+
+    // runs one tick, returning whether it asked for a redraw
+    fn redraws(project: &mut Project) -> bool {
+        let mut redraw = false;
+        Simulator::simulate(project, &mut |callback| if let UICallback::Redraw = callback { redraw = true });
+        redraw
+    }
+
+    #[test]
+    fn a_settled_circuit_is_not_redrawn() {
+        let (mut project, _) = not_gates(2, false);
+        Simulator::settle(&mut project, Simulator::MAX_SETTLE_TICKS).unwrap();
+        redraws(&mut project);
+        assert!(!redraws(&mut project));
+        assert!(!redraws(&mut project));
+    }
+
+    #[test]
+    fn a_block_showing_something_new_is_redrawn() {
+        let (mut project, ids) = not_gates(2, false);
+        Simulator::settle(&mut project, Simulator::MAX_SETTLE_TICKS).unwrap();
+        redraws(&mut project);
+
+        let plot = project.main_plot_mut();
+        let active = plot.get_block(ids[1]).unwrap().is_active();
+        plot.get_block_mut(ids[1]).unwrap().set_active(!active);
+        assert!(plot.take_redraw());
+        assert!(!plot.take_redraw());
+    }
+}
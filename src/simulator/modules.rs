@@ -6,6 +6,9 @@ use serde::{Serialize, Deserialize};
 
 pub type SimulatorFn = fn(u128, &mut Block) -> u128;
 
+// optional names of the input and output pins, in pin order
+pub type PinNames = (Vec<Option<String>>, Vec<Option<String>>);
+
 #[derive(Default, Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum Category {
     #[default]
@@ -54,6 +57,14 @@ pub struct Module {
     num_outputs: u8,
     decoration: Decoration,
     custom_data: Option<Custom>,
+    // a locked module's circuit cannot be edited, only simulated
+    #[serde(default)]
+    locked: bool,
+    #[serde(default)]
+    pin_names: PinNames,
+    // a pure source or sink is meant to have no inputs or no outputs and is not warned about
+    #[serde(default)]
+    source_or_sink: bool
 }
 
 impl Module {
@@ -67,7 +78,10 @@ impl Module {
             custom_data: Some(Custom::new(Plot::new())),
             num_inputs,
             num_outputs,
-            decoration: Decoration::None
+            decoration: Decoration::None,
+            locked: false,
+            pin_names: PinNames::default(),
+            source_or_sink: false
         }
     }
 
@@ -79,7 +93,10 @@ impl Module {
             custom_data: None,
             num_inputs,
             num_outputs,
-            decoration
+            decoration,
+            locked: false,
+            pin_names: PinNames::default(),
+            source_or_sink: false
         }
     }
 
@@ -104,6 +121,16 @@ impl Module {
         }
     }
 
+    // the same pins and the same circuit, whatever the ids inside it
+    pub fn same_contents(&self, other: &Self) -> bool {
+        self.num_inputs == other.num_inputs && self.num_outputs == other.num_outputs && self.pin_names == other.pin_names &&
+            self.plot().map(Plot::layout) == other.plot().map(Plot::layout)
+    }
+
+    pub fn io_blocks(&self) -> Option<(BlockID, BlockID)> {
+        self.custom_data.as_ref().map(|data| (data.input_block, data.output_block))
+    }
+
     pub fn has_io_blocks(&self) -> bool {
         match &self.custom_data {
             Some(data) => {
@@ -113,6 +140,73 @@ impl Module {
         }
     }
 
+    // the assigned pin names, padded or cut to the current pin counts
+    pub fn pin_names(&self) -> PinNames {
+        let fit = |names: &Vec<Option<String>>, count: u8| {
+            let mut names = names.clone();
+            names.resize(count as usize, None);
+            names
+        };
+        (fit(&self.pin_names.0, self.num_inputs), fit(&self.pin_names.1, self.num_outputs))
+    }
+
+    pub fn set_pin_names(&mut self, pin_names: PinNames) {
+        self.pin_names = pin_names;
+    }
+
+    // assigned pin names, falling back to the names of the nets attached to the input and output block
+    pub fn port_names(&self) -> PinNames {
+        let (assigned_inputs, assigned_outputs) = self.pin_names();
+        let Some(data) = &self.custom_data else {
+            return (assigned_inputs, assigned_outputs);
+        };
+
+        let plot = data.plot();
+        let names = |assigned: Vec<Option<String>>, pins: Option<&Vec<Option<ConnectionID>>>| assigned.into_iter()
+            .enumerate()
+            .map(|(i, name)| name.or_else(|| pins
+                .and_then(|pins| pins.get(i).copied().flatten())
+                .and_then(|id| plot.get_connection(&id))
+                .and_then(|connection| connection.name().clone())
+            ))
+            .collect::<Vec<_>>();
+
+        (
+            names(assigned_inputs, plot.get_block(data.input_block).map(|block| block.outputs())),
+            names(assigned_outputs, plot.get_block(data.output_block).map(|block| block.inputs()))
+        )
+    }
+
+    // whether the module's own plot takes its inputs from toggles instead of whatever is wired to its input block
+    pub fn isolated(&self) -> bool {
+        self.custom_data.as_ref()
+            .and_then(|data| data.plot.get_block(data.input_block))
+            .map_or(false, |block| block.test_inputs().is_some())
+    }
+
+    pub fn set_isolated(&mut self, isolated: bool) {
+        if let Some(data) = &mut self.custom_data && let Some(block) = data.plot.get_block_mut(data.input_block) {
+            block.set_test_inputs(isolated.then_some(0));
+            data.plot.add_block_to_update(data.input_block);
+        }
+    }
+
+    pub fn source_or_sink(&self) -> bool {
+        self.source_or_sink
+    }
+
+    pub fn set_source_or_sink(&mut self, source_or_sink: bool) {
+        self.source_or_sink = source_or_sink;
+    }
+
+    pub fn locked(&self) -> bool {
+        self.locked
+    }
+
+    pub fn set_locked(&mut self, locked: bool) {
+        self.locked = locked;
+    }
+
     pub fn builtin(&self) -> bool {
         self.builtin
     }
@@ -129,6 +223,10 @@ impl Module {
         &self.name
     }
 
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
     pub fn get_num_inputs(&self) -> u8 {
         self.num_inputs
     }
@@ -1,14 +1,23 @@
 use super::*;
-use crate::{renderer::{*, vector::Vector2}, application::selection::*, project::{ProjectRef, Project}};
+use crate::{renderer::{*, vector::Vector2}, application::{selection::*, editor::{GridStyle, GRID_SIZE}}, project::{ProjectRef, Project}, id::Id};
 use std::{collections::{HashMap, HashSet}, cmp};
 use serde::{Serialize, Deserialize};
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum PlotDescriptor {
     Main(),
     Module(String)
 }
 
+impl PlotDescriptor {
+    pub fn to_provider(&self, project: ProjectRef) -> PlotProvider {
+        match self {
+            Self::Main() => PlotProvider::Main(project),
+            Self::Module(name) => PlotProvider::Module(project, name.clone())
+        }
+    }
+}
+
 impl From<&PlotProvider> for PlotDescriptor {
     fn from(value: &PlotProvider) -> Self {
         match value {
@@ -37,15 +46,23 @@ impl From<PlotProvider> for PlotDescriptor {
     }
 }
 
+impl std::fmt::Display for PlotDescriptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Main() => write!(f, "the main circuit"),
+            Self::Module(name) => write!(f, "module \"{name}\"")
+        }
+    }
+}
+
 impl PlotProvider {
     #[inline]
     pub fn with<T>(&self, func: impl FnOnce(&Plot) -> T) -> Option<T> {
         match self {
             Self::None => None,
-            Self::Main(project) => Some(func(project.lock().unwrap().main_plot())),
+            Self::Main(project) => Some(func(project.lock().main_plot())),
             Self::Module(project, module) => project
                 .lock()
-                .unwrap()
                 .plot(module)
                 .map(func)
         }
@@ -55,10 +72,9 @@ impl PlotProvider {
     pub fn with_mut<T>(&self, func: impl Fn(&mut Plot) -> T) -> Option<T> {
         match self {
             Self::None => None,
-            Self::Main(project) => Some(func(project.lock().unwrap().main_plot_mut())),
+            Self::Main(project) => Some(func(project.lock().main_plot_mut())),
             Self::Module(project, module) => project
                 .lock()
-                .unwrap()
                 .plot_mut(module)
                 .map(func),
         }
@@ -82,8 +98,19 @@ impl PlotProvider {
             _ => None
         }
     }
+
+    // true for the plot of a locked module, which must not be edited
+    pub fn is_locked(&self) -> bool {
+        match self {
+            Self::Module(project, name) => project.lock().module(name).map_or(false, |module| module.locked()),
+            _ => false
+        }
+    }
 }
 
+// old-to-new block and connection ids after renumbering a plot
+pub type IdMapping = (HashMap<BlockID, BlockID>, HashMap<ConnectionID, ConnectionID>);
+
 #[derive(Serialize, Debug, Default, Deserialize, Clone)]
 pub struct Plot {
     blocks: HashMap<BlockID, Block>,
@@ -95,7 +122,41 @@ pub struct Plot {
     selection: Selection,
 
     #[serde(skip)]
-    to_update: HashSet<BlockID>
+    to_update: HashSet<BlockID>,
+
+    #[serde(skip)]
+    frozen: bool,
+
+    // peak number of output changes per block within a single tick, while tracking is enabled
+    #[serde(skip)]
+    activity: Option<HashMap<BlockID, u32>>,
+
+    // blocks with validation problems, tinted while problems are highlighted
+    #[serde(skip)]
+    flagged: HashMap<BlockID, validation::Severity>,
+
+    // wires into input pins with more than one driver, and the output pins driving them
+    #[serde(skip)]
+    conflicts: (HashSet<ConnectionID>, HashSet<(BlockID, u8)>),
+
+    // rounds of updates the last call to `simulate()` took until nothing was left to update
+    #[serde(skip)]
+    passes: u32,
+
+    // displayed state of each block when the plot was last redrawn by the simulator
+    #[serde(skip)]
+    drawn: HashMap<BlockID, (u128, bool)>,
+
+    // last translation and scale the plot was viewed with
+    #[serde(default)]
+    view: Option<(Vector2<f64>, f64)>,
+
+    // custom canvas color, the theme's background if unset
+    #[serde(default)]
+    background: Option<Color>,
+
+    #[serde(default)]
+    grid_style: GridStyle
 }
 
 impl Identifiable for Plot {
@@ -109,15 +170,41 @@ impl Plot {
             connections: HashMap::new(),
             states: vec![PlotState::default()],
             selection: Selection::None,
-            to_update: HashSet::new()
+            to_update: HashSet::new(),
+            frozen: false,
+            activity: None,
+            flagged: HashMap::new(),
+            conflicts: (HashSet::new(), HashSet::new()),
+            passes: 0,
+            drawn: HashMap::new(),
+            view: None,
+            background: None,
+            grid_style: GridStyle::default()
         }
     }
 
+    // frozen plots are skipped by the simulator
+    pub fn frozen(&self) -> bool {
+        self.frozen
+    }
+
+    pub fn set_frozen(&mut self, frozen: bool) {
+        self.frozen = frozen;
+    }
+
     pub fn push_state(&mut self) {
         let state = self.into();
         self.states.push(state);
     }
 
+    pub fn state_depth(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn truncate_states(&mut self, depth: usize) {
+        self.states.truncate(depth);
+    }
+
     pub fn pop_state(&mut self) {
         if let Some(state) = self.states.pop() {
             state.apply(self);
@@ -157,6 +244,12 @@ impl Plot {
         None
     }
 
+    pub fn get_connection_at(&self, position: Vector2<i32>, view: WireView) -> Option<ConnectionID> {
+        self.connections.values()
+            .find(|connection| connection.touches(position, view, self))
+            .map(|connection| connection.id())
+    }
+
     pub fn get_block_at(&self, position: Vector2<i32>) -> Option<BlockID> {
         for (i, block) in self.blocks.iter() {
             if block.touches(position) {
@@ -167,6 +260,22 @@ impl Plot {
         None
     }
 
+    // finds the free input pin closest to `position` within `distance` pixels
+    pub fn get_input_near(&self, position: Vector2<i32>, distance: i32) -> Option<(Port, Vector2<i32>)> {
+        self.blocks.values()
+            .flat_map(|block| block.inputs().iter().enumerate()
+                .filter(|(_, connection)| connection.is_none())
+                .map(move |(i, _)| (Port::Input(block.id(), i as u8), block.get_connector_pos(Connector::Input(i as u8))))
+            )
+            .map(|(port, pin)| {
+                let delta = pin - position;
+                (port, pin, delta.0 * delta.0 + delta.1 * delta.1)
+            })
+            .filter(|(_, _, distance_sq)| *distance_sq <= distance * distance)
+            .min_by_key(|(_, _, distance_sq)| *distance_sq)
+            .map(|(port, pin, _)| (port, pin))
+    }
+
     pub fn connections(&self) -> &HashMap<ConnectionID, Connection> {
         &self.connections
     }
@@ -190,15 +299,20 @@ impl Plot {
         }
     }
 
-    fn add_to_existing_connection(&mut self, existing: ConnectionID, connection: &Connection) {
-        if let Some(existing) = self.connections.get_mut(&existing) {
-            for segment in connection.segments().values() {
-                existing.add_segment(segment.clone());
-            }
-            let destinations = existing.destinations();
-            let id = existing.id();
-            self.patch_destinations(destinations, id);
-        }
+    // returns the segments the existing connection had before
+    fn add_to_existing_connection(&mut self, existing: ConnectionID, connection: &Connection) -> Option<HashMap<Id, Segment>> {
+        let existing = self.connections.get_mut(&existing)?;
+        let previous = existing.segments().clone();
+
+        // keep the ids of the new segments so that redoing the merge restores them exactly
+        let mut segments = previous.clone();
+        segments.extend(connection.segments().iter().map(|(id, segment)| (*id, segment.clone())));
+        existing.set_segments(segments);
+
+        let destinations = existing.destinations();
+        let id = existing.id();
+        self.patch_destinations(destinations, id);
+        Some(previous)
     }
 
     pub unsafe fn add_connection_unsafe(&mut self, connection: Connection) {
@@ -206,12 +320,14 @@ impl Plot {
         self.connections.insert(connection.id(), connection);
     }
 
-    pub fn add_connection(&mut self, connection: Connection) {
+    // if the origin pin already drives a net, the connection is merged into it instead;
+    // the id and previous segments of that net are returned so the merge can be undone
+    pub fn add_connection(&mut self, connection: Connection) -> Option<(ConnectionID, HashMap<Id, Segment>)> {
         let origin = self.blocks.get_mut(&connection.origin().block_id()).expect("faulty origin block");
 
         if let Some(existing) = origin.connection(connection.origin().into()) {
-            self.add_to_existing_connection(existing, &connection);
-            return;
+            self.to_update.insert(connection.origin().block_id());
+            return self.add_to_existing_connection(existing, &connection).map(|previous| (existing, previous));
         }
         
         origin.set_connection(connection.origin().into(), Some(connection.id()));
@@ -219,13 +335,334 @@ impl Plot {
         self.patch_destinations(connection.destinations(), connection.id());
         self.to_update.insert(connection.origin().block_id());
         self.connections.insert(connection.id(), connection);
+        None
+    }
+
+    // takes the segments of `merged` back out of the net `existing`, freeing the pins only they reached
+    pub fn unmerge_connection(&mut self, existing: ConnectionID, previous: HashMap<Id, Segment>, merged: &Connection) {
+        let Some(connection) = self.connections.get_mut(&existing) else {
+            return;
+        };
+        connection.set_segments(previous);
+        let remaining = connection.destinations();
+        self.to_update.insert(connection.origin().block_id());
+
+        for destination in merged.destinations() {
+            let still_reached = remaining.iter().any(|port| port.block_id() == destination.block_id() && port.index() == destination.index());
+            if !still_reached && let Some(block) = self.blocks.get_mut(&destination.block_id()) && block.connection(destination.into()) == Some(existing) {
+                block.set_connection(destination.into(), None);
+            }
+            self.to_update.insert(destination.block_id());
+        }
+    }
+
+    // the circuit without its ids: every block as its position, module and whether it is disabled, in that order,
+    // and every wire as the pins it connects, each given by its block's index in the first list
+    pub fn layout(&self) -> (Vec<(Vector2<i32>, String, bool)>, Vec<((usize, u8), Vec<(usize, u8)>)>) {
+        let mut blocks = self.blocks.values().collect::<Vec<_>>();
+        blocks.sort_by_key(|block| (block.position(), block.module_id().clone(), block.disabled()));
+        let index = blocks.iter().enumerate().map(|(i, block)| (block.id(), i)).collect::<HashMap<_, _>>();
+        let pin = |port: Port| (index[&port.block_id()], port.index());
+
+        let mut wires = self.connections.values()
+            .map(|connection| {
+                let mut destinations = connection.destinations().into_iter().map(pin).collect::<Vec<_>>();
+                destinations.sort();
+                (pin(connection.origin()), destinations)
+            })
+            .collect::<Vec<_>>();
+        wires.sort();
+
+        let blocks = blocks.into_iter().map(|block| (block.position(), block.module_id().clone(), block.disabled())).collect();
+        (blocks, wires)
+    }
+
+    // every net as its driving output and the inputs it feeds, named nets first
+    pub fn net_list(&self) -> Vec<(ConnectionID, Port, Vec<Port>)> {
+        let reading_order = |port: &Port| self.get_block(port.block_id())
+            .map(|block| (block.position().1, block.position().0, block.id(), port.index()));
+
+        let mut nets = self.connections.values()
+            .map(|connection| {
+                let mut sinks = connection.destinations();
+                sinks.sort_by_key(reading_order);
+                (connection, sinks)
+            })
+            .collect::<Vec<_>>();
+        nets.sort_by_key(|(connection, _)| (connection.name().is_none(), connection.name().clone(), reading_order(&connection.origin())));
+
+        nets.into_iter()
+            .map(|(connection, sinks)| (connection.id(), connection.origin(), sinks))
+            .collect()
+    }
+
+    // names a pin for listings, like `And (100, 50) in 2`
+    pub fn describe_port(&self, port: &Port) -> String {
+        let (direction, index) = match port {
+            Port::Input(_, index) => ("in", index),
+            Port::Output(_, index) => ("out", index)
+        };
+        match self.get_block(port.block_id()) {
+            Some(block) => format!("{} ({}, {}) {direction} {}", block.name(), block.position().0, block.position().1, index + 1),
+            None => format!("missing block {direction} {}", index + 1)
+        }
+    }
+
+    // walks back from a net to the blocks currently determining its value, stopping at
+    // inputs and stateful elements
+    pub fn trace_source(&self, project: &Project, connection_id: &ConnectionID) -> Vec<BlockID> {
+        let mut chain = Vec::new();
+        let mut pending = self.get_connection(connection_id).map(|connection| connection.origin().block_id()).into_iter().collect::<Vec<_>>();
+
+        while let Some(block_id) = pending.pop() {
+            if chain.contains(&block_id) {
+                continue;
+            }
+            let Some(block) = self.get_block(block_id) else {
+                continue;
+            };
+            chain.push(block_id);
+
+            let category = project.module(block.module_id()).map(|module| module.category());
+            if !matches!(category, Some(Category::Gate | Category::Custom)) {
+                continue;
+            }
+
+            let inputs = block.inputs().iter()
+                .filter_map(|id| id.and_then(|id| self.get_connection(&id)))
+                .map(|connection| (connection.origin().block_id(), connection.is_active()))
+                .collect::<Vec<_>>();
+            let output = block.outputs().iter()
+                .find_map(|id| id.and_then(|id| self.get_connection(&id)))
+                .map_or(false, |connection| connection.is_active());
+
+            // only the inputs that force a gate's output matter, e.g. the low inputs of a low And
+            let controlling = match block.module_id().as_str() {
+                "And" | "Nand" if output == (block.module_id() == "Nand") => Some(false),
+                "Or" | "Nor" if output != (block.module_id() == "Nor") => Some(true),
+                _ => None
+            };
+            pending.extend(inputs.into_iter()
+                .filter(|(_, active)| controlling.map_or(true, |value| *active == value))
+                .map(|(origin, _)| origin)
+            );
+        }
+
+        chain
+    }
+
+    // exchanges the connections attached to two input pins of a block
+    pub fn swap_inputs(&mut self, block_id: BlockID, a: u8, b: u8) {
+        let Some(block) = self.blocks.get_mut(&block_id) else {
+            return;
+        };
+        let (first, second) = (block.connection(Connector::Input(a)), block.connection(Connector::Input(b)));
+        block.set_connection(Connector::Input(a), second);
+        block.set_connection(Connector::Input(b), first);
+
+        // a connection attached to both pins must only be patched once
+        let second = second.filter(|id| Some(*id) != first);
+        for connection_id in [first, second].into_iter().flatten() {
+            if let Some(connection) = self.connections.get_mut(&connection_id) {
+                connection.for_each_mut_segment(|segment| match segment {
+                    Segment::Block(id, port) if *id == block_id && *port == a => *port = b,
+                    Segment::Block(id, port) if *id == block_id && *port == b => *port = a,
+                    _ => ()
+                });
+            }
+        }
+        self.to_update.insert(block_id);
+    }
+
+    // every input pin reached by a wire together with the output pin and connection driving it
+    fn driven_inputs(&self) -> Vec<(Port, (BlockID, u8), ConnectionID)> {
+        let mut connections = self.connections.values().collect::<Vec<_>>();
+        connections.sort_by_key(|connection| connection.id());
+
+        connections.into_iter()
+            .flat_map(|connection| {
+                let origin = (connection.origin().block_id(), connection.origin().index());
+                connection.destinations().into_iter().map(move |port| (port, origin, connection.id()))
+            })
+            .collect()
+    }
+
+    // input pins with more than one driver, each with the first driving output pin and wire and a
+    // conflicting one
+    pub fn multiple_drivers(&self) -> Vec<(Port, ((BlockID, u8), ConnectionID), ((BlockID, u8), ConnectionID))> {
+        let mut drivers = HashMap::new();
+        let mut conflicts = Vec::new();
+        for (port, origin, connection_id) in self.driven_inputs() {
+            match drivers.get(&(port.block_id(), port.index())) {
+                // the same output pin twice is a duplicate wire, not a conflict
+                Some((first, _)) if *first == origin => (),
+                Some(first) => conflicts.push((port, *first, (origin, connection_id))),
+                None => { drivers.insert((port.block_id(), port.index()), (origin, connection_id)); }
+            }
+        }
+        conflicts
+    }
+
+    // remembers which wires and output pins take part in a drive conflict, so they can be drawn as such
+    pub fn mark_drive_conflicts(&mut self) {
+        let mut conflicts = (HashSet::new(), HashSet::new());
+        for (_, first, second) in self.multiple_drivers() {
+            for (origin, connection_id) in [first, second] {
+                conflicts.0.insert(connection_id);
+                conflicts.1.insert(origin);
+            }
+        }
+        self.conflicts = conflicts;
+    }
+
+    pub fn has_drive_conflicts(&self) -> bool {
+        !self.conflicts.0.is_empty()
+    }
+
+    pub fn in_drive_conflict(&self, connection_id: ConnectionID) -> bool {
+        self.conflicts.0.contains(&connection_id)
+    }
+
+    pub fn is_conflicting_driver(&self, block_id: BlockID, port: u8) -> bool {
+        self.conflicts.1.contains(&(block_id, port))
+    }
+
+    // wires between the same output and input pin as another wire, as the input pin, the connection
+    // that pin is linked to and the redundant one
+    pub fn duplicate_connections(&self) -> Vec<(Port, ConnectionID, ConnectionID)> {
+        let mut seen = HashMap::new();
+        let mut duplicates = Vec::new();
+        for (port, origin, connection_id) in self.driven_inputs() {
+            match seen.get(&(origin, port.block_id(), port.index())) {
+                Some(first) if *first != connection_id => {
+                    let linked = self.get_block(port.block_id()).and_then(|block| block.connection(port.into()));
+                    let (kept, redundant) = if linked == Some(connection_id) { (connection_id, *first) } else { (*first, connection_id) };
+                    duplicates.push((port, kept, redundant));
+                }
+                Some(_) => (),
+                None => { seen.insert((origin, port.block_id(), port.index()), connection_id); }
+            }
+        }
+        duplicates
+    }
+
+    // redundant wires that only duplicate others and can be removed without losing a destination,
+    // each with the connection it duplicates
+    pub fn removable_duplicates(&self) -> Vec<(ConnectionID, Connection)> {
+        let duplicates = self.duplicate_connections();
+        let mut removable: Vec<(ConnectionID, Connection)> = Vec::new();
+        for (_, kept, redundant) in duplicates.iter() {
+            if removable.iter().any(|(_, connection)| connection.id() == *redundant || connection.id() == *kept) {
+                continue;
+            }
+            let Some(connection) = self.get_connection(redundant) else {
+                continue;
+            };
+            let covered = connection.destinations().iter().all(|destination| duplicates.iter().any(|(port, other, duplicate)|
+                duplicate == redundant && other == kept && port.block_id() == destination.block_id() && port.index() == destination.index()
+            ));
+            if covered {
+                removable.push((*kept, connection.clone()));
+            }
+        }
+        removable
+    }
+
+    // all blocks from left to right, top to bottom
+    pub fn blocks_in_reading_order(&self) -> Vec<BlockID> {
+        let mut blocks = self.blocks.values().collect::<Vec<_>>();
+        blocks.sort_by_key(|block| (block.position().1, block.position().0, block.id()));
+        blocks.into_iter().map(|block| block.id()).collect()
+    }
+
+    // the block after `current` in reading order, or before it if `backwards`, wrapping around at the ends;
+    // without a current block it is the first or the last block
+    pub fn next_in_reading_order(&self, current: Option<BlockID>, backwards: bool) -> Option<BlockID> {
+        let order = self.blocks_in_reading_order();
+        if order.is_empty() {
+            return None;
+        }
+        let next = match current.and_then(|id| order.iter().position(|other| *other == id)) {
+            Some(i) if backwards => (i + order.len() - 1) % order.len(),
+            Some(i) => (i + 1) % order.len(),
+            None if backwards => order.len() - 1,
+            None => 0
+        };
+        Some(order[next])
+    }
+
+    // the closest block whose center lies in `direction` of the center of `from`, preferring blocks
+    // within 45 degrees of that direction
+    pub fn nearest_block_in_direction(&self, from: BlockID, direction: Vector2<i32>) -> Option<BlockID> {
+        let center = |block: &Block| block.position() + Vector2(block.size().0 / 2, block.size().1 / 2);
+        let origin = center(self.get_block(from)?);
+
+        let candidates = self.blocks.values()
+            .filter(|block| block.id() != from)
+            .filter_map(|block| {
+                let offset = center(block) - origin;
+                let along = offset.0 * direction.0 + offset.1 * direction.1;
+                let across = (offset.0 * direction.1 - offset.1 * direction.0).abs();
+                (along > 0).then_some((along < across, along + across * 2, block.id()))
+            });
+        candidates.min().map(|(_, _, block_id)| block_id)
+    }
+
+    // groups of blocks placed at exactly the same position, each group and the groups themselves ordered by id
+    pub fn stacked_blocks(&self) -> Vec<Vec<BlockID>> {
+        let mut positions: HashMap<(i32, i32), Vec<BlockID>> = HashMap::new();
+        for block in self.blocks.values() {
+            positions.entry((block.position().0, block.position().1)).or_default().push(block.id());
+        }
+
+        let mut stacks = positions.into_values()
+            .filter(|stack| stack.len() > 1)
+            .map(|mut stack| {
+                stack.sort();
+                stack
+            })
+            .collect::<Vec<_>>();
+        stacks.sort();
+        stacks
+    }
+
+    // new positions that spread every stack of blocks downwards, leaving the first block of each in place
+    pub fn separate_stacked_blocks(&self) -> Vec<(BlockID, Vector2<i32>, Vector2<i32>)> {
+        let mut moves = Vec::new();
+        for stack in self.stacked_blocks() {
+            let mut offset = 0;
+            for (i, block_id) in stack.iter().enumerate() {
+                let Some(block) = self.get_block(*block_id) else {
+                    continue;
+                };
+                if i > 0 {
+                    moves.push((*block_id, block.position(), block.position() + Vector2(0, offset)));
+                }
+                // round up to whole grid cells so the blocks stay aligned
+                offset += (block.size().1 + GRID_SIZE * 2 - 1) / GRID_SIZE * GRID_SIZE;
+            }
+        }
+        moves
+    }
+
+    // re-inserts a previously removed connection and re-links all of its endpoints
+    pub fn restore_connection(&mut self, connection: Connection) {
+        let id = connection.id();
+        for port in std::iter::once(connection.origin()).chain(connection.destinations()) {
+            if let Some(block) = self.blocks.get_mut(&port.block_id()) {
+                block.set_connection(port.into(), Some(id));
+            }
+            self.to_update.insert(port.block_id());
+        }
+        self.connections.insert(id, connection);
     }
 
     pub fn remove_connection(&mut self, id: ConnectionID) -> Option<Connection> {
         if let Some(c) = self.connections.get(&id) {
             let mut connection = c.clone();
             let mut refactor = |port: Port| {
-                if let Some(block) = self.get_block_mut(port.block_id()) {
+                // a pin may have been taken over by another connection in the meantime
+                if let Some(block) = self.get_block_mut(port.block_id()) && block.connection(port.into()) == Some(id) {
                     block.set_connection(port.into(), None);
                 }
                 self.to_update.insert(port.block_id());
@@ -245,6 +682,38 @@ impl Plot {
         None
     }
 
+    // instances whose pin counts differ from their module's, e.g. after the module was changed in another session,
+    // with the counts they should have
+    pub fn pin_mismatches(&self, project: &Project) -> Vec<(BlockID, u8, u8)> {
+        let mut mismatches = self.blocks.values()
+            .filter(|block| !block.unique())
+            .filter_map(|block| {
+                let module = project.module(block.module_id())?;
+                let (inputs, outputs) = (module.get_num_inputs(), module.get_num_outputs());
+                (block.inputs().len() != inputs as usize || block.outputs().len() != outputs as usize).then_some((block.id(), inputs, outputs))
+            })
+            .collect::<Vec<_>>();
+        mismatches.sort();
+        mismatches
+    }
+
+    // gives a block new pin counts, removing the wires on pins that no longer exist;
+    // returns the block as it was and the removed wires
+    pub fn fit_pins(&mut self, block_id: BlockID, num_inputs: u8, num_outputs: u8) -> Option<(Block, Vec<Connection>)> {
+        let block = self.blocks.get(&block_id)?.clone();
+        let removed = block.inputs().iter().skip(num_inputs as usize)
+            .chain(block.outputs().iter().skip(num_outputs as usize))
+            .filter_map(|connection_id| *connection_id)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .filter_map(|connection_id| self.remove_connection(connection_id))
+            .collect();
+
+        self.blocks.get_mut(&block_id)?.set_pin_counts(num_inputs, num_outputs);
+        self.to_update.insert(block_id);
+        Some((block, removed))
+    }
+
     pub fn delete_block(&mut self, id: BlockID) -> Vec<Connection> {
         let mut deleted_connections = vec![];
         let mut unique = false;
@@ -256,6 +725,14 @@ impl Plot {
                 .collect();
         }
 
+        // connections still attached to the block without it knowing about them would be left dangling
+        let mut dangling = self.connections.values()
+            .filter(|connection| std::iter::once(connection.origin()).chain(connection.destinations()).any(|port| port.block_id() == id))
+            .map(|connection| connection.id())
+            .collect::<Vec<_>>();
+        dangling.sort();
+        deleted_connections.extend(dangling.into_iter().filter_map(|connection_id| self.remove_connection(connection_id)));
+
         if !unique {
             self.blocks.remove(&id);
         }
@@ -263,10 +740,348 @@ impl Plot {
         deleted_connections
     }
 
+    // renumbers all blocks, wires and bend points in a fixed order, returning the old-to-new block
+    // and connection ids so that references from outside the plot can follow
+    pub fn normalize_ids(&mut self) -> IdMapping {
+        self.unhighlight();
+        self.selection = Selection::None;
+
+        let mut block_order = self.blocks.values()
+            .map(|block| (block.position().1, block.position().0, block.id()))
+            .collect::<Vec<_>>();
+        block_order.sort();
+        let block_ids = block_order.into_iter()
+            .enumerate()
+            .map(|(index, (_, _, id))| (id, Id::from_index(index)))
+            .collect::<HashMap<_, _>>();
+
+        let mut connection_order = self.connections.values()
+            .map(|connection| (block_ids.get(&connection.origin().block_id()).copied(), connection.origin().index(), connection.id()))
+            .collect::<Vec<_>>();
+        connection_order.sort();
+        let connection_ids = connection_order.into_iter()
+            .enumerate()
+            .map(|(index, (_, _, id))| (id, Id::from_index(index)))
+            .collect::<HashMap<_, _>>();
+
+        self.blocks = std::mem::take(&mut self.blocks).into_values()
+            .map(|mut block| {
+                let id = block_ids[&block.id()];
+                block.set_id(id);
+                block.connections_mut().flatten().for_each(|connection_id| if let Some(new_id) = connection_ids.get(connection_id) {
+                    *connection_id = *new_id;
+                });
+                (id, block)
+            })
+            .collect();
+
+        self.connections = std::mem::take(&mut self.connections).into_values()
+            .map(|mut connection| {
+                let id = connection_ids[&connection.id()];
+                connection.set_id(id);
+                connection.remap_ids(&block_ids);
+                (id, connection)
+            })
+            .collect();
+
+        self.states.iter_mut().for_each(|state| state.remap_ids(&block_ids, &connection_ids));
+        self.to_update = self.to_update.iter().map(|id| block_ids.get(id).copied().unwrap_or(*id)).collect();
+        if let Some(activity) = self.activity.as_mut() {
+            *activity = std::mem::take(activity).into_iter()
+                .filter_map(|(id, count)| Some((*block_ids.get(&id)?, count)))
+                .collect();
+        }
+
+        (block_ids, connection_ids)
+    }
+
+    // the number of wires, their summed length and the wire reaching the most inputs with how many it reaches
+    pub fn wire_stats(&self) -> (usize, f64, Option<(ConnectionID, usize)>) {
+        let length = self.connections.values().map(|connection| connection.length(self)).sum();
+        let busiest = self.connections.values()
+            .map(|connection| (connection.id(), connection.destinations().len()))
+            .max_by_key(|(id, count)| (*count, cmp::Reverse(*id)));
+        (self.connections.len(), length, busiest)
+    }
+
+    // rough measure of how much work drawing the plot is
+    pub fn complexity(&self) -> usize {
+        self.blocks.len() + self.connections.len()
+    }
+
+    // true once no block is waiting to be simulated again
+    pub fn is_stable(&self) -> bool {
+        self.to_update.is_empty()
+    }
+
+    pub fn passes(&self) -> u32 {
+        self.passes
+    }
+
+    // remembers what every block displays now, returning whether anything differs from the last redraw
+    pub fn take_redraw(&mut self) -> bool {
+        let before = self.drawn.len();
+        self.drawn.retain(|block_id, _| self.blocks.contains_key(block_id));
+        let mut changes = self.drawn.len() != before;
+
+        for (block_id, block) in self.blocks.iter() {
+            let state = block.displayed_state();
+            if self.drawn.insert(*block_id, state) != Some(state) {
+                changes = true;
+            }
+        }
+        changes
+    }
+
+    // whether a wire from the output pin `origin` to the input pin `destination` can be added
+    pub fn check_connection(&self, origin: (BlockID, u8), destination: (BlockID, u8)) -> Result<(), String> {
+        let origin_block = self.get_block(origin.0).ok_or_else(|| String::from("The origin block does not exist."))?;
+        if origin.1 as usize >= origin_block.outputs().len() {
+            return Err(format!("{} has no output {}.", origin_block.name(), origin.1));
+        }
+
+        let destination_block = self.get_block(destination.0).ok_or_else(|| String::from("The destination block does not exist."))?;
+        if destination.1 as usize >= destination_block.inputs().len() {
+            return Err(format!("{} has no input {}.", destination_block.name(), destination.1));
+        }
+        if destination_block.connection(Connector::Input(destination.1)).is_some() {
+            return Err(format!("Input {} of {} is already connected.", destination.1, destination_block.name()));
+        }
+        Ok(())
+    }
+
+    // moves the end of a wire from one input pin to another, leaving the rest of the wire as it is
+    pub fn reanchor_connection(&mut self, connection_id: ConnectionID, from: (BlockID, u8), to: (BlockID, u8)) -> bool {
+        let Some(connection) = self.connections.get_mut(&connection_id) else {
+            return false;
+        };
+        if !connection.destinations().iter().any(|port| port.block_id() == from.0 && port.index() == from.1) {
+            return false;
+        }
+
+        connection.for_each_mut_segment(|segment| if let Segment::Block(id, port) = segment && *id == from.0 && *port == from.1 {
+            *id = to.0;
+            *port = to.1;
+        });
+        let origin = connection.origin().block_id();
+
+        if let Some(block) = self.blocks.get_mut(&from.0) && block.connection(Connector::Input(from.1)) == Some(connection_id) {
+            block.set_connection(Connector::Input(from.1), None);
+        }
+        if let Some(block) = self.blocks.get_mut(&to.0) {
+            block.set_connection(Connector::Input(to.1), Some(connection_id));
+        }
+
+        self.to_update.extend([origin, from.0, to.0]);
+        true
+    }
+
+    // the only free output and the only free input of the two selected blocks
+    pub fn selected_pins(&self) -> Result<((BlockID, u8), (BlockID, u8)), String> {
+        let selected = self.selection.blocks();
+        if selected.len() != 2 {
+            return Err(String::from("Select two blocks to connect their free pins."));
+        }
+
+        let free = |connectors: fn(&Block) -> &Vec<Option<ConnectionID>>| selected.iter()
+            .filter_map(|block_id| self.get_block(*block_id).map(|block| (*block_id, connectors(block))))
+            .flat_map(|(block_id, connections)| connections.iter().enumerate()
+                .filter(|(_, connection)| connection.is_none())
+                .map(move |(i, _)| (block_id, i as u8))
+            )
+            .collect::<Vec<_>>();
+        let (outputs, inputs) = (free(Block::outputs), free(Block::inputs));
+
+        match (outputs.as_slice(), inputs.as_slice()) {
+            ([output], [input]) if output.0 == input.0 => Err(String::from("The free output and input belong to the same block.")),
+            ([output], [input]) => self.check_connection(*output, *input).map(|_| (*output, *input)),
+            _ => Err(format!(
+                "The selected blocks have {} free output(s) and {} free input(s); exactly one of each is needed.",
+                outputs.len(), inputs.len()
+            ))
+        }
+    }
+
+    // splits the wire so that it runs through `block` instead, which must have one input and one output,
+    // returning the original wire, the wire into the block (keeping the original's id and name) and the
+    // one from the block to all former destinations
+    pub fn splice_block(&self, block: &Block, connection_id: ConnectionID) -> Option<(Connection, Connection, Connection)> {
+        if block.inputs().len() != 1 || block.outputs().len() != 1 {
+            return None;
+        }
+
+        let original = self.get_connection(&connection_id)?.clone();
+        let mut upstream = original.clone();
+        upstream.set_segments(HashMap::from([(Id::new(), Segment::Block(block.id(), 0))]));
+
+        let downstream = Connection::new(
+            Port::Output(block.id(), 0),
+            original.destinations().into_iter().map(|port| Segment::Block(port.block_id(), port.index())).collect()
+        );
+        Some((original, upstream, downstream))
+    }
+
+    // a routed wire between the selected pins, see `selected_pins()`
+    pub fn connect_selected_pins(&self) -> Result<Connection, String> {
+        let ((origin_id, output), (destination_id, input)) = self.selected_pins()?;
+        let mut connection = Connection::new_basic(origin_id, output, destination_id, input);
+        if let Some(segments) = routing::Router::new(self).route_connection(self, &connection) {
+            connection.set_segments(segments);
+        }
+        Ok(connection)
+    }
+
     pub fn add_block_to_update(&mut self, block: BlockID) {
         self.to_update.insert(block);
     }
 
+    // top-left and bottom-right corner around all blocks in the plot
+    pub fn content_bounds(&self) -> Option<(Vector2<i32>, Vector2<i32>)> {
+        self.blocks.values()
+            .map(|block| block.bounds())
+            .reduce(|(min, max), (start, end)| (
+                Vector2(cmp::min(min.0, start.0), cmp::min(min.1, start.1)),
+                Vector2(cmp::max(max.0, end.0), cmp::max(max.1, end.1))
+            ))
+    }
+
+    pub fn set_activity_tracking(&mut self, enabled: bool) {
+        self.activity = enabled.then(HashMap::new);
+    }
+
+    pub fn activity(&self) -> Option<&HashMap<BlockID, u32>> {
+        self.activity.as_ref()
+    }
+
+    pub fn flagged(&self, block_id: BlockID) -> Option<validation::Severity> {
+        self.flagged.get(&block_id).copied()
+    }
+
+    pub fn set_flagged(&mut self, flagged: HashMap<BlockID, validation::Severity>) {
+        self.flagged = flagged;
+    }
+
+    pub fn view(&self) -> Option<(Vector2<f64>, f64)> {
+        self.view
+    }
+
+    pub fn set_view(&mut self, translation: Vector2<f64>, scale: f64) {
+        self.view = Some((translation, scale));
+    }
+
+    pub fn background(&self) -> Option<Color> {
+        self.background
+    }
+
+    pub fn set_background(&mut self, background: Option<Color>) {
+        self.background = background;
+    }
+
+    pub fn grid_style(&self) -> GridStyle {
+        self.grid_style
+    }
+
+    pub fn set_grid_style(&mut self, grid_style: GridStyle) {
+        self.grid_style = grid_style;
+    }
+
+    // replaces the selection with every block of one of the given modules
+    pub fn select_module_instances(&mut self, module_ids: &HashSet<String>) {
+        self.unhighlight();
+        let mut selected = self.blocks.values_mut()
+            .filter(|block| module_ids.contains(block.module_id()))
+            .map(|block| {
+                block.set_highlighted(true);
+                Selectable::Block(block.id())
+            })
+            .collect::<Vec<_>>();
+        selected.sort_by_key(|selectable| selectable.block_id());
+        self.selection = Selection::Many(selected);
+    }
+
+    // a copy holding only the selected blocks and the wires between them
+    pub fn selection_only(&self) -> Self {
+        let selected = self.selected().iter().filter_map(|selected| selected.block_id()).collect::<HashSet<_>>();
+        let mut plot = self.clone();
+        plot.blocks.retain(|id, _| selected.contains(id));
+        plot.connections.retain(|_, connection| std::iter::once(connection.origin()).chain(connection.destinations())
+            .all(|port| selected.contains(&port.block_id()))
+        );
+        plot
+    }
+
+    // selected wires and the wires running only between selected blocks, sorted by id
+    pub fn selected_connections(&self) -> Vec<ConnectionID> {
+        let selected = self.selected();
+        let blocks = selected.iter().filter_map(|selected| selected.block_id()).collect::<HashSet<_>>();
+        let mut connections = self.connections.values()
+            .filter(|connection| selected.contains(&Selectable::Connection(connection.id())) || (
+                !blocks.is_empty() && std::iter::once(connection.origin()).chain(connection.destinations())
+                    .all(|port| blocks.contains(&port.block_id()))
+            ))
+            .map(|connection| connection.id())
+            .collect::<Vec<_>>();
+        connections.sort();
+        connections
+    }
+
+    // the first `count` of `net1`, `net2`, ... that no wire in this plot is called yet
+    pub fn unused_net_names(&self, count: usize) -> Vec<String> {
+        let taken = self.connections.values().filter_map(|connection| connection.name().as_ref()).collect::<HashSet<_>>();
+        (1..).map(|i| format!("net{i}"))
+            .filter(|name| !taken.contains(name))
+            .take(count)
+            .collect()
+    }
+
+    // ends a press on empty canvas that started with `previous` selected: a click clears the selection and a
+    // drag selects what lies in the marquee, unless `additive` keeps the previous selection and adds to it
+    pub fn finish_marquee(&mut self, previous: Vec<Selectable>, area: (Vector2<i32>, Vector2<i32>), additive: bool, click: bool) {
+        // put back what was selected before, so that it is unhighlighted properly
+        self.set_selection(Selection::Many(previous.clone()));
+        if !additive {
+            self.unhighlight();
+        }
+
+        if click {
+            return;
+        }
+
+        self.set_selection(Selection::Area(area.0, area.1));
+        self.highlight_area();
+        if additive {
+            let mut selected = self.selected();
+            selected.retain(|item| !previous.contains(item));
+            selected.splice(0..0, previous);
+            self.set_selection(Selection::Many(selected));
+        }
+    }
+
+    // returns the top-left and bottom-right corner around all selected blocks and waypoints
+    pub fn selection_bounds(&self) -> Option<(Vector2<i32>, Vector2<i32>)> {
+        self.selected().iter()
+            .filter_map(|selected| match selected {
+                Selectable::Block(id) => self.get_block(*id).map(|block| block.bounds()),
+                Selectable::Waypoint(id) => self.get_connection(id.connection_id())
+                    .and_then(|connection| connection.get_segment(id.location()))
+                    .and_then(|segment| segment.position())
+                    .map(|position| (*position, *position)),
+                Selectable::Connection(id) => self.get_connection(id).and_then(|connection|
+                    std::iter::once(connection.origin()).chain(connection.destinations())
+                        .filter_map(|port| self.get_block(port.block_id()).map(|block| block.get_connector_pos(port.into())))
+                        .map(|position| (position, position))
+                        .reduce(|(min, max), (position, _)| (
+                            Vector2(cmp::min(min.0, position.0), cmp::min(min.1, position.1)),
+                            Vector2(cmp::max(max.0, position.0), cmp::max(max.1, position.1))
+                        ))
+                )
+            })
+            .reduce(|(min, max), (start, end)| (
+                Vector2(cmp::min(min.0, start.0), cmp::min(min.1, start.1)),
+                Vector2(cmp::max(max.0, end.0), cmp::max(max.1, end.1))
+            ))
+    }
+
     pub fn to_update(&self) -> &HashSet<BlockID> {
         &self.to_update
     }
@@ -281,16 +1096,21 @@ impl Plot {
         }
     }
 
+    pub const PIN_SNAP_DISTANCE: i32 = 20;
+
     const RECURSION_CAP: u8 = 100;
 
     pub fn simulate(&mut self, project: &mut Project, call_stack: &mut HashSet<String>) -> SimResult<bool> {
         let mut updated = HashMap::new();
         let mut queued = HashSet::new();
         let mut changes = false;
+        let mut output_changes: HashMap<BlockID, u32> = HashMap::new();
+        let mut passes = 0;
 
         while !self.to_update.is_empty() {
             let to_update = std::mem::take(&mut self.to_update);
             changes = true;
+            passes += 1;
             
             for block_id in to_update.iter() {
                 if updated.contains_key(block_id) {
@@ -303,7 +1123,9 @@ impl Plot {
                 }
 
                 if let Some(block) = self.blocks.get_mut(block_id) {
-                    block.simulate(&mut self.connections, &mut self.to_update, &mut queued, project, call_stack)?;
+                    if block.simulate(&mut self.connections, &mut self.to_update, &mut queued, project, call_stack)? && self.activity.is_some() {
+                        *output_changes.entry(*block_id).or_default() += 1;
+                    }
 
                     if !updated.contains_key(block_id) {
                         updated.insert(*block_id, 0);   
@@ -313,27 +1135,135 @@ impl Plot {
         }
 
         self.to_update = queued;
+        self.passes = passes;
+
+        if let Some(activity) = self.activity.as_mut() {
+            for (block_id, count) in output_changes {
+                let peak = activity.entry(block_id).or_default();
+                *peak = (*peak).max(count);
+            }
+        }
 
         Ok(changes)
     }
 }
 
+impl Plot {
+    // debugging overlay: the number of connections of each block and the fan-out of each net
+    fn render_connection_counts<R>(&self, renderer: &R, screen_space: &ScreenSpace) -> Result<(), R::Error>
+        where R: Renderer
+    {
+        renderer.set_font_size(11.).set_color(unsafe { &COLOR_THEME.suggestion_fg_color });
+
+        for block in self.blocks.values().filter(|block| block.is_in_area(screen_space)) {
+            let count = block.connected_to().len().to_string();
+            renderer.move_to(Vector2(block.position().0 + block.size().0 - 8 * count.len() as i32 - 5, block.position().1 - 5))
+                .show_text(&count)?;
+        }
+
+        for connection in self.connections.values() {
+            if let Some(origin) = self.get_block(connection.origin().block_id()) {
+                let position = origin.get_connector_pos(connection.origin().into());
+                renderer.move_to(Vector2(position.0 + 8, position.1 - 8))
+                    .show_text(&format!("×{}", connection.destinations().len()))?;
+            }
+        }
+
+        renderer.set_font_size(DEFAULT_FONT_SIZE);
+        Ok(())
+    }
+
+    // the selected blocks, their direct neighbors and the wires between them; None without selected blocks
+    pub fn focused(&self) -> Option<(HashSet<BlockID>, HashSet<ConnectionID>)> {
+        let selected: HashSet<BlockID> = self.selection.blocks().into_iter().collect();
+        if selected.is_empty() {
+            return None;
+        }
+
+        let mut blocks = selected.clone();
+        let mut connections = HashSet::new();
+        for connection in self.connections.values() {
+            let destinations = connection.destinations();
+            let touches_selection = selected.contains(&connection.origin().block_id())
+                || destinations.iter().any(|port| selected.contains(&port.block_id()));
+            if touches_selection {
+                connections.insert(connection.id());
+                blocks.insert(connection.origin().block_id());
+                blocks.extend(destinations.iter().map(|port| port.block_id()));
+            }
+        }
+
+        Some((blocks, connections))
+    }
+
+    // snapshot annotation: the current value of each net below its origin pin, prefixed by its name
+    fn render_value_labels<R>(&self, renderer: &R) -> Result<(), R::Error>
+        where R: Renderer
+    {
+        renderer.set_font_size(11.).set_color(unsafe { &COLOR_THEME.accent_fg_color });
+
+        for connection in self.connections.values() {
+            if let Some(origin) = self.get_block(connection.origin().block_id()) {
+                let position = origin.get_connector_pos(connection.origin().into());
+                let value = connection.is_active() as u8;
+                let label = match connection.name() {
+                    Some(name) => format!("{name} = {value}"),
+                    None => value.to_string()
+                };
+                renderer.move_to(Vector2(position.0 + 8, position.1 + 18))
+                    .show_text(&label)?;
+            }
+        }
+
+        renderer.set_font_size(DEFAULT_FONT_SIZE);
+        Ok(())
+    }
+}
+
 impl Renderable for Plot {
     fn render<R>(&self, renderer: &R, plot: &Plot) -> Result<(), R::Error>
         where R: Renderer
     {
         let screen_space = renderer.screen_space();
+        let focused = if renderer.focus() { self.focused() } else { None };
+        let focus_active = focused.is_some();
+        let (focused_blocks, focused_connections) = focused.unwrap_or_default();
 
         // render all blocks
-        for (_, block) in self.blocks.iter().filter(|(_, block)| block.is_in_area(&screen_space)) {
+        for (_, block) in self.blocks.iter().filter(|(id, block)| !focused_blocks.contains(id) && block.is_in_area(&screen_space)) {
             block.render(renderer, plot)?;
         }
 
         // render all connections
-        for connection in self.connections.values() {
+        for connection in self.connections.values().filter(|connection| !focused_connections.contains(&connection.id())) {
             connection.render(renderer, plot)?;
         }
 
+        // focus mode: fade what has been drawn so far, then draw the focused part on top
+        if focus_active {
+            let bg_color = unsafe { COLOR_THEME.bg_color };
+            let (start, end) = (screen_space.0, screen_space.1);
+            renderer.rectangle(Vector2(start.0 as i32 - 1, start.1 as i32 - 1), Vector2((end.0 - start.0) as i32 + 2, (end.1 - start.1) as i32 + 2))
+                .set_color(&(bg_color.0, bg_color.1, bg_color.2, 0.75))
+                .fill()?;
+
+            for (_, block) in self.blocks.iter().filter(|(id, block)| focused_blocks.contains(id) && block.is_in_area(&screen_space)) {
+                block.render(renderer, plot)?;
+            }
+
+            for connection in self.connections.values().filter(|connection| focused_connections.contains(&connection.id())) {
+                connection.render(renderer, plot)?;
+            }
+        }
+
+        if renderer.connection_counts() {
+            self.render_connection_counts(renderer, &screen_space)?;
+        }
+
+        if renderer.value_labels() {
+            self.render_value_labels(renderer)?;
+        }
+
         Ok(())
     }
 }
@@ -359,6 +1289,7 @@ impl SelectionField for Plot {
                     Selectable::Waypoint(id) if let Some(waypoint) = self.get_connection_mut(id.connection_id())
                                                                                                     .and_then(|c| c.get_segment_mut(id.location())) =>
                         waypoint.set_highlighted(false),
+                    Selectable::Connection(id) if let Some(connection) = self.get_connection_mut(&id) => connection.set_highlighted(false),
                     _ => ()
                 }
             },
@@ -369,6 +1300,7 @@ impl SelectionField for Plot {
                         Selectable::Waypoint(id) if let Some(waypoint) = self.get_connection_mut(id.connection_id())
                                                                                                         .and_then(|c| c.get_segment_mut(id.location())) =>
                             waypoint.set_highlighted(false),
+                        Selectable::Connection(id) if let Some(connection) = self.get_connection_mut(id) => connection.set_highlighted(false),
                         _ => ()
                     }
                 });
@@ -427,3 +1359,357 @@ impl SelectionField for Plot {
         self.connections.iter_mut().for_each(|(_, connection)| connection.for_each_mut_segment(highlight_segment));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deleting_a_block_removes_its_wires() {
+        let gate = |name: &str, x: i32| Block::new(&builtin::BUILTINS[name].module(), Vector2(x, 0), None);
+        let (first, middle, last) = (gate("And", 0), gate("Not", 200), gate("Not", 400));
+        let (first_id, middle_id, last_id) = (first.id(), middle.id(), last.id());
+
+        let mut plot = Plot::new();
+        [first, middle, last].into_iter().for_each(|block| plot.add_block(block));
+        plot.add_connection(Connection::new_basic(first_id, 0, middle_id, 0));
+        plot.add_connection(Connection::new_basic(middle_id, 0, last_id, 0));
+
+        let deleted = plot.delete_block(middle_id);
+        assert_eq!(deleted.len(), 2);
+        assert!(plot.connections().is_empty());
+        assert!(plot.get_block(middle_id).is_none());
+        assert_eq!(plot.get_block(first_id).unwrap().outputs()[0], None);
+        assert_eq!(plot.get_block(last_id).unwrap().inputs()[0], None);
+    }
+
+    // two gates side by side, the left one selected
+    fn selected_pair() -> (Plot, BlockID, BlockID) {
+        let gate = |x: i32| Block::new(&builtin::BUILTINS["And"].module(), Vector2(x, 0), None);
+        let (left, right) = (gate(0), gate(300));
+        let (left_id, right_id) = (left.id(), right.id());
+
+        let mut plot = Plot::new();
+        plot.add_block(left);
+        plot.add_block(right);
+        plot.get_block_mut(left_id).unwrap().set_highlighted(true);
+        plot.set_selection(Selection::Single(Selectable::Block(left_id), Vector2(0, 0)));
+        (plot, left_id, right_id)
+    }
+
+    // the marquee covers only the right gate
+    const MARQUEE: (Vector2<i32>, Vector2<i32>) = (Vector2(250, -50), Vector2(450, 200));
+
+    #[test]
+    fn clicking_empty_canvas_deselects() {
+        let (mut plot, left_id, _) = selected_pair();
+        let previous = plot.selected();
+        plot.finish_marquee(previous, (MARQUEE.0, MARQUEE.0), false, true);
+        assert!(plot.selected().is_empty());
+        assert!(!plot.get_block(left_id).unwrap().highlighted());
+    }
+
+    #[test]
+    fn shift_clicking_empty_canvas_keeps_the_selection() {
+        let (mut plot, left_id, _) = selected_pair();
+        let previous = plot.selected();
+        plot.finish_marquee(previous, (MARQUEE.0, MARQUEE.0), true, true);
+        assert_eq!(plot.selected(), vec![Selectable::Block(left_id)]);
+        assert!(plot.get_block(left_id).unwrap().highlighted());
+    }
+
+    #[test]
+    fn marquee_replaces_the_selection() {
+        let (mut plot, left_id, right_id) = selected_pair();
+        let previous = plot.selected();
+        plot.finish_marquee(previous, MARQUEE, false, false);
+        assert_eq!(plot.selected(), vec![Selectable::Block(right_id)]);
+        assert!(!plot.get_block(left_id).unwrap().highlighted());
+    }
+
+    #[test]
+    fn shift_marquee_adds_to_the_selection() {
+        let (mut plot, left_id, right_id) = selected_pair();
+        let previous = plot.selected();
+        plot.finish_marquee(previous, MARQUEE, true, false);
+        assert_eq!(plot.selected(), vec![Selectable::Block(left_id), Selectable::Block(right_id)]);
+    }
+
+    fn gate(name: &str, position: Vector2<i32>) -> Block {
+        Block::new(&builtin::BUILTINS[name].module(), position, None)
+    }
+
+    #[test]
+    fn swapping_inputs_moves_the_wires_to_the_other_pins() {
+        let (first, second, sink) = (gate("And", Vector2(0, 0)), gate("Or", Vector2(0, 100)), gate("And", Vector2(200, 50)));
+        let (first_id, second_id, sink_id) = (first.id(), second.id(), sink.id());
+        let (upper, lower) = (Connection::new_basic(first_id, 0, sink_id, 0), Connection::new_basic(second_id, 0, sink_id, 1));
+        let (upper_id, lower_id) = (upper.id(), lower.id());
+
+        let mut plot = Plot::new();
+        [first, second, sink].into_iter().for_each(|block| plot.add_block(block));
+        plot.add_connection(upper);
+        plot.add_connection(lower);
+
+        plot.swap_inputs(sink_id, 0, 1);
+        assert_eq!(plot.get_block(sink_id).unwrap().inputs()[..2], [Some(lower_id), Some(upper_id)]);
+        let pins = |id: ConnectionID| plot.get_connection(&id).unwrap().destinations().iter().map(|port| (port.block_id(), port.index())).collect::<Vec<_>>();
+        assert_eq!(pins(upper_id), vec![(sink_id, 1)]);
+        assert_eq!(pins(lower_id), vec![(sink_id, 0)]);
+    }
+
+    #[test]
+    fn tracing_follows_the_inputs_that_force_a_gate() {
+        // a high Not and a low And feed an And, which is low because of the low And alone
+        let mut project = Project::default();
+        let (high, low, sink, probe) = (gate("Not", Vector2(0, 0)), gate("And", Vector2(0, 100)), gate("And", Vector2(200, 50)), gate("Not", Vector2(400, 50)));
+        let (high_id, low_id, sink_id, probe_id) = (high.id(), low.id(), sink.id(), probe.id());
+        let traced_net = Connection::new_basic(sink_id, 0, probe_id, 0);
+        let traced_id = traced_net.id();
+
+        let plot = project.main_plot_mut();
+        [high, low, sink, probe].into_iter().for_each(|block| plot.add_block(block));
+        plot.add_connection(Connection::new_basic(high_id, 0, sink_id, 0));
+        plot.add_connection(Connection::new_basic(low_id, 0, sink_id, 1));
+        plot.add_connection(traced_net);
+        Simulator::settle(&mut project, 10).unwrap();
+
+        let traced = project.main_plot().trace_source(&project, &traced_id);
+        assert_eq!(traced, vec![sink_id, low_id]);
+    }
+
+    #[test]
+    fn disabling_a_gate_changes_the_values_behind_it() {
+        // a chain of four Not gates, the wire into the last one shows the value three gates behind the first
+        let mut project = Project::default();
+        let chain = (0..4).map(|i| gate("Not", Vector2(200 * i, 0))).collect::<Vec<_>>();
+        let ids = chain.iter().map(|block| block.id()).collect::<Vec<_>>();
+        let wires = ids.windows(2).map(|pair| Connection::new_basic(pair[0], 0, pair[1], 0)).collect::<Vec<_>>();
+        let last_id = wires.last().unwrap().id();
+
+        let plot = project.main_plot_mut();
+        chain.into_iter().for_each(|block| plot.add_block(block));
+        wires.into_iter().for_each(|wire| { plot.add_connection(wire); });
+        let last_value = |project: &mut Project, disabled: bool| {
+            let plot = project.main_plot_mut();
+            plot.get_block_mut(ids[0]).unwrap().set_disabled(disabled);
+            plot.add_block_to_update(ids[0]);
+            Simulator::settle(project, 10).unwrap();
+            project.main_plot().get_connection(&last_id).unwrap().is_active()
+        };
+
+        assert!(last_value(&mut project, false));
+        assert!(!last_value(&mut project, true));
+        assert!(last_value(&mut project, false));
+    }
+
+    #[test]
+    fn selecting_instances_picks_every_gate_of_the_module() {
+        let blocks = [gate("And", Vector2(0, 0)), gate("Or", Vector2(0, 100)), gate("And", Vector2(200, 0)), gate("Not", Vector2(200, 100))];
+        let mut ands = blocks.iter().filter(|block| block.module_id() == "And").map(|block| Selectable::Block(block.id())).collect::<Vec<_>>();
+        ands.sort_by_key(|selectable| selectable.block_id());
+
+        let mut plot = Plot::new();
+        blocks.into_iter().for_each(|block| plot.add_block(block));
+        plot.select_module_instances(&HashSet::from([String::from("And")]));
+        assert_eq!(plot.selected(), ands);
+        assert!(plot.blocks().values().all(|block| block.highlighted() == (block.module_id() == "And")));
+    }
+
+    #[test]
+    fn blocks_at_the_same_position_are_reported_and_separated() {
+        let (first, second, apart) = (gate("And", Vector2(100, 100)), gate("Or", Vector2(100, 100)), gate("Not", Vector2(300, 100)));
+        let mut stack = vec![first.id(), second.id()];
+        stack.sort();
+
+        let mut plot = Plot::new();
+        [first, second, apart].into_iter().for_each(|block| plot.add_block(block));
+        assert_eq!(plot.stacked_blocks(), vec![stack.clone()]);
+
+        let moves = plot.separate_stacked_blocks();
+        assert_eq!(moves.iter().map(|(block_id, ..)| *block_id).collect::<Vec<_>>(), vec![stack[1]]);
+        for (block_id, _, to) in moves {
+            plot.get_block_mut(block_id).unwrap().set_position(to);
+        }
+        assert!(plot.stacked_blocks().is_empty());
+    }
+
+    #[test]
+    fn tab_order_reads_left_to_right_and_top_to_bottom() {
+        let blocks = [gate("And", Vector2(300, 0)), gate("And", Vector2(0, 200)), gate("And", Vector2(0, 0))];
+        let ids = blocks.iter().map(|block| block.id()).collect::<Vec<_>>();
+        let mut plot = Plot::new();
+        blocks.into_iter().for_each(|block| plot.add_block(block));
+
+        assert_eq!(plot.blocks_in_reading_order(), vec![ids[2], ids[0], ids[1]]);
+        assert_eq!(plot.next_in_reading_order(None, false), Some(ids[2]));
+        assert_eq!(plot.next_in_reading_order(Some(ids[2]), false), Some(ids[0]));
+        // cycling wraps around at both ends
+        assert_eq!(plot.next_in_reading_order(Some(ids[1]), false), Some(ids[2]));
+        assert_eq!(plot.next_in_reading_order(Some(ids[2]), true), Some(ids[1]));
+    }
+
+    #[test]
+    fn arrow_keys_move_to_the_nearest_block_in_their_direction() {
+        let (center, right, far_right, below) = (gate("And", Vector2(0, 0)), gate("And", Vector2(200, 25)), gate("And", Vector2(500, 0)), gate("And", Vector2(0, 200)));
+        let (center_id, right_id, below_id) = (center.id(), right.id(), below.id());
+        let mut plot = Plot::new();
+        [center, right, far_right, below].into_iter().for_each(|block| plot.add_block(block));
+
+        assert_eq!(plot.nearest_block_in_direction(center_id, Vector2(1, 0)), Some(right_id));
+        assert_eq!(plot.nearest_block_in_direction(center_id, Vector2(0, 1)), Some(below_id));
+        assert_eq!(plot.nearest_block_in_direction(center_id, Vector2(-1, 0)), None);
+    }
+
+    #[test]
+    fn the_net_list_shows_every_driver_with_its_sinks() {
+        // one gate fanning out to two others, a named wire between those two and one wire to a last gate
+        let (root, upper, lower, last) = (gate("Not", Vector2(0, 100)), gate("And", Vector2(200, 0)), gate("And", Vector2(200, 200)), gate("Not", Vector2(400, 200)));
+        let (root_id, upper_id, lower_id, last_id) = (root.id(), upper.id(), lower.id(), last.id());
+        let (fan_out, named, single) = (Connection::new_basic(root_id, 0, upper_id, 0), Connection::new_basic(upper_id, 0, lower_id, 1), Connection::new_basic(lower_id, 0, last_id, 0));
+        let (fan_out_id, named_id, single_id) = (fan_out.id(), named.id(), single.id());
+
+        let mut plot = Plot::new();
+        [root, upper, lower, last].into_iter().for_each(|block| plot.add_block(block));
+        plot.add_connection(fan_out);
+        plot.add_connection(Connection::new_basic(root_id, 0, lower_id, 0));
+        plot.add_connection(named);
+        plot.add_connection(single);
+        plot.get_connection_mut(&named_id).unwrap().set_name(Some(String::from("carry")));
+
+        let nets = plot.net_list().into_iter()
+            .map(|(id, driver, sinks)| (id, (driver.block_id(), driver.index()), sinks.iter().map(|sink| (sink.block_id(), sink.index())).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        assert_eq!(nets, vec![
+            (named_id, (upper_id, 0), vec![(lower_id, 1)]),
+            (fan_out_id, (root_id, 0), vec![(upper_id, 0), (lower_id, 0)]),
+            (single_id, (lower_id, 0), vec![(last_id, 0)])
+        ]);
+    }
+
+    #[test]
+    fn the_free_pins_of_two_selected_blocks_are_connected() {
+        let (source, left, right, sink) = (gate("Not", Vector2(0, 0)), gate("Not", Vector2(200, 0)), gate("Not", Vector2(400, 0)), gate("Not", Vector2(600, 0)));
+        let (source_id, left_id, right_id, sink_id) = (source.id(), left.id(), right.id(), sink.id());
+
+        let mut plot = Plot::new();
+        [source, left, right, sink].into_iter().for_each(|block| plot.add_block(block));
+        plot.add_connection(Connection::new_basic(source_id, 0, left_id, 0));
+        plot.add_connection(Connection::new_basic(right_id, 0, sink_id, 0));
+        plot.set_selection(Selection::Many(vec![Selectable::Block(left_id), Selectable::Block(right_id)]));
+
+        let connection = plot.connect_selected_pins().unwrap();
+        assert_eq!((connection.origin().block_id(), connection.origin().index()), (left_id, 0));
+        assert_eq!(connection.destinations().iter().map(|port| (port.block_id(), port.index())).collect::<Vec<_>>(), vec![(right_id, 0)]);
+
+        plot.add_connection(connection);
+        assert!(plot.selected_pins().is_err());
+        assert!(plot.check_connection((source_id, 0), (right_id, 0)).is_err());
+    }
+
+    #[test]
+    fn connecting_pins_needs_exactly_one_free_output_and_input() {
+        let (left, right) = (gate("Not", Vector2(0, 0)), gate("Not", Vector2(200, 0)));
+        let (left_id, right_id) = (left.id(), right.id());
+
+        let mut plot = Plot::new();
+        plot.add_block(left);
+        plot.add_block(right);
+        plot.set_selection(Selection::Single(Selectable::Block(left_id), Vector2(0, 0)));
+        assert!(plot.selected_pins().is_err());
+
+        // both gates have a free output and a free input
+        plot.set_selection(Selection::Many(vec![Selectable::Block(left_id), Selectable::Block(right_id)]));
+        assert!(plot.connect_selected_pins().is_err());
+        assert!(plot.check_connection((left_id, 1), (right_id, 0)).is_err());
+        assert!(plot.check_connection((left_id, 0), (right_id, 0)).is_ok());
+    }
+
+    #[test]
+    fn focus_keeps_the_selection_and_its_neighbors() {
+        let blocks = (0..5).map(|i| gate("Not", Vector2(200 * i, 0))).collect::<Vec<_>>();
+        let ids = blocks.iter().map(|block| block.id()).collect::<Vec<_>>();
+        let wires = ids.windows(2).map(|pair| Connection::new_basic(pair[0], 0, pair[1], 0)).collect::<Vec<_>>();
+        let wire_ids = wires.iter().map(|wire| wire.id()).collect::<Vec<_>>();
+
+        let mut plot = Plot::new();
+        blocks.into_iter().for_each(|block| plot.add_block(block));
+        wires.into_iter().for_each(|wire| { plot.add_connection(wire); });
+        assert!(plot.focused().is_none());
+
+        // the two connected gates in the middle of the chain of five
+        plot.set_selection(Selection::Many(vec![Selectable::Block(ids[1]), Selectable::Block(ids[2])]));
+        let (blocks, connections) = plot.focused().unwrap();
+        assert_eq!(blocks, ids[..4].iter().copied().collect::<HashSet<_>>());
+        assert_eq!(connections, wire_ids[..3].iter().copied().collect::<HashSet<_>>());
+    }
+
+    #[test]
+    fn labeled_wires_still_carry_their_values() {
+        let mut project = Project::default();
+        let chain = (0..3).map(|i| gate("Not", Vector2(200 * i, 0))).collect::<Vec<_>>();
+        let ids = chain.iter().map(|block| block.id()).collect::<Vec<_>>();
+        let wires = ids.windows(2).map(|pair| Connection::new_basic(pair[0], 0, pair[1], 0)).collect::<Vec<_>>();
+        let (first_id, second_id) = (wires[0].id(), wires[1].id());
+
+        let plot = project.main_plot_mut();
+        chain.into_iter().for_each(|block| plot.add_block(block));
+        wires.into_iter().for_each(|wire| { plot.add_connection(wire); });
+        plot.get_connection_mut(&first_id).unwrap().set_name(Some(String::from("net1")));
+        plot.set_selection(Selection::Many(vec![Selectable::Block(ids[0]), Selectable::Block(ids[1])]));
+        assert_eq!(plot.selected_connections(), vec![first_id]);
+        assert_eq!(plot.unused_net_names(2), vec![String::from("net2"), String::from("net3")]);
+
+        for id in [first_id, second_id] {
+            plot.get_connection_mut(&id).unwrap().set_labeled(true);
+        }
+        Simulator::settle(&mut project, 10).unwrap();
+        assert!(project.main_plot().get_connection(&first_id).unwrap().is_active());
+        assert!(!project.main_plot().get_connection(&second_id).unwrap().is_active());
+    }
+
+    #[test]
+    fn wire_stats_add_up_the_drawn_lengths() {
+        // every Not gate is as wide as this one, and its pins are the same distance below its top edge
+        let width = gate("Not", Vector2(0, 0)).size().0;
+        let (a, b, c) = (gate("Not", Vector2(0, 0)), gate("Not", Vector2(width + 300, 0)), gate("Not", Vector2(width + 300, 400)));
+        let d = gate("Not", Vector2(2 * width + 400, 0));
+        let (a_id, b_id, c_id, d_id) = (a.id(), b.id(), c.id(), d.id());
+        let fan_out = Connection::new_basic(a_id, 0, b_id, 0);
+        let fan_out_id = fan_out.id();
+
+        let mut plot = Plot::new();
+        [a, b, c, d].into_iter().for_each(|block| plot.add_block(block));
+        plot.add_connection(fan_out);
+        plot.add_connection(Connection::new_basic(a_id, 0, c_id, 0));
+        plot.add_connection(Connection::new_basic(b_id, 0, d_id, 0));
+
+        // 300 across to b, a 300 by 400 diagonal to c and 100 from b to d
+        let (count, length, busiest) = plot.wire_stats();
+        assert_eq!(count, 2);
+        assert!((length - 900.).abs() < 1e-9, "length was {length}");
+        assert_eq!(busiest, Some((fan_out_id, 2)));
+    }
+
+    #[test]
+    fn a_near_miss_snaps_to_the_closest_free_input() {
+        let gate = |name: &str, x: i32| Block::new(&builtin::BUILTINS[name].module(), Vector2(x, 0), None);
+        let (source, target) = (gate("Not", -300), gate("And", 0));
+        let (source_id, target_id) = (source.id(), target.id());
+
+        let mut plot = Plot::new();
+        plot.add_block(source);
+        plot.add_block(target);
+        let near = |plot: &Plot, position| plot.get_input_near(position, Plot::PIN_SNAP_DISTANCE)
+            .map(|(port, pin)| (port.block_id(), port.index(), pin));
+
+        // the first input sits at (0, 50), the second one at (0, 75)
+        assert_eq!(near(&plot, Vector2(4, 53)), Some((target_id, 0, Vector2(0, 50))));
+        assert_eq!(near(&plot, Vector2(100, 53)), None);
+
+        // an occupied pin is passed over for the next free one in reach
+        plot.add_connection(Connection::new_basic(source_id, 0, target_id, 0));
+        assert_eq!(near(&plot, Vector2(3, 60)), Some((target_id, 1, Vector2(0, 75))));
+        assert_eq!(near(&plot, Vector2(4, 45)), None);
+    }
+}
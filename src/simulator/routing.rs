@@ -0,0 +1,182 @@
+use std::{collections::{BinaryHeap, HashMap}, cmp::Reverse};
+use crate::{application::editor::GRID_SIZE, renderer::vector::Vector2, id::Id};
+use super::*;
+
+// gives up on a wire after visiting this many grid points, leaving it as it was
+const MAX_VISITED: usize = 20_000;
+// how far a route may stray outside the box spanned by its two ends, in grid steps
+const MAX_DETOUR: i32 = 12;
+// extra cost of a bend in grid steps, to prefer long straight runs
+const BEND_COST: u32 = 3;
+// space kept free around every block
+const MARGIN: i32 = GRID_SIZE / 2;
+
+const DIRECTIONS: [Vector2<i32>; 4] = [Vector2(1, 0), Vector2(-1, 0), Vector2(0, 1), Vector2(0, -1)];
+
+// finds orthogonal wire paths that go around blocks instead of through them
+pub struct Router {
+    obstacles: Vec<(Vector2<i32>, Vector2<i32>)>
+}
+
+impl Router {
+    pub fn new(plot: &Plot) -> Self {
+        Self {
+            obstacles: plot.blocks().values()
+                .map(|block| {
+                    let (min, max) = block.bounds();
+                    (min - Vector2(MARGIN, MARGIN), max + Vector2(MARGIN, MARGIN))
+                })
+                .collect()
+        }
+    }
+
+    fn blocked(&self, point: Vector2<i32>) -> bool {
+        self.obstacles.iter().any(|(min, max)| point.0 > min.0 && point.0 < max.0 && point.1 > min.1 && point.1 < max.1)
+    }
+
+    // new segments for every destination of the connection, `None` if any of them cannot be routed
+    pub fn route_connection(&self, plot: &Plot, connection: &Connection) -> Option<HashMap<Id, Segment>> {
+        let origin = connection.origin();
+        let start = plot.get_block(origin.block_id())?.get_connector_pos(origin.into());
+
+        connection.destinations().into_iter()
+            .map(|destination| {
+                let end = plot.get_block(destination.block_id())?.get_connector_pos(destination.into());
+                let points = self.route(start, end)?;
+                let segment = points.into_iter().rev().fold(
+                    Segment::Block(destination.block_id(), destination.index()),
+                    |next, point| Segment::Waypoint(HashMap::from([(Id::new(), next)]), point, false)
+                );
+                Some((Id::new(), segment))
+            })
+            .collect()
+    }
+
+    // the bend points of a path leaving the output pin at `start` to the right and entering the
+    // input pin at `end` from the left
+    fn route(&self, start: Vector2<i32>, end: Vector2<i32>) -> Option<Vec<Vector2<i32>>> {
+        // search a lattice anchored just right of the output pin
+        let exit = start + Vector2(GRID_SIZE, 0);
+        let to_point = |node: Vector2<i32>| exit + Vector2(node.0 * GRID_SIZE, node.1 * GRID_SIZE);
+        let target = Vector2(
+            (end.0 - GRID_SIZE - exit.0).div_euclid(GRID_SIZE),
+            ((end.1 - exit.1) as f64 / GRID_SIZE as f64).round() as i32
+        );
+
+        let min = Vector2(0.min(target.0) - MAX_DETOUR, 0.min(target.1) - MAX_DETOUR);
+        let max = Vector2(0.max(target.0) + MAX_DETOUR, 0.max(target.1) + MAX_DETOUR);
+        let in_bounds = |node: Vector2<i32>| node.0 >= min.0 && node.0 <= max.0 && node.1 >= min.1 && node.1 <= max.1;
+        let heuristic = |node: Vector2<i32>| (node.0.abs_diff(target.0) + node.1.abs_diff(target.1)) as u32;
+
+        if self.blocked(exit) || self.blocked(to_point(target)) {
+            return None;
+        }
+
+        // a* over (node, direction) so that bends can be priced in
+        let mut costs = HashMap::from([((0, 0, 0usize), 0u32)]);
+        let mut previous: HashMap<(i32, i32, usize), (i32, i32, usize)> = HashMap::new();
+        let mut open = BinaryHeap::from([Reverse((heuristic(Vector2(0, 0)), 0u32, (0, 0), 0usize))]);
+        let mut visited = 0;
+
+        let found = loop {
+            let Reverse((_, cost, (x, y), direction)) = open.pop()?;
+            let node = Vector2(x, y);
+            if node == target {
+                break (x, y, direction);
+            }
+            if costs.get(&(x, y, direction)).map_or(false, |best| *best < cost) {
+                continue;
+            }
+
+            visited += 1;
+            if visited > MAX_VISITED {
+                return None;
+            }
+
+            for (next_direction, step) in DIRECTIONS.iter().enumerate() {
+                let next = node + *step;
+                if !in_bounds(next) || self.blocked(to_point(next)) {
+                    continue;
+                }
+
+                let next_cost = cost + 1 + if next_direction != direction { BEND_COST } else { 0 };
+                let key = (next.0, next.1, next_direction);
+                if costs.get(&key).map_or(true, |best| next_cost < *best) {
+                    costs.insert(key, next_cost);
+                    previous.insert(key, (x, y, direction));
+                    open.push(Reverse((next_cost + heuristic(next), next_cost, (next.0, next.1), next_direction)));
+                }
+            }
+        };
+
+        let mut nodes = vec![Vector2(found.0, found.1)];
+        let mut current = found;
+        while let Some(before) = previous.get(&current) {
+            nodes.push(Vector2(before.0, before.1));
+            current = *before;
+        }
+        nodes.reverse();
+
+        let last = to_point(target);
+        let mut points = vec![start];
+        points.extend(nodes.into_iter().map(to_point));
+        points.extend([Vector2(last.0, end.1), end]);
+        points.dedup();
+
+        // only the corners are needed as waypoints
+        let corners = points.windows(3)
+            .filter(|window| !(window[0].0 == window[1].0 && window[1].0 == window[2].0) && !(window[0].1 == window[1].1 && window[1].1 == window[2].1))
+            .map(|window| window[1])
+            .collect();
+        Some(corners)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gate(position: Vector2<i32>) -> Block {
+        Block::new(&builtin::BUILTINS["And"].module(), position, None)
+    }
+
+    // the ends of a wire from the output of `from` to the first input of `to`
+    fn pins(from: &Block, to: &Block) -> (Vector2<i32>, Vector2<i32>) {
+        (from.get_connector_pos(Connector::Output(0)), to.get_connector_pos(Connector::Input(0)))
+    }
+
+    // whether the horizontal or vertical line from `a` to `b` runs through the inside of the area
+    fn crosses(a: Vector2<i32>, b: Vector2<i32>, (min, max): (Vector2<i32>, Vector2<i32>)) -> bool {
+        let (low, high) = (Vector2(a.0.min(b.0), a.1.min(b.1)), Vector2(a.0.max(b.0), a.1.max(b.1)));
+        low.0 < max.0 && high.0 > min.0 && low.1 < max.1 && high.1 > min.1
+    }
+
+    #[test]
+    fn a_free_path_is_a_straight_wire() {
+        let (from, to) = (gate(Vector2(0, 0)), gate(Vector2(400, 0)));
+        let mut plot = Plot::new();
+        plot.add_block(from.clone());
+        plot.add_block(to.clone());
+
+        let (start, end) = pins(&from, &to);
+        assert_eq!(Router::new(&plot).route(start, end), Some(vec![]));
+    }
+
+    #[test]
+    fn wires_go_around_blocks_in_their_way() {
+        let (from, obstacle, to) = (gate(Vector2(0, 0)), gate(Vector2(300, 0)), gate(Vector2(600, 0)));
+        let bounds = obstacle.bounds();
+        let mut plot = Plot::new();
+        [from.clone(), obstacle, to.clone()].into_iter().for_each(|block| plot.add_block(block));
+
+        let (start, end) = pins(&from, &to);
+        let corners = Router::new(&plot).route(start, end).unwrap();
+        assert!(!corners.is_empty());
+
+        let path = std::iter::once(start).chain(corners).chain(std::iter::once(end)).collect::<Vec<_>>();
+        for line in path.windows(2) {
+            assert!(line[0].0 == line[1].0 || line[0].1 == line[1].1, "{line:?} is not orthogonal");
+            assert!(!crosses(line[0], line[1], bounds), "{line:?} runs through the block in the way");
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::project::Project;
+use super::*;
+
+// the simulation state of every plot at one moment, restorable without touching the circuits
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    name: String,
+    ticks: u64,
+    plots: HashMap<PlotDescriptor, PlotState>
+}
+
+impl Snapshot {
+    pub fn capture(name: String, project: &Project) -> Self {
+        let plots = project.modules().iter()
+            .filter_map(|(name, module)| Some((PlotDescriptor::Module(name.clone()), PlotState::from(module.plot()?))))
+            .chain(std::iter::once((PlotDescriptor::Main(), PlotState::from(project.main_plot()))))
+            .collect();
+
+        Self {
+            name,
+            ticks: project.ticks(),
+            plots
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn plots_mut(&mut self) -> &mut HashMap<PlotDescriptor, PlotState> {
+        &mut self.plots
+    }
+
+    // blocks and connections added since the capture keep their current state
+    pub fn restore(&self, project: &mut Project) {
+        for (descriptor, state) in self.plots.iter() {
+            if let Some(plot) = project.plot_by_descriptor_mut(descriptor) {
+                state.apply(plot);
+                plot.update_all_blocks();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::vector::Vector2;
+
+    #[test]
+    fn restoring_a_snapshot_brings_back_every_stored_value() {
+        let mut project = Project::default();
+        let flip_flop = project.module(&String::from("T Flip-Flop with Set/Reset")).unwrap().clone();
+        let (set, cleared) = (Block::new(&&flip_flop, Vector2(0, 0), None), Block::new(&&flip_flop, Vector2(0, 200), None));
+        let (set_id, cleared_id) = (set.id(), cleared.id());
+        project.main_plot_mut().add_block(set);
+        project.main_plot_mut().add_block(cleared);
+        project.main_plot_mut().get_block_mut(set_id).unwrap().set_bytes(1);
+        project.main_plot_mut().get_block_mut(cleared_id).unwrap().set_bytes(0);
+        project.capture_snapshot(String::from("before"));
+
+        // both flip-flops end up in the other state
+        project.main_plot_mut().get_block_mut(set_id).unwrap().set_bytes(0);
+        project.main_plot_mut().get_block_mut(cleared_id).unwrap().set_bytes(1);
+        Simulator::settle(&mut project, 5).unwrap();
+
+        project.restore_snapshot(0).unwrap();
+        assert_eq!(project.main_plot().get_block(set_id).unwrap().bytes(), 1);
+        assert_eq!(project.main_plot().get_block(cleared_id).unwrap().bytes(), 0);
+        assert_eq!(project.main_plot().blocks().len(), 2);
+        assert!(project.restore_snapshot(1).is_err());
+    }
+}
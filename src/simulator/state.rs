@@ -24,6 +24,19 @@ impl From<&Plot> for PlotState {
 }
 
 impl PlotState {
+    pub fn blocks_mut(&mut self) -> &mut HashMap<BlockID, State> {
+        &mut self.blocks
+    }
+
+    pub fn remap_ids(&mut self, blocks: &HashMap<BlockID, BlockID>, connections: &HashMap<ConnectionID, ConnectionID>) {
+        self.blocks = std::mem::take(&mut self.blocks).into_iter()
+            .map(|(id, state)| (blocks.get(&id).copied().unwrap_or(id), state))
+            .collect();
+        self.connections = std::mem::take(&mut self.connections).into_iter()
+            .map(|(id, active)| (connections.get(&id).copied().unwrap_or(id), active))
+            .collect();
+    }
+
     pub fn apply(&self, plot: &mut Plot) {
         plot.blocks_mut().iter_mut().for_each(|(id, block)| if let Some(state) = self.blocks.get(id) {
             block.set_state(state.clone())
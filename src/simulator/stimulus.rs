@@ -0,0 +1,159 @@
+use serde::{Serialize, Deserialize, Deserializer};
+use super::*;
+
+// a sequence of input values played into the switches and buttons of the main circuit
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stimulus {
+    inputs: Vec<BlockID>,
+    steps: Vec<Vec<bool>>,
+    #[serde(deserialize_with = "deserialize_period")]
+    period: u64,
+    looping: bool,
+    saved: bool,
+    #[serde(skip)]
+    playing: bool,
+    #[serde(skip)]
+    start: u64
+}
+
+impl Stimulus {
+    pub const DEFAULT_PERIOD: u64 = 10;
+
+    pub fn new(inputs: Vec<BlockID>, steps: Vec<Vec<bool>>, period: u64, looping: bool, saved: bool) -> Self {
+        Self {
+            inputs,
+            steps,
+            period: period.max(1),
+            looping,
+            saved,
+            playing: false,
+            start: 0
+        }
+    }
+
+    // the blocks a stimulus can drive, ordered top to bottom
+    pub fn input_blocks(plot: &Plot) -> Vec<&Block> {
+        let mut blocks = plot.blocks().values()
+            .filter(|block| block.clickable())
+            .collect::<Vec<_>>();
+        blocks.sort_by_key(|block| (block.position().1, block.position().0, block.id()));
+        blocks
+    }
+
+    // reads one step per line as a row of `0`s and `1`s, one per input; empty lines and lines starting with `#` are skipped
+    pub fn parse_steps(text: &str, num_inputs: usize) -> Result<Vec<Vec<bool>>, String> {
+        text.lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(line_number, line)| {
+                let step = line.chars()
+                    .filter(|c| !c.is_whitespace())
+                    .map(|c| match c {
+                        '0' => Ok(false),
+                        '1' => Ok(true),
+                        c => Err(format!("Line {line_number}: unexpected character '{c}', expected '0' or '1'."))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if step.len() != num_inputs {
+                    return Err(format!("Line {line_number}: expected {num_inputs} values, got {}.", step.len()));
+                }
+                Ok(step)
+            })
+            .collect()
+    }
+
+    pub fn steps_to_text(&self) -> String {
+        self.steps.iter()
+            .map(|step| step.iter().map(|value| if *value { '1' } else { '0' }).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn inputs(&self) -> &Vec<BlockID> {
+        &self.inputs
+    }
+
+    pub fn remap_inputs(&mut self, blocks: &HashMap<BlockID, BlockID>) {
+        self.inputs.iter_mut().for_each(|block_id| if let Some(new_id) = blocks.get(block_id) {
+            *block_id = *new_id;
+        });
+    }
+
+    pub fn period(&self) -> u64 {
+        self.period
+    }
+
+    pub fn looping(&self) -> bool {
+        self.looping
+    }
+
+    pub fn saved(&self) -> bool {
+        self.saved
+    }
+
+    pub fn playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn play(&mut self, tick: u64) {
+        self.playing = true;
+        self.start = tick;
+    }
+
+    pub fn stop(&mut self) {
+        self.playing = false;
+    }
+
+    // applies the step scheduled for `tick`, if any, stopping once the last step has been played
+    pub fn advance(&mut self, tick: u64, plot: &mut Plot) {
+        if !self.playing || self.steps.is_empty() {
+            return;
+        }
+
+        let elapsed = tick.saturating_sub(self.start);
+        if elapsed % self.period != 0 {
+            return;
+        }
+
+        let mut index = (elapsed / self.period) as usize;
+        if index >= self.steps.len() {
+            if !self.looping {
+                self.playing = false;
+                return;
+            }
+            index %= self.steps.len();
+        }
+
+        for (block_id, value) in self.inputs.iter().zip(&self.steps[index]) {
+            if let Some(block) = plot.get_block_mut(*block_id) && block.is_active() != *value {
+                block.set_active(*value);
+                plot.add_block_to_update(*block_id);
+            }
+        }
+    }
+}
+
+// a hand-edited file may hold a period of 0, which would leave no tick to play a step on
+fn deserialize_period<'de, D>(deserializer: D) -> Result<u64, D::Error>
+    where D: Deserializer<'de>
+{
+    u64::deserialize(deserializer).map(|period| period.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_period_loads_as_one_tick() {
+        let json = r#"{"inputs":[],"steps":[[],[]],"period":0,"looping":true,"saved":true}"#;
+        let mut stimulus: Stimulus = serde_json::from_str(json).unwrap();
+        assert_eq!(stimulus.period(), 1);
+
+        let mut plot = Plot::new();
+        stimulus.play(0);
+        (0..5).for_each(|tick| stimulus.advance(tick, &mut plot));
+        assert!(stimulus.playing());
+    }
+}
@@ -0,0 +1,246 @@
+use std::{collections::HashSet, fmt};
+use crate::project::Project;
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    severity: Severity,
+    message: String,
+    location: Option<(PlotDescriptor, BlockID)>
+}
+
+impl Diagnostic {
+    pub fn warning(message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message,
+            location: None
+        }
+    }
+
+    pub fn error(message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            message,
+            location: None
+        }
+    }
+
+    pub fn at(mut self, plot: PlotDescriptor, block_id: BlockID) -> Self {
+        self.location = Some((plot, block_id));
+        self
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn message(&self) -> &String {
+        &self.message
+    }
+
+    pub fn location(&self) -> Option<&(PlotDescriptor, BlockID)> {
+        self.location.as_ref()
+    }
+
+    pub fn is_fatal(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Warning => write!(f, "Warning: {}", self.message),
+            Severity::Error => write!(f, "Error: {}", self.message)
+        }
+    }
+}
+
+// quick checks for problems that would make the simulation misbehave
+pub fn validate(project: &Project) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let mut names = project.modules().iter()
+        .filter(|(_, module)| !module.builtin())
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    names.sort();
+
+    for name in names {
+        let module = &project.modules()[name];
+        let descriptor = PlotDescriptor::Module(name.clone());
+        match module.plot() {
+            Some(plot) => validate_plot(project, plot, &descriptor, &format!("module \"{name}\""), &mut diagnostics),
+            None => diagnostics.push(Diagnostic::error(format!("Module \"{name}\" has no circuit.")))
+        }
+
+        if !module.has_io_blocks() {
+            diagnostics.push(Diagnostic::error(format!("Module \"{name}\" is missing its input or output block.")));
+        }
+
+        if !module.source_or_sink() && (module.get_num_inputs() == 0 || module.get_num_outputs() == 0) {
+            let missing = match (module.get_num_inputs(), module.get_num_outputs()) {
+                (0, 0) => "no inputs and no outputs",
+                (0, _) => "no inputs",
+                _ => "no outputs"
+            };
+            let diagnostic = Diagnostic::warning(format!(
+                "Module \"{name}\" has {missing}; if that is intended, mark it as a source or sink in its context menu."
+            ));
+            let io_block = module.io_blocks().map(|(input, output)| if module.get_num_inputs() == 0 { input } else { output });
+            diagnostics.push(match io_block {
+                Some(block_id) if module.has_io_blocks() => diagnostic.at(descriptor.clone(), block_id),
+                _ => diagnostic
+            });
+        }
+
+        if let Some(block_id) = recursive_block(project, name) {
+            diagnostics.push(Diagnostic::error(format!("Recursion detected; module \"{name}\" contains itself.")).at(descriptor, block_id));
+        }
+    }
+
+    validate_plot(project, project.main_plot(), &PlotDescriptor::Main(), "the main circuit", &mut diagnostics);
+    diagnostics
+}
+
+fn validate_plot(project: &Project, plot: &Plot, descriptor: &PlotDescriptor, context: &str, diagnostics: &mut Vec<Diagnostic>) {
+    for block in plot.blocks().values() {
+        if project.module(block.module_id()).is_none() {
+            diagnostics.push(Diagnostic::warning(format!("A block in {context} uses the unknown module \"{}\".", block.module_id()))
+                .at(descriptor.clone(), block.id()));
+        }
+
+        if let Some(module) = project.module(block.module_id()) && !block.unique()
+            && (block.inputs().len() != module.get_num_inputs() as usize || block.outputs().len() != module.get_num_outputs() as usize) {
+            diagnostics.push(Diagnostic::error(format!(
+                "A \"{}\" block in {context} has {} inputs and {} outputs, but its module now has {} and {}; use \"Fit Blocks to Their Modules\" to update it.",
+                block.name(), block.inputs().len(), block.outputs().len(), module.get_num_inputs(), module.get_num_outputs()
+            )).at(descriptor.clone(), block.id()));
+        }
+
+        if block.connected_to().iter().any(|id| plot.get_connection(id).is_none()) {
+            diagnostics.push(Diagnostic::warning(format!("A \"{}\" block in {context} refers to a missing connection.", block.name()))
+                .at(descriptor.clone(), block.id()));
+        }
+    }
+
+    for stack in plot.stacked_blocks() {
+        let position = plot.get_block(stack[0]).map(|block| block.position()).unwrap_or_default();
+        diagnostics.push(Diagnostic::warning(format!(
+            "{} blocks in {context} are stacked on top of each other at ({}, {}); use \"Separate Stacked Blocks\" to spread them out.",
+            stack.len(), position.0, position.1
+        )).at(descriptor.clone(), stack[0]));
+    }
+
+    for (port, first, second) in plot.multiple_drivers() {
+        let name = |id: BlockID| plot.get_block(id).map_or_else(|| String::from("missing"), |block| block.name().clone());
+        diagnostics.push(Diagnostic::error(format!(
+            "Input {} of a \"{}\" block in {context} has multiple drivers: a \"{}\" and a \"{}\" block.",
+            port.index() + 1, name(port.block_id()), name(first.0.0), name(second.0.0)
+        )).at(descriptor.clone(), port.block_id()));
+
+        // one entry per driver, so each of them can be jumped to
+        for ((block_id, output), _) in [first, second] {
+            let position = plot.get_block(block_id).map(|block| block.position()).unwrap_or_default();
+            diagnostics.push(Diagnostic::error(format!(
+                "Output {} of the \"{}\" block at ({}, {}) in {context} drives input {} of a \"{}\" block together with another output.",
+                output + 1, name(block_id), position.0, position.1, port.index() + 1, name(port.block_id())
+            )).at(descriptor.clone(), block_id));
+        }
+    }
+
+    for (port, _, _) in plot.duplicate_connections() {
+        let name = plot.get_block(port.block_id()).map_or_else(|| String::from("missing"), |block| block.name().clone());
+        diagnostics.push(Diagnostic::warning(format!(
+            "Input {} of a \"{name}\" block in {context} is wired to the same output more than once; use \"Remove Duplicate Connections\" to merge them.",
+            port.index() + 1
+        )).at(descriptor.clone(), port.block_id()));
+    }
+
+    for connection in plot.connections().values() {
+        let mut ports = std::iter::once(connection.origin()).chain(connection.destinations());
+        if ports.any(|port| plot.get_block(port.block_id()).is_none()) {
+            let diagnostic = Diagnostic::error(format!("A connection in {context} is attached to a missing block."));
+            let origin = connection.origin().block_id();
+            diagnostics.push(match plot.get_block(origin) {
+                Some(_) => diagnostic.at(descriptor.clone(), origin),
+                None => diagnostic
+            });
+        }
+    }
+}
+
+// finds a block inside the module `name` that places the module inside itself
+fn recursive_block(project: &Project, name: &String) -> Option<BlockID> {
+    let mut visited = HashSet::new();
+    project.plot(name)?.blocks().values()
+        .find(|block| block.module_id() == name || contains_module(project, block.module_id(), name, &mut visited))
+        .map(|block| block.id())
+}
+
+// checks if `target` is placed inside the module `name`, directly or through other modules
+pub(crate) fn contains_module(project: &Project, name: &String, target: &String, visited: &mut HashSet<String>) -> bool {
+    if !visited.insert(name.clone()) {
+        return false;
+    }
+
+    project.plot(name).map_or(false, |plot| plot.blocks().values().any(|block|
+        block.module_id() == target || contains_module(project, block.module_id(), target, visited)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::vector::Vector2;
+
+    fn interface_warnings(module: Module) -> Vec<Diagnostic> {
+        let mut project = Project::default();
+        project.add_module(module);
+        validate(&project).into_iter()
+            .filter(|diagnostic| diagnostic.message().contains("source or sink"))
+            .collect()
+    }
+
+    #[test]
+    fn a_module_without_outputs_is_flagged() {
+        let warnings = interface_warnings(Module::new(String::from("Display"), 4, 0));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message().contains("\"Display\" has no outputs"));
+        assert!(!warnings[0].is_fatal());
+        assert!(warnings[0].location().is_some());
+    }
+
+    #[test]
+    fn sources_and_sinks_are_not_flagged() {
+        let mut clock = Module::new(String::from("Clock Generator"), 0, 1);
+        clock.set_source_or_sink(true);
+        assert!(interface_warnings(clock).is_empty());
+        assert!(interface_warnings(Module::new(String::from("Buffer"), 1, 1)).is_empty());
+    }
+
+    #[test]
+    fn a_module_containing_itself_is_fatal() {
+        let mut module = Module::new(String::from("Loop"), 1, 1);
+        let instance = Block::new(&&module.clone(), Vector2(200, 100), None);
+        let instance_id = instance.id();
+        module.plot_mut().unwrap().add_block(instance);
+
+        let mut project = Project::default();
+        project.add_module(module);
+        let recursions = validate(&project).into_iter()
+            .filter(|diagnostic| diagnostic.message().starts_with("Recursion detected"))
+            .collect::<Vec<_>>();
+        assert_eq!(recursions.len(), 1);
+        assert!(recursions[0].message().contains("\"Loop\" contains itself"));
+        assert!(recursions[0].is_fatal());
+        assert_eq!(recursions[0].location().map(|(_, block_id)| *block_id), Some(instance_id));
+    }
+}
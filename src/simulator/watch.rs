@@ -0,0 +1,62 @@
+use crate::project::Project;
+use super::*;
+
+// a net whose live value is listed in the watch panel
+#[derive(Debug, Clone)]
+pub struct Watch {
+    plot: PlotDescriptor,
+    connection: ConnectionID,
+    value: Option<bool>,
+    last_changed: u64
+}
+
+impl Watch {
+    pub fn new(plot: PlotDescriptor, connection: ConnectionID) -> Self {
+        Self {
+            plot,
+            connection,
+            value: None,
+            last_changed: 0
+        }
+    }
+
+    pub fn plot(&self) -> &PlotDescriptor {
+        &self.plot
+    }
+
+    pub fn connection(&self) -> ConnectionID {
+        self.connection
+    }
+
+    pub fn set_connection(&mut self, connection: ConnectionID) {
+        self.connection = connection;
+    }
+
+    pub fn value(&self) -> Option<bool> {
+        self.value
+    }
+
+    pub fn last_changed(&self) -> u64 {
+        self.last_changed
+    }
+
+    pub fn watches(&self, plot: &PlotDescriptor, connection: &ConnectionID) -> bool {
+        &self.plot == plot && &self.connection == connection
+    }
+
+    // reads the current value of the net, noting the tick if it changed
+    pub fn update(&mut self, project: &Project, tick: u64) {
+        let value = project.plot_by_descriptor(&self.plot)
+            .and_then(|plot| plot.get_connection(&self.connection))
+            .map(|connection| connection.is_active());
+
+        if value != self.value {
+            self.value = value;
+            self.last_changed = tick;
+        }
+    }
+
+    pub fn describe(&self, project: &Project) -> String {
+        project.describe_net(&self.plot, &self.connection)
+    }
+}
@@ -1,9 +1,11 @@
-use crate::{application::{Application, editor::EditorMode}, simulator::PlotProvider};
-use super::circuit_view::CircuitView;
+use crate::{application::{Application, action::ActionStack, editor::EditorMode, selection::*}, simulator::{Simulator, PlotProvider, PlotDescriptor, BlockID, ConnectionID, breakpoint::Breakpoint, watch::Watch}, renderer::vector::Vector2, id::Id};
+use super::{circuit_view::CircuitView, dialogs};
 use gtk::{prelude::*, subclass::prelude::*, gio, glib};
 
 use std::{cell::{RefCell, Cell}, collections::HashMap};
 
+pub type ErrorLocation = (PlotProvider, BlockID);
+
 glib::wrapper! {
     pub struct CircuitPanel(ObjectSubclass<CircuitPanelTemplate>)
         @extends gtk::Box, gtk::Widget,
@@ -21,8 +23,9 @@ impl CircuitPanel {
 
     pub fn reset_ui(&self) {
         self.imp().close_tabs();
-        self.undo_button().set_sensitive(false);
-        self.redo_button().set_sensitive(false);
+        self.imp().clear_watches();
+        self.imp().clear_nets();
+        ActionStack::reset_buttons(self.undo_button(), self.redo_button());
     }
 
     #[template_callback]
@@ -44,7 +47,15 @@ impl CircuitPanel {
     }
 
     pub fn open_tab(&self, plot_provider: PlotProvider) {
-        if let PlotProvider::Module(_, module_name) = &plot_provider {
+        if plot_provider.is_main() {
+            let view = &self.imp().view;
+            if let Some(page) = (0..view.n_pages()).map(|i| view.nth_page(i)).find(|page| 
+                matches!(page.child().downcast::<CircuitView>(), Ok(circuit_view) if circuit_view.plot_provider().is_main())
+            ) {
+                view.set_selected_page(&page);
+            }
+        }
+        else if let PlotProvider::Module(_, module_name) = &plot_provider {
             let mut i = 0;
             let view = &self.imp().view;
             while i < view.n_pages() {
@@ -57,7 +68,8 @@ impl CircuitPanel {
             }
             
             // page not found, create new
-            self.imp().new_tab(module_name, plot_provider.clone());
+            let complexity = plot_provider.with(|plot| plot.complexity()).unwrap_or_default();
+            self.imp().new_tab(module_name, plot_provider.clone(), complexity);
         }
     }
 
@@ -65,8 +77,14 @@ impl CircuitPanel {
         self.imp().application.replace(app);
     }
 
-    pub fn new_tab(&self, title: &str, plot_provider: PlotProvider) {
-        self.imp().new_tab(title, plot_provider)
+    // `complexity` is passed in since the project is often locked while tabs get opened
+    pub fn new_tab(&self, title: &str, plot_provider: PlotProvider, complexity: usize) {
+        self.imp().new_tab(title, plot_provider, complexity)
+    }
+
+    // opens a tab without checking how large the plot is
+    pub fn open_view(&self, title: &str, plot_provider: PlotProvider, lite: bool) {
+        self.imp().open_view(title, plot_provider, lite)
     }
 
     pub fn set_title(&self, title: &str) {
@@ -77,17 +95,251 @@ impl CircuitPanel {
         self.imp().remove_tab(module_name)
     }
 
+    // names of all open module tabs in the order they appear
+    pub fn tab_order(&self) -> Vec<String> {
+        let view = &self.imp().view;
+        (0..view.n_pages())
+            .filter_map(|i| view.nth_page(i).child().downcast::<CircuitView>().ok())
+            .filter_map(|circuit_view| circuit_view.plot_provider().is_module().cloned())
+            .collect()
+    }
+
+    pub fn finish_close_page(&self, page: &adw::TabPage, confirm: bool) {
+        if confirm {
+            if let Ok(circuit_view) = page.child().downcast::<CircuitView>() {
+                circuit_view.store_view();
+            }
+            self.imp().pages.borrow_mut().retain(|_, p| p != page);
+        }
+        self.imp().view.close_page_finish(page, confirm);
+    }
+
+    pub fn store_views(&self) {
+        let view = &self.imp().view;
+        (0..view.n_pages())
+            .filter_map(|i| view.nth_page(i).child().downcast::<CircuitView>().ok())
+            .for_each(|circuit_view| circuit_view.store_view());
+    }
+
     pub fn push_error(&self, error: String) {
+        self.push_error_at(error, None)
+    }
+
+    // a short-lived message for results that aren't errors; toast titles are markup
+    pub fn push_notice(&self, notice: String) {
+        self.imp().toast_overlay.add_toast(&adw::Toast::new(&glib::markup_escape_text(&notice)));
+    }
+
+    pub fn push_error_at(&self, error: String, location: Option<ErrorLocation>) {
         let template = self.imp();
         if template.info_bar.is_visible() {
             let mut errors = template.errors.borrow_mut();
-            if !errors.contains(&error) {
-                errors.push(error);
+            if !errors.iter().any(|(message, _)| message == &error) {
+                errors.push((error, location));
             }
         }
         else {
-            template.info_label.set_label(&error);
-            template.info_bar.show();
+            template.show_error(error, location);
+        }
+    }
+
+    pub fn show_watches(&self) {
+        self.imp().toggle_watch_button.set_active(true);
+        self.update_watches();
+    }
+
+    // refreshes the watch list with the latest values from the simulation
+    pub fn update_watches(&self) {
+        let template = self.imp();
+        let project = template.application.borrow().imp().project().clone();
+        let watches = {
+            let project = project.lock();
+            project.watches().iter()
+                .map(|watch| (watch.clone(), watch.describe(&project)))
+                .collect::<Vec<_>>()
+        };
+
+        if template.watch_rows.borrow().len() != watches.len() {
+            template.clear_watches();
+            let rows = watches.iter().map(|(watch, _)| self.add_watch_row(watch)).collect();
+            template.watch_rows.replace(rows);
+        }
+
+        let rows = template.watch_rows.borrow();
+
+        for ((watch, description), (name_label, value_label, tick_label)) in watches.iter().zip(rows.iter()) {
+            name_label.set_label(description);
+            value_label.set_label(match watch.value() {
+                Some(true) => "1",
+                Some(false) => "0",
+                None => "-"
+            });
+            tick_label.set_label(&format!("changed at tick {}", watch.last_changed()));
+        }
+    }
+
+    fn add_watch_row(&self, watch: &Watch) -> (gtk::Label, gtk::Label, gtk::Label) {
+        let name_label = gtk::Label::builder().hexpand(true).halign(gtk::Align::Start).build();
+        let value_label = gtk::Label::new(None);
+        value_label.add_css_class("monospace");
+        let tick_label = gtk::Label::new(None);
+        tick_label.add_css_class("dim-label");
+
+        let remove_button = gtk::Button::from_icon_name("small-x-symbolic");
+        remove_button.set_tooltip_text(Some("Stop watching"));
+        remove_button.add_css_class("flat");
+        let (plot, connection_id) = (watch.plot().clone(), watch.connection());
+        remove_button.connect_clicked(glib::clone!(@weak self as panel => move |_| {
+            let project = panel.imp().application.borrow().imp().project().clone();
+            project.lock().remove_watch(&plot, &connection_id);
+            panel.update_watches();
+        }));
+
+        let row = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(12)
+            .margin_start(12)
+            .margin_end(6)
+            .build();
+        row.append(&name_label);
+        row.append(&value_label);
+        row.append(&tick_label);
+        row.append(&remove_button);
+        self.imp().watch_list.append(&row);
+
+        (name_label, value_label, tick_label)
+    }
+
+    // lists every net of the open circuit with its driver and sinks, while the net list is shown
+    pub fn update_nets(&self) {
+        let template = self.imp();
+        if !template.toggle_nets_button.is_active() {
+            return;
+        }
+
+        template.clear_nets();
+        let Some(plot_provider) = template.application.borrow().imp().current_plot() else {
+            return;
+        };
+
+        let nets = plot_provider.with(|plot| plot.net_list().into_iter()
+            .map(|(connection_id, driver, sinks)| (
+                connection_id,
+                plot.get_connection(&connection_id).and_then(|connection| connection.name().clone()).unwrap_or_else(|| String::from("Unnamed net")),
+                plot.describe_port(&driver),
+                sinks.iter().map(|sink| plot.describe_port(sink)).collect::<Vec<_>>().join("\n")
+            ))
+            .collect::<Vec<_>>()
+        ).unwrap_or_default();
+
+        for (connection_id, name, driver, sinks) in nets {
+            let name_label = gtk::Label::builder().label(&name).width_chars(16).xalign(0.0).build();
+            name_label.add_css_class("heading");
+            let driver_label = gtk::Label::builder().label(&driver).width_chars(24).xalign(0.0).build();
+            let sinks_label = gtk::Label::builder()
+                .label(if sinks.is_empty() { "no sinks" } else { sinks.as_str() })
+                .hexpand(true)
+                .xalign(0.0)
+                .build();
+            if sinks.is_empty() {
+                sinks_label.add_css_class("dim-label");
+            }
+
+            let row = gtk::Box::builder()
+                .orientation(gtk::Orientation::Horizontal)
+                .spacing(12)
+                .margin_start(12)
+                .margin_end(12)
+                .margin_top(3)
+                .margin_bottom(3)
+                .build();
+            row.append(&name_label);
+            row.append(&driver_label);
+            row.append(&gtk::Label::new(Some("→")));
+            row.append(&sinks_label);
+            template.net_list.append(&row);
+            template.net_rows.borrow_mut().push(connection_id);
+        }
+    }
+
+    // selects a net in the open tab and scrolls its driver into view
+    pub fn show_connection(&self, plot_provider: PlotProvider, connection_id: ConnectionID) {
+        let position = plot_provider.with_mut(|plot| {
+            plot.unhighlight();
+            let origin = plot.get_connection(&connection_id)?.origin();
+            let position = plot.get_block(origin.block_id())?.get_connector_pos(origin.into());
+            plot.get_connection_mut(&connection_id)?.set_highlighted(true);
+            plot.set_selection(Selection::Single(Selectable::Connection(connection_id), position));
+            Some(position)
+        }).flatten();
+
+        if let Some(position) = position && let Some(page) = self.imp().view.selected_page() && let Ok(circuit_view) = page.child().downcast::<CircuitView>() {
+            circuit_view.center_on(position);
+        }
+    }
+
+    // shows the tick rate reached by the simulation, highlighting it when it falls behind the requested one
+    pub fn update_performance(&self, rate: Option<(f64, i32)>) {
+        let label = &self.imp().performance_label;
+        let Some((achieved, tps)) = rate else {
+            label.set_visible(false);
+            return;
+        };
+
+        label.set_label(&format!("{achieved:.0}/{tps} TPS"));
+        label.set_tooltip_text(Some(&format!("Simulating {achieved:.1} of {tps} requested ticks per second")));
+        if achieved < tps as f64 * Simulator::LAGGING_FACTOR {
+            label.add_css_class("warning");
+        }
+        else {
+            label.remove_css_class("warning");
+        }
+        label.set_visible(true);
+
+        // the simulator may have slowed itself down
+        self.imp().application.borrow().sync_project_gactions();
+    }
+
+    // called by the simulator after a breakpoint paused the simulation
+    pub fn breakpoint_hit(&self, breakpoint: &Breakpoint) {
+        let app = self.imp().application.borrow().clone();
+        app.sync_project_gactions();
+
+        let project = app.imp().project().clone();
+        let (message, origin) = {
+            let project = project.lock();
+            let origin = project.plot_by_descriptor(breakpoint.plot())
+                .and_then(|plot| plot.get_connection(&breakpoint.connection()))
+                .map(|connection| connection.origin().block_id());
+            (format!("Breakpoint hit: {}.", breakpoint.describe(&project)), origin)
+        };
+
+        let plot_provider = breakpoint.plot().to_provider(project);
+        self.open_tab(plot_provider.clone());
+        self.push_error_at(message, origin.map(|block_id| (plot_provider, block_id)));
+    }
+
+    // the simulator set the speed to halt, so the menu needs to follow
+    pub fn simulation_halted(&self, error: String) {
+        self.imp().application.borrow().sync_project_gactions();
+        self.push_error(error);
+    }
+
+    // opens the tab containing the block, selects it and scrolls it into view
+    pub fn show_block(&self, plot_provider: PlotProvider, block_id: BlockID) {
+        self.open_tab(plot_provider.clone());
+
+        let position = plot_provider.with_mut(|plot| {
+            plot.unhighlight();
+            let block = plot.get_block_mut(block_id)?;
+            block.set_highlighted(true);
+            let (position, size) = (block.position(), block.size());
+            plot.set_selection(Selection::Single(Selectable::Block(block_id), position));
+            Some(position + Vector2(size.0 / 2, size.1 / 2))
+        }).flatten();
+
+        if let Some(position) = position && let Some(page) = self.imp().view.selected_page() && let Ok(circuit_view) = page.child().downcast::<CircuitView>() {
+            circuit_view.center_on(position);
         }
     }
 }
@@ -116,19 +368,45 @@ pub struct CircuitPanelTemplate {
     #[template_child]
     toggle_grid_button: TemplateChild<gtk::ToggleButton>,
 
+    #[template_child]
+    toggle_watch_button: TemplateChild<gtk::ToggleButton>,
+
+    #[template_child]
+    watch_list: TemplateChild<gtk::ListBox>,
+
+    #[template_child]
+    toggle_nets_button: TemplateChild<gtk::ToggleButton>,
+
+    #[template_child]
+    net_list: TemplateChild<gtk::ListBox>,
+
     #[template_child]
     info_bar: TemplateChild<gtk::InfoBar>,
 
+    #[template_child]
+    toast_overlay: TemplateChild<adw::ToastOverlay>,
+
     #[template_child]
     info_label: TemplateChild<gtk::Label>,
 
+    #[template_child]
+    performance_label: TemplateChild<gtk::Label>,
+
+    #[template_child]
+    info_goto_button: TemplateChild<gtk::Button>,
+
     #[template_child]
     info_close_button: TemplateChild<gtk::Button>,
 
     application: RefCell<Application>,
-    pages: RefCell<HashMap<String, adw::TabPage>>,
+    // keyed by a view id, a plot can be open in several tabs
+    pages: RefCell<HashMap<Id, adw::TabPage>>,
+    menu_page: RefCell<Option<adw::TabPage>>,
     force_closing: Cell<bool>,
-    errors: RefCell<Vec<String>>
+    errors: RefCell<Vec<(String, Option<ErrorLocation>)>>,
+    error_location: RefCell<Option<ErrorLocation>>,
+    watch_rows: RefCell<Vec<(gtk::Label, gtk::Label, gtk::Label)>>,
+    net_rows: RefCell<Vec<ConnectionID>>
 }
 
 impl CircuitPanelTemplate {
@@ -136,30 +414,102 @@ impl CircuitPanelTemplate {
         let page = self.view.add_page(content, None);
         page.set_indicator_activatable(true);
         page.set_title(title);
+        Self::update_indicator(&page, content.plot_provider().with(|plot| plot.frozen()).unwrap_or_default());
         page
     }
 
-    fn new_tab(&self, title: &str, plot_provider: PlotProvider) {
+    fn update_indicator(page: &adw::TabPage, frozen: bool) {
+        if frozen {
+            page.set_indicator_icon(Some(&gio::ThemedIcon::new("media-playback-pause-symbolic")));
+            page.set_indicator_tooltip("Simulation paused; click to resume");
+        }
+        else {
+            page.set_indicator_icon(Some(&gio::ThemedIcon::new("media-playback-start-symbolic")));
+            page.set_indicator_tooltip("Simulation running; click to pause");
+        }
+    }
+
+    fn new_tab(&self, title: &str, plot_provider: PlotProvider, complexity: usize) {
+        let app = self.application.borrow().clone();
+        if complexity > app.imp().preferences().borrow().complexity_threshold() && let Some(window) = app.active_window() {
+            dialogs::run(app, window, (self.instance().clone(), title.to_owned(), plot_provider, complexity), dialogs::large_circuit);
+            return;
+        }
+
+        self.open_view(title, plot_provider, false);
+    }
+
+    fn open_view(&self, title: &str, plot_provider: PlotProvider, lite: bool) {
         let content = CircuitView::new(self.application.borrow().clone(), plot_provider);
         if self.toggle_grid_button.is_active() {
             content.set_editor_mode(EditorMode::Grid);
         }
+        content.set_lite(lite);
 
         let page = self.add_page(&content, title);
         self.view.set_selected_page(&page);
-        self.pages.borrow_mut().insert(title.to_owned(), page);
+        self.pages.borrow_mut().insert(Id::new(), page);
+    }
+
+    // opens another view of the same plot that can be panned and zoomed on its own
+    fn duplicate_tab(&self, page: &adw::TabPage) {
+        let Ok(circuit_view) = page.child().downcast::<CircuitView>() else {
+            return;
+        };
+
+        // start out where the original view is
+        circuit_view.store_view();
+        let plot_provider = circuit_view.plot_provider();
+        let title = match plot_provider.is_module() {
+            Some(module_name) => module_name.clone(),
+            None => String::from("Main Circuit")
+        };
+        let count = self.views_of(&plot_provider);
+        self.open_view(&format!("{title} ({})", count + 1), plot_provider, circuit_view.lite());
+    }
+
+    fn views_of(&self, plot_provider: &PlotProvider) -> usize {
+        let descriptor = PlotDescriptor::from(plot_provider);
+        (0..self.view.n_pages())
+            .filter_map(|i| self.view.nth_page(i).child().downcast::<CircuitView>().ok())
+            .filter(|circuit_view| PlotDescriptor::from(&circuit_view.plot_provider()) == descriptor)
+            .count()
     }
 
+    // closes every tab showing the module
     fn remove_tab(&self, module_name: &String) {
-        if let Some(page) = self.pages.borrow().get(module_name) {
-            self.view.close_page(page);
-        }
+        let pages = self.pages.borrow().values()
+            .filter(|page| matches!(page.child().downcast::<CircuitView>(), Ok(circuit_view) if circuit_view.plot_provider().is_module() == Some(module_name)))
+            .cloned()
+            .collect::<Vec<_>>();
+        pages.iter().for_each(|page| self.view.close_page(page));
     }
 
     fn set_title(&self, title: &str) {
         (self.header_bar.title_widget().unwrap().downcast_ref().unwrap() as &adw::WindowTitle).set_subtitle(title);
     }
 
+    fn show_error(&self, error: String, location: Option<ErrorLocation>) {
+        self.info_label.set_label(&error);
+        self.info_goto_button.set_visible(location.is_some());
+        self.error_location.replace(location);
+        self.info_bar.show();
+    }
+
+    fn clear_watches(&self) {
+        while let Some(row) = self.watch_list.row_at_index(0) {
+            self.watch_list.remove(&row);
+        }
+        self.watch_rows.borrow_mut().clear();
+    }
+
+    fn clear_nets(&self) {
+        while let Some(row) = self.net_list.row_at_index(0) {
+            self.net_list.remove(&row);
+        }
+        self.net_rows.borrow_mut().clear();
+    }
+
     fn close_tabs(&self) {
         self.force_closing.set(true);
         for i in (0..self.view.n_pages()).rev() {
@@ -199,19 +549,110 @@ impl ObjectImpl for CircuitPanelTemplate {
             }
         }));
 
+        self.toggle_nets_button.connect_toggled(glib::clone!(@weak self as widget => move |_| widget.instance().update_nets()));
+        self.view.connect_selected_page_notify(glib::clone!(@weak self as widget => move |_| widget.instance().update_nets()));
+        self.net_list.connect_row_activated(glib::clone!(@weak self as widget => move |_, row| {
+            let connection_id = widget.net_rows.borrow().get(row.index() as usize).copied();
+            let plot_provider = widget.application.borrow().imp().current_plot();
+            if let Some(connection_id) = connection_id && let Some(plot_provider) = plot_provider {
+                widget.instance().show_connection(plot_provider, connection_id);
+            }
+        }));
+
+        self.view.connect_setup_menu(glib::clone!(@weak self as widget => move |_, page| if let Some(page) = page {
+            widget.menu_page.replace(Some(page.clone()));
+        }));
+        let tab_actions = gio::SimpleActionGroup::new();
+        let duplicate_action = gio::SimpleAction::new("duplicate", None);
+        duplicate_action.connect_activate(glib::clone!(@weak self as widget => move |_, _| {
+            let page = widget.menu_page.take().or_else(|| widget.view.selected_page());
+            if let Some(page) = page {
+                widget.duplicate_tab(&page);
+            }
+        }));
+        tab_actions.add_action(&duplicate_action);
+        let lite_action = gio::SimpleAction::new("toggle-lite", None);
+        lite_action.connect_activate(glib::clone!(@weak self as widget => move |_, _| {
+            let page = widget.menu_page.take().or_else(|| widget.view.selected_page());
+            if let Some(page) = page && let Ok(circuit_view) = page.child().downcast::<CircuitView>() {
+                circuit_view.set_lite(!circuit_view.lite());
+            }
+        }));
+        tab_actions.add_action(&lite_action);
+        let copy_module_action = gio::SimpleAction::new("copy-module", None);
+        copy_module_action.connect_activate(glib::clone!(@weak self as widget => move |_, _| {
+            let page = widget.menu_page.take().or_else(|| widget.view.selected_page());
+            if let Some(page) = page && let Ok(circuit_view) = page.child().downcast::<CircuitView>() {
+                if let Some(module_name) = circuit_view.plot_provider().is_module() {
+                    widget.application.borrow().copy_module(module_name);
+                }
+            }
+        }));
+        tab_actions.add_action(&copy_module_action);
+        let isolation_action = gio::SimpleAction::new("toggle-isolation", None);
+        isolation_action.connect_activate(glib::clone!(@weak self as widget => move |_, _| {
+            let page = widget.menu_page.take().or_else(|| widget.view.selected_page());
+            if let Some(page) = page && let Ok(circuit_view) = page.child().downcast::<CircuitView>() {
+                if let Some(module_name) = circuit_view.plot_provider().is_module() {
+                    let project = widget.application.borrow().imp().project().clone();
+                    if let Some(module) = project.lock().module_mut(module_name) {
+                        module.set_isolated(!module.isolated());
+                    }
+                    circuit_view.rerender();
+                }
+            }
+        }));
+        tab_actions.add_action(&isolation_action);
+        self.instance().insert_action_group("tab", Some(&tab_actions));
+
+        self.view.connect_indicator_activated(|_, page| {
+            if let Ok(circuit_view) = page.child().downcast::<CircuitView>() {
+                let frozen = circuit_view.plot_provider().with_mut(|plot| {
+                    plot.set_frozen(!plot.frozen());
+                    plot.frozen()
+                });
+                if let Some(frozen) = frozen {
+                    Self::update_indicator(page, frozen);
+                }
+            }
+        });
+
         self.view.connect_close_page(glib::clone!(@weak self as widget => @default-return false, move |view, page| {
-            let is_main = page.child().downcast::<CircuitView>()
-                .map(|circuit_view| circuit_view.plot_provider().is_main());
-            view.close_page_finish(page, !matches!(is_main, Ok(true)) || widget.force_closing.get());
+            let circuit_view = page.child().downcast::<CircuitView>().ok();
+            // the main circuit always stays open in at least one tab
+            let is_main = circuit_view.as_ref().map_or(false, |circuit_view| circuit_view.plot_provider().is_main() && widget.views_of(&circuit_view.plot_provider()) == 1);
+            let pending = circuit_view.as_ref().map_or(false, |circuit_view| circuit_view.has_pending_edit());
+
+            if widget.force_closing.get() || !(is_main || pending) {
+                widget.instance().finish_close_page(page, true);
+            }
+            else if is_main {
+                view.close_page_finish(page, false);
+            }
+            else {
+                let app = widget.application.borrow().clone();
+                if let Some(window) = app.active_window() {
+                    dialogs::run(app, window, (widget.instance().clone(), page.clone()), dialogs::confirm_close_tab);
+                }
+                else {
+                    view.close_page_finish(page, false);
+                }
+            }
             true
         }));
 
         self.info_close_button.connect_clicked(glib::clone!(@weak self as widget => move |_| widget.info_bar.hide()));
-        self.info_bar.connect_hide(glib::clone!(@weak self as widget => move |bar| {
-            let mut errors = widget.errors.borrow_mut();
-            if let Some(err) = errors.pop() {
-                widget.info_label.set_label(&err);
-                bar.show();
+        self.info_goto_button.connect_clicked(glib::clone!(@weak self as widget => move |_| {
+            if let Some((plot_provider, block_id)) = widget.error_location.take() {
+                widget.instance().show_block(plot_provider, block_id);
+            }
+            widget.info_bar.hide();
+        }));
+        self.info_bar.connect_hide(glib::clone!(@weak self as widget => move |_| {
+            widget.error_location.replace(None);
+            let next = widget.errors.borrow_mut().pop();
+            if let Some((err, location)) = next {
+                widget.show_error(err, location);
             }
         }));
     }
@@ -1,6 +1,6 @@
 use std::{cell::{RefCell, Cell}, collections::HashMap};
 use gtk::{prelude::*, subclass::prelude::*, gio, glib, gdk};
-use crate::{renderer::{*, vector::*}, simulator::*, fatal::FatalResult, application::{selection::*, Application, action::Action, editor::EditorMode}};
+use crate::{renderer::{*, vector::*}, simulator::*, project::Project, fatal::FatalResult, application::{selection::*, Application, action::Action, editor::{EditorMode, WireStyle}}};
 
 glib::wrapper! {
     pub struct CircuitView(ObjectSubclass<CircuitViewTemplate>)
@@ -30,11 +30,42 @@ impl CircuitView {
         self.imp().rerender();
     }
 
+    pub fn lite(&self) -> bool {
+        self.imp().lite.get()
+    }
+
+    pub fn set_lite(&self, lite: bool) {
+        self.imp().lite.set(lite);
+        self.imp().drawing_area.queue_draw();
+    }
+
 
     pub fn plot_provider(&self) -> PlotProvider {
         self.imp().plot_provider()
     }
 
+    pub fn center_on(&self, position: Vector2<i32>) {
+        self.imp().center_on(position);
+    }
+
+    // true while a wire or block is being placed, which closing the view would throw away
+    pub fn has_pending_edit(&self) -> bool {
+        let placing = self.plot_provider()
+            .with(|plot| matches!(plot.selection(), Selection::Connection(..) | Selection::MoveBlock(..)))
+            .unwrap_or_default();
+        let quick_add = self.imp().quick_add.borrow().as_ref().map_or(false, |quick_add| quick_add.popover.is_visible());
+        placing || quick_add
+    }
+
+    // remembers the current pan and zoom in the plot so it gets saved with the project
+    pub fn store_view(&self) {
+        let (translation, scale) = {
+            let renderer = self.imp().renderer.borrow();
+            (renderer.translation(), renderer.scale())
+        };
+        self.plot_provider().with_mut(|plot| plot.set_view(translation, scale));
+    }
+
     pub fn mouse_world_position(&self) -> Vector2<f64> {
         let mouse_position = self.imp().mouse_position.get();
         self.imp().renderer.borrow().screen_to_world(mouse_position)
@@ -66,12 +97,27 @@ pub struct CircuitViewTemplate {
     #[template_child]
     area_context_menu: TemplateChild<gtk::PopoverMenu>,
 
+    #[template_child]
+    wire_context_menu: TemplateChild<gtk::PopoverMenu>,
+
     #[template_child]
     left_osd_box: TemplateChild<gtk::Box>,
 
     #[template_child]
     left_osd_label: TemplateChild<gtk::Label>,
 
+    #[template_child]
+    measure_button: TemplateChild<gtk::ToggleButton>,
+
+    #[template_child]
+    measure_osd_box: TemplateChild<gtk::Box>,
+
+    #[template_child]
+    connect_button: TemplateChild<gtk::Button>,
+
+    #[template_child]
+    measure_osd_label: TemplateChild<gtk::Label>,
+
     #[template_child]
     border_color_enabled: TemplateChild<gtk::CheckButton>,
 
@@ -85,12 +131,85 @@ pub struct CircuitViewTemplate {
     alt_down: Cell<bool>,
     application: RefCell<Application>,
     editor_mode: RefCell<EditorMode>,
-    mouse_position: Cell<Vector2<f64>>
+    mouse_position: Cell<Vector2<f64>>,
+    measurement: Cell<Option<(Vector2<i32>, Vector2<i32>)>>,
+    quick_add: RefCell<Option<QuickAdd>>,
+    find_block: RefCell<Option<FindBlock>>,
+    view_restored: Cell<bool>,
+    keyboard_wiring: Cell<bool>,
+    lite: Cell<bool>,
+    marquee_base: RefCell<Vec<Selectable>>
+}
+
+struct QuickAdd {
+    popover: gtk::Popover,
+    entry: gtk::SearchEntry,
+    list: gtk::ListBox,
+    builtins: gtk::CheckButton,
+    matches: RefCell<Vec<String>>,
+    position: Cell<Vector2<i32>>
+}
+
+impl QuickAdd {
+    const MAX_MATCHES: usize = 8;
+
+    // scores how well `query` matches `name`, None if the query's characters don't appear in order
+    fn score(query: &str, name: &str) -> Option<u8> {
+        let (query, name) = (query.to_lowercase(), name.to_lowercase());
+        if query.is_empty() || name.starts_with(&query) {
+            return Some(3);
+        }
+        if name.contains(&query) {
+            return Some(2);
+        }
+
+        let mut chars = name.chars();
+        query.chars().all(|c| chars.any(|n| n == c)).then_some(1)
+    }
+
+    // the names of the modules matching `query`, best match first: by score, then by how recently the module was placed,
+    // then modules of the project before built-in ones
+    fn ranked(project: &Project, query: &str, include_builtins: bool) -> Vec<String> {
+        let recent = project.recent_modules();
+        let mut matches = project.modules().values()
+            .filter(|module| !module.hidden())
+            .filter_map(|module| Self::score(query, module.name()).map(|score| {
+                let recency = recent.iter().position(|name| name == module.name()).map(|index| recent.len() - index);
+                ((score, recency, !module.builtin()), module.name().clone())
+            }))
+            .collect::<Vec<_>>();
+
+        // built-in modules only show up when asked for or when no module of the project matches
+        if !include_builtins && matches.iter().any(|((_, _, custom), _)| *custom) {
+            matches.retain(|((_, _, custom), _)| *custom);
+        }
+
+        matches.sort_by(|(a_rank, a), (b_rank, b)| b_rank.cmp(a_rank).then(a.len().cmp(&b.len())).then(a.cmp(b)));
+        matches.into_iter().map(|(_, name)| name).collect()
+    }
+
+    fn select_relative(&self, offset: i32) {
+        let index = self.list.selected_row().map(|row| row.index()).unwrap_or(0) + offset;
+        if let Some(row) = self.list.row_at_index(index) {
+            self.list.select_row(Some(&row));
+        }
+    }
+}
+
+// highlights every block whose name matches while typing
+struct FindBlock {
+    popover: gtk::Popover,
+    entry: gtk::SearchEntry,
+    count_label: gtk::Label,
+    matches: RefCell<Vec<BlockID>>,
+    current: Cell<Option<usize>>
 }
 
 impl CircuitViewTemplate {
     fn rerender(&self) {
         self.drawing_area.queue_draw();
+        // keep a shown signal readout up to date with the simulation
+        self.drawing_area.trigger_tooltip_query();
     }
 
     fn plot_provider(&self) -> PlotProvider {
@@ -132,6 +251,265 @@ impl CircuitViewTemplate {
             widget.border_color_button.set_sensitive(button.is_active());
         }));
         self.border_color_button.set_sensitive(false);
+
+        self.measure_button.connect_toggled(glib::clone!(@weak self as widget => move |button| {
+            widget.measurement.set(None);
+            widget.measure_osd_box.set_visible(button.is_active());
+            widget.update_measurement();
+            widget.drawing_area.queue_draw();
+        }));
+    }
+
+    // the selection changes with nearly every redraw, so the connect command follows it from here
+    fn update_connect_button(&self) {
+        let Some(pins) = self.plot_provider.borrow().with(|plot| plot.selected_pins().map(|_| ())) else {
+            return;
+        };
+
+        self.connect_button.set_tooltip_text(Some(match &pins {
+            Ok(()) => "Connect the selected pins (Ctrl+J)",
+            Err(reason) => reason.as_str()
+        }));
+        if let Some(action) = self.application.borrow().lookup_action("connect-selected-pins")
+            .and_then(|action| action.downcast::<gio::SimpleAction>().ok()) {
+            action.set_enabled(pins.is_ok());
+        }
+    }
+
+    fn init_quick_add(&self) {
+        let entry = gtk::SearchEntry::builder()
+            .placeholder_text("Add module...")
+            .build();
+        let list = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::Browse)
+            .build();
+        let builtins = gtk::CheckButton::builder()
+            .label("Include built-in modules")
+            .build();
+
+        let content = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(6)
+            .build();
+        content.append(&entry);
+        content.append(&list);
+        content.append(&builtins);
+
+        let popover = gtk::Popover::builder()
+            .child(&content)
+            .has_arrow(false)
+            .build();
+        popover.set_parent(&*self.drawing_area);
+
+        entry.connect_search_changed(glib::clone!(@weak self as widget => move |entry| widget.update_quick_add(entry.text().as_str())));
+        builtins.connect_toggled(glib::clone!(@weak self as widget, @weak entry => move |_| widget.update_quick_add(entry.text().as_str())));
+        entry.connect_activate(glib::clone!(@weak self as widget => move |_| widget.place_quick_add()));
+        list.connect_row_activated(glib::clone!(@weak self as widget => move |_, _| widget.place_quick_add()));
+        popover.connect_closed(glib::clone!(@weak self as widget => move |_| { widget.drawing_area.grab_focus(); }));
+
+        let key_controller = gtk::EventControllerKey::new();
+        key_controller.connect_key_pressed(glib::clone!(@weak self as widget => @default-return gtk::Inhibit(false), move |_, key, _, _| {
+            if let Some(quick_add) = widget.quick_add.borrow().as_ref() {
+                match key {
+                    gdk::Key::Down => quick_add.select_relative(1),
+                    gdk::Key::Up => quick_add.select_relative(-1),
+                    _ => return gtk::Inhibit(false)
+                }
+            }
+            gtk::Inhibit(true)
+        }));
+        entry.add_controller(&key_controller);
+
+        self.quick_add.replace(Some(QuickAdd {
+            popover,
+            entry,
+            list,
+            builtins,
+            matches: RefCell::new(Vec::new()),
+            position: Cell::new(Vector2::default())
+        }));
+    }
+
+    fn show_quick_add(&self) {
+        if let Some(quick_add) = self.quick_add.borrow().as_ref() {
+            let mouse_position = self.mouse_position.get();
+            let position = self.renderer.borrow().screen_to_world(mouse_position);
+            quick_add.position.set(self.editor_mode.borrow().align(VectorCast::cast(position)));
+
+            quick_add.entry.set_text("");
+            quick_add.popover.set_pointing_to(Some(&gdk::Rectangle::new(mouse_position.0 as i32, mouse_position.1 as i32, 1, 1)));
+            quick_add.popover.popup();
+            quick_add.entry.grab_focus();
+        }
+        self.update_quick_add("");
+    }
+
+    fn update_quick_add(&self, query: &str) {
+        let include_builtins = self.quick_add.borrow().as_ref().map_or(false, |quick_add| quick_add.builtins.is_active());
+        let matches = QuickAdd::ranked(&self.application.borrow().imp().project().lock(), query, include_builtins);
+
+        if let Some(quick_add) = self.quick_add.borrow().as_ref() {
+            while let Some(row) = quick_add.list.row_at_index(0) {
+                quick_add.list.remove(&row);
+            }
+
+            let matches = matches.into_iter().take(QuickAdd::MAX_MATCHES).collect::<Vec<_>>();
+            for name in matches.iter() {
+                quick_add.list.append(&gtk::Label::builder().label(name).xalign(0.).build());
+            }
+            quick_add.list.select_row(quick_add.list.row_at_index(0).as_ref());
+            quick_add.matches.replace(matches);
+        }
+    }
+
+    fn place_quick_add(&self) {
+        let Some((name, position)) = self.quick_add.borrow().as_ref().and_then(|quick_add| {
+            quick_add.popover.popdown();
+            let index = quick_add.list.selected_row().map(|row| row.index()).unwrap_or(0);
+            quick_add.matches.borrow().get(index as usize).cloned().map(|name| (name, quick_add.position.get()))
+        }) else {
+            return;
+        };
+
+        let app = self.application.borrow().clone();
+        let border_color = self.border_color_enabled.is_active().then(|| self.border_color_button.rgba().into_color());
+        let block = app.imp().project().lock()
+            .module(&name)
+            .map(|module| Block::new(&module, position, border_color));
+        if let Some(block) = block {
+            app.new_action(Action::NewBlock(self.plot_provider(), block));
+        }
+    }
+
+    fn init_find_block(&self) {
+        let entry = gtk::SearchEntry::builder()
+            .placeholder_text("Find block...")
+            .build();
+        let count_label = gtk::Label::new(None);
+        count_label.add_css_class("dim-label");
+
+        let content = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(6)
+            .build();
+        content.append(&entry);
+        content.append(&count_label);
+
+        let popover = gtk::Popover::builder()
+            .child(&content)
+            .has_arrow(false)
+            .position(gtk::PositionType::Bottom)
+            .build();
+        popover.set_parent(&*self.drawing_area);
+
+        entry.connect_search_changed(glib::clone!(@weak self as widget => move |entry| widget.update_find_block(entry.text().as_str())));
+        entry.connect_activate(glib::clone!(@weak self as widget => move |_| widget.find_next_block(false)));
+        entry.connect_next_match(glib::clone!(@weak self as widget => move |_| widget.find_next_block(false)));
+        entry.connect_previous_match(glib::clone!(@weak self as widget => move |_| widget.find_next_block(true)));
+        entry.connect_stop_search(glib::clone!(@weak popover => move |_| popover.popdown()));
+        popover.connect_closed(glib::clone!(@weak self as widget => move |_| { widget.drawing_area.grab_focus(); }));
+
+        self.find_block.replace(Some(FindBlock {
+            popover,
+            entry,
+            count_label,
+            matches: RefCell::new(Vec::new()),
+            current: Cell::new(None)
+        }));
+    }
+
+    fn show_find_block(&self) {
+        if let Some(find_block) = self.find_block.borrow().as_ref() {
+            find_block.popover.set_pointing_to(Some(&gdk::Rectangle::new(12, 12, 1, 1)));
+            find_block.popover.popup();
+            find_block.entry.grab_focus();
+        }
+        // pick up blocks added since the last search
+        let query = self.find_block.borrow().as_ref().map(|find_block| find_block.entry.text().to_string()).unwrap_or_default();
+        self.update_find_block(&query);
+    }
+
+    // highlights all blocks whose name contains `query`, an empty query clears the highlights
+    fn update_find_block(&self, query: &str) {
+        let query = query.to_lowercase();
+        let matches = self.plot_provider.borrow().with_mut(|plot| {
+            plot.unhighlight();
+            if query.is_empty() {
+                plot.set_selection(Selection::None);
+                return vec![];
+            }
+
+            let matches = plot.blocks_in_reading_order().into_iter()
+                .filter(|block_id| plot.get_block(*block_id).map_or(false, |block| block.name().to_lowercase().contains(&query)))
+                .collect::<Vec<_>>();
+            matches.iter().for_each(|block_id| if let Some(block) = plot.get_block_mut(*block_id) {
+                block.set_highlighted(true);
+            });
+            plot.set_selection(Selection::Many(matches.iter().map(|block_id| Selectable::Block(*block_id)).collect()));
+            matches
+        }).unwrap_or_default();
+
+        if let Some(find_block) = self.find_block.borrow().as_ref() {
+            find_block.count_label.set_label(&match matches.len() {
+                _ if query.is_empty() => String::new(),
+                0 => String::from("No matches"),
+                1 => String::from("1 match"),
+                n => format!("{n} matches")
+            });
+            find_block.matches.replace(matches);
+            find_block.current.set(None);
+        }
+        self.rerender();
+    }
+
+    // scrolls to the next or previous match, keeping all of them highlighted
+    fn find_next_block(&self, backwards: bool) {
+        let Some((block_id, index, count)) = self.find_block.borrow().as_ref().and_then(|find_block| {
+            let matches = find_block.matches.borrow();
+            if matches.is_empty() {
+                return None;
+            }
+
+            let index = match find_block.current.get() {
+                Some(i) if backwards => (i + matches.len() - 1) % matches.len(),
+                Some(i) => (i + 1) % matches.len(),
+                None if backwards => matches.len() - 1,
+                None => 0
+            };
+            find_block.current.set(Some(index));
+            Some((matches[index], index, matches.len()))
+        }) else {
+            return;
+        };
+
+        let center = self.plot_provider.borrow()
+            .with(|plot| plot.get_block(block_id).map(|block| block.position() + Vector2(block.size().0 / 2, block.size().1 / 2)))
+            .flatten();
+        if let Some(center) = center {
+            self.center_on(center);
+        }
+
+        if let Some(find_block) = self.find_block.borrow().as_ref() {
+            find_block.count_label.set_label(&format!("{} of {count}", index + 1));
+        }
+    }
+
+    fn update_measurement(&self) {
+        let mut readout = Vec::new();
+        if let Some((start, end)) = self.measurement.get() {
+            let delta = end - start;
+            let distance = (delta.0 as f64).hypot(delta.1 as f64);
+            readout.push(format!("Distance: {distance:.1} (Δx {}, Δy {})", delta.0, delta.1));
+        }
+
+        if let Some((min, max)) = self.plot_provider.borrow().with(|plot| plot.selection_bounds()).flatten() {
+            readout.push(format!("Selection: {} × {}", max.0 - min.0, max.1 - min.1));
+        }
+
+        if readout.is_empty() {
+            readout.push(String::from("Drag to measure"));
+        }
+        self.measure_osd_label.set_label(&readout.join("\n"));
     }
 
     fn init_mouse(&self) {
@@ -148,6 +526,15 @@ impl CircuitViewTemplate {
                 widget.renderer.borrow_mut().save_translation();
                 widget.set_left_osd_visible(true);
             }
+            else if widget.keyboard_wiring.take() {
+                widget.finish_keyboard_wiring(VectorCast::cast(widget.renderer.borrow().screen_to_world(Vector2(x, y))));
+            }
+            else if widget.measure_button.is_active() {
+                let position = VectorCast::cast(widget.renderer.borrow().screen_to_world(Vector2(x, y)));
+                widget.measurement.set(Some((position, position)));
+                widget.update_measurement();
+                widget.drawing_area.queue_draw();
+            }
             else {
                 widget.drag_begin(VectorCast::cast(widget.renderer.borrow().screen_to_world(Vector2(x, y))));
             }
@@ -164,6 +551,13 @@ impl CircuitViewTemplate {
                 let translation = widget.renderer.borrow().translation();
                 widget.set_left_osd_label(&format!("{}, {}", translation.x() as i32, translation.y() as i32));
             }
+            else if widget.measure_button.is_active() {
+                if let Some((start, _)) = widget.measurement.get() {
+                    widget.measurement.set(Some((start, start + Vector2((x / scale) as i32, (y / scale) as i32))));
+                    widget.update_measurement();
+                    widget.drawing_area.queue_draw();
+                }
+            }
             else {
                 widget.drag_update(Vector2((x / scale) as i32, (y / scale) as i32));
             }
@@ -176,7 +570,7 @@ impl CircuitViewTemplate {
             if widget.ctrl_down.get() && (x != 0. || y != 0.) {
                 widget.set_left_osd_visible(false);
             }
-            else {
+            else if !widget.measure_button.is_active() {
                 let scale = widget.renderer.borrow().scale();
                 widget.drag_end(Vector2((x / scale) as i32, (y / scale) as i32));
             }
@@ -201,6 +595,18 @@ impl CircuitViewTemplate {
                 gdk::Key::Control_L | gdk::Key::Control_R => widget.ctrl_down.set(true),
                 gdk::Key::Shift_L | gdk::Key::Shift_R => widget.shift_down.set(true),
                 gdk::Key::Alt_L | gdk::Key::Alt_R => widget.alt_down.set(true),
+                gdk::Key::BackSpace => widget.application.borrow().activate_action("delete-block", None),
+                gdk::Key::space => widget.show_quick_add(),
+                gdk::Key::f if !widget.ctrl_down.get() => widget.show_find_block(),
+                gdk::Key::Home => widget.reset_view(),
+                gdk::Key::w => widget.start_keyboard_wiring(),
+                gdk::Key::Escape => widget.cancel_wiring(),
+                gdk::Key::Tab => widget.focus_next_block(false),
+                gdk::Key::ISO_Left_Tab => widget.focus_next_block(true),
+                gdk::Key::Left => widget.focus_block_in_direction(Vector2(-1, 0)),
+                gdk::Key::Right => widget.focus_block_in_direction(Vector2(1, 0)),
+                gdk::Key::Up => widget.focus_block_in_direction(Vector2(0, -1)),
+                gdk::Key::Down => widget.focus_block_in_direction(Vector2(0, 1)),
                 _ => ()
             }
             gtk::Inhibit(true)
@@ -232,14 +638,42 @@ impl CircuitViewTemplate {
     }
 
     fn init_drawing_area(&self) {
-        self.drawing_area.set_draw_func(glib::clone!(@weak self as widget => move |area, context, width, height|
+        self.drawing_area.set_draw_func(glib::clone!(@weak self as widget => move |area, context, width, height| {
+            if !widget.view_restored.get() && width > 0 && height > 0 {
+                widget.view_restored.set(true);
+                widget.restore_view(Vector2(width, height));
+            }
+
+            let (net_colors, connection_counts) = widget.plot_provider.borrow().project()
+                .map(|project| {
+                    let project = project.lock();
+                    (project.net_colors(), project.connection_counts())
+                })
+                .unwrap_or_default();
+            let wire_style = widget.application.borrow().imp().preferences().borrow().wire_style();
+            let lite = widget.lite.get();
+            let (wire_style, net_colors, connection_counts) = if lite { (WireStyle::Straight, false, false) } else { (wire_style, net_colors, connection_counts) };
+            widget.renderer.borrow_mut().set_lite(lite);
+            widget.renderer.borrow_mut().set_wire_style(wire_style);
+            widget.renderer.borrow_mut().set_net_colors(net_colors);
+            widget.renderer.borrow_mut().set_connection_counts(connection_counts);
+            widget.renderer.borrow_mut().set_focus(widget.application.borrow().focus_mode());
+
             widget.plot_provider.borrow().with_mut(|plot| 
                 widget.renderer.borrow_mut()
                     .callback(plot, *widget.editor_mode.borrow(), area, context, width, height)
                     .map(|_| ())
                     .unwrap_or_die()
             );
-        ));
+
+            if let Some((start, end)) = widget.measurement.get() {
+                let renderer = widget.renderer.borrow();
+                renderer.set_color(unsafe { &COLOR_THEME.accent_fg_color }).set_line_width(1.);
+                renderer.move_to(start).line_to(end).stroke().map(|_| ()).unwrap_or_die();
+            }
+
+            widget.update_connect_button();
+        }));
 
         self.drawing_area.set_focusable(true);
         self.drawing_area.grab_focus();
@@ -261,6 +695,61 @@ impl CircuitViewTemplate {
         self.init_keyboard();
         self.init_scrolling();
         self.init_context_menu();
+        self.init_quick_add();
+        self.init_find_block();
+        self.init_signal_readout();
+
+        if let PlotProvider::Module(_, module_name) = self.plot_provider() && let Some(menu) = self.area_context_menu.menu_model().and_downcast::<gio::Menu>() {
+            let item = gio::MenuItem::new(Some("Find _Instances"), None);
+            item.set_action_and_target_value(Some("app.find-module-instances"), Some(&module_name.to_variant()));
+            menu.append_item(&item);
+        }
+    }
+
+    fn init_signal_readout(&self) {
+        self.drawing_area.set_has_tooltip(true);
+        self.drawing_area.connect_query_tooltip(glib::clone!(@weak self as widget => @default-return false, move |_, x, y, _, tooltip| {
+            match widget.signal_readout(Vector2(x as f64, y as f64)) {
+                Some(readout) => {
+                    tooltip.set_text(Some(&readout));
+                    true
+                }
+                None => false
+            }
+        }));
+    }
+
+    // describes the live value of the pin or wire at `position` in screen space
+    fn signal_readout(&self, position: Vector2<f64>) -> Option<String> {
+        let position = VectorCast::cast(self.renderer.borrow().screen_to_world(position));
+        let wire_style = self.wire_style();
+        let plot_provider = self.plot_provider();
+        let descriptor = PlotDescriptor::from(&plot_provider);
+        let project = plot_provider.project()?;
+        let project = project.lock();
+        let plot = project.plot_by_descriptor(&descriptor)?;
+
+        let pin = plot.get_block_at(position).and_then(|id| plot.get_block(id)).and_then(|block| {
+            let (pin, connector) = block.position_on_connection(position, true)
+                .map(|i| (format!("input {}", i + 1), Connector::Input(i)))
+                .or_else(|| block.position_on_connection(position, false).map(|i| (format!("output {}", i + 1), Connector::Output(i))))?;
+            Some((format!("{} {pin}", block.name()), block.connection(connector)))
+        });
+        let (pin, connection_id) = match pin {
+            Some((pin, connection_id)) => (Some(pin), connection_id),
+            None => (None, Some(plot.get_connection_at(position, wire_style)?))
+        };
+
+        let net = connection_id.and_then(|id| plot.get_connection(&id).map(|connection| (
+            project.describe_net(&descriptor, &id),
+            if connection.is_active() { "1" } else { "0" }
+        )));
+        Some(match (pin, net) {
+            (Some(pin), Some((net, value))) => format!("{pin}: {value}\n{net}"),
+            (Some(pin), None) => format!("{pin}: unconnected"),
+            (None, Some((net, value))) => format!("{net}: {value}"),
+            (None, None) => return None
+        })
     }
 
     fn on_mouse_move(&self, x: f64, y: f64) {
@@ -275,11 +764,125 @@ impl CircuitViewTemplate {
                 block.set_position(position);
                 self.drawing_area.queue_draw();
             }
+            else if self.keyboard_wiring.get() && let Selection::Connection(source, start, _) = plot.selection().clone() {
+                let end = self.connection_end(plot, VectorCast::cast(self.renderer.borrow().screen_to_world(position)));
+                plot.set_selection(Selection::Connection(source, start, end));
+                self.drawing_area.queue_draw();
+            }
         );
     }
 
+    // where a connection being drawn to `position` ends, snapping to nearby input pins
+    fn connection_end(&self, plot: &Plot, position: Vector2<i32>) -> Vector2<i32> {
+        plot.get_input_near(position, Plot::PIN_SNAP_DISTANCE)
+            .map(|(_, pin)| pin)
+            .unwrap_or_else(|| self.editor_mode.borrow().align(position))
+    }
+
+    fn center_on(&self, position: Vector2<i32>) {
+        self.view_restored.set(true);
+        let mut renderer = self.renderer.borrow_mut();
+        let (size, scale) = (renderer.size(), renderer.scale());
+        renderer.translate(Vector2(size.0 as f64 / 2. - position.0 as f64 * scale, size.1 as f64 / 2. - position.1 as f64 * scale));
+        drop(renderer);
+        self.rerender();
+    }
+
+    // the block keyboard navigation starts from
+    fn focused_block(&self, plot: &Plot) -> Option<BlockID> {
+        plot.selected().iter().find_map(|selected| selected.block_id())
+    }
+
+    // moves the selection to the next or previous block in reading order, wrapping around at the ends
+    fn focus_next_block(&self, backwards: bool) {
+        let next = self.plot_provider.borrow().with(|plot| plot.next_in_reading_order(self.focused_block(plot), backwards)).flatten();
+
+        if let Some(block_id) = next {
+            self.focus_block(block_id);
+        }
+    }
+
+    fn focus_block_in_direction(&self, direction: Vector2<i32>) {
+        let next = self.plot_provider.borrow().with(|plot| match self.focused_block(plot) {
+            Some(block_id) => plot.nearest_block_in_direction(block_id, direction),
+            None => plot.blocks_in_reading_order().first().copied()
+        }).flatten();
+
+        if let Some(block_id) = next {
+            self.focus_block(block_id);
+        }
+    }
+
+    fn focus_block(&self, block_id: BlockID) {
+        let center = self.plot_provider.borrow().with_mut(|plot| {
+            plot.unhighlight();
+            let block = plot.get_block_mut(block_id)?;
+            block.set_highlighted(true);
+            let (position, size) = (block.position(), block.size());
+            plot.set_selection(Selection::Single(Selectable::Block(block_id), position));
+            Some(position + Vector2(size.0 / 2, size.1 / 2))
+        }).flatten();
+
+        if let Some(center) = center {
+            self.center_on(center);
+        }
+    }
+
+    // starts a connection from the output pin or waypoint under the mouse, the next click places its end
+    fn start_keyboard_wiring(&self) {
+        if self.plot_provider.borrow().is_locked() {
+            return;
+        }
+
+        let position = VectorCast::cast(self.renderer.borrow().screen_to_world(self.mouse_position.get()));
+        let started = self.plot_provider.borrow().with_mut(|plot| {
+            let source = plot.get_block_at(position)
+                .and_then(|id| plot.get_block(id).map(|block| (id, block)))
+                .and_then(|(id, block)| block.position_on_connection(position, false)
+                    .map(|i| (ConnectionSource::Block(id, i), block.get_connector_pos(Connector::Output(i))))
+                )
+                .or_else(|| plot.get_waypoint_at(position).and_then(|id| {
+                    let start = *plot.get_connection(id.connection_id())?.get_segment(id.location())?.position()?;
+                    Some((ConnectionSource::Waypoint(id), start))
+                }));
+
+            source.map(|(source, start)| {
+                plot.unhighlight();
+                plot.set_selection(Selection::Connection(source, start, start));
+            }).is_some()
+        }).unwrap_or(false);
+
+        self.keyboard_wiring.set(started);
+        self.drawing_area.queue_draw();
+    }
+
+    fn finish_keyboard_wiring(&self, position: Vector2<i32>) {
+        let placed = self.plot_provider.borrow().with_mut(|plot| match plot.selection().clone() {
+            Selection::Connection(source, start, _) => {
+                let end = self.connection_end(plot, position);
+                plot.set_selection(Selection::Connection(source, start, end));
+                true
+            }
+            _ => false
+        }).unwrap_or(false);
+
+        // placing the end works just like dropping a dragged connection
+        if placed {
+            self.drag_end(Vector2::default());
+        }
+    }
+
+    fn cancel_wiring(&self) {
+        self.keyboard_wiring.set(false);
+        self.plot_provider.borrow().with_mut(|plot| if plot.selection().connecting() {
+            plot.unhighlight();
+        });
+        self.drawing_area.queue_draw();
+    }
+
     fn context_menu(&self, x: f64, y: f64) {        
         let position = self.renderer.borrow().screen_to_world(Vector2(x, y));
+        let wire_style = self.wire_style();
 
         self.plot_provider.borrow_mut().with_mut(|plot| {
             match plot.get_block_at(VectorCast::cast(position)) {
@@ -306,6 +909,25 @@ impl CircuitViewTemplate {
                         self.context_menu.popup();
                     }
                 }
+                None if let Some(id) = plot.get_waypoint_at(VectorCast::cast(position)) => {
+                    plot.unhighlight();
+                    if let Some(waypoint) = plot.get_connection_mut(id.connection_id()).and_then(|c| c.get_segment_mut(id.location())) {
+                        waypoint.set_highlighted(true);
+                        let start = *waypoint.position().unwrap();
+                        plot.set_selection(Selection::Single(Selectable::Waypoint(id), start));
+                    }
+
+                    self.wire_context_menu.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+                    self.wire_context_menu.popup();
+                }
+                None if let Some(id) = plot.get_connection_at(VectorCast::cast(position), wire_style) => {
+                    plot.unhighlight();
+                    plot.get_connection_mut(&id).unwrap().set_highlighted(true);
+                    plot.set_selection(Selection::Single(Selectable::Connection(id), VectorCast::cast(position)));
+
+                    self.wire_context_menu.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+                    self.wire_context_menu.popup();
+                }
                 None => {
                     self.area_context_menu.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
                     self.area_context_menu.popup();
@@ -324,7 +946,54 @@ impl CircuitViewTemplate {
         self.left_osd_label.set_text(label);
     }
 
+    // applies the view saved with the plot, or fits the plot's content into view if
+    // there is none or the saved one doesn't show any of the content anymore
+    fn restore_view(&self, size: Vector2<i32>) {
+        const MARGIN: f64 = 50.;
+        let (saved, bounds) = self.plot_provider.borrow()
+            .with(|plot| (plot.view(), plot.content_bounds()))
+            .unwrap_or_default();
+
+        let visible = |translation: Vector2<f64>, scale: f64| bounds.map_or(true, |(start, end)|
+            start.0 as f64 * scale + translation.0 < size.0 as f64 && end.0 as f64 * scale + translation.0 > 0. &&
+            start.1 as f64 * scale + translation.1 < size.1 as f64 && end.1 as f64 * scale + translation.1 > 0.
+        );
+
+        let (translation, scale) = match (saved, bounds) {
+            (Some((translation, scale)), _) if scale > 0. && visible(translation, scale) => (translation, scale),
+            (_, Some((start, end))) => {
+                let content = Vector2((end.0 - start.0) as f64 + 2. * MARGIN, (end.1 - start.1) as f64 + 2. * MARGIN);
+                let scale = (size.0 as f64 / content.0).min(size.1 as f64 / content.1).clamp(0.1, DEFAULT_SCALE);
+                let center = Vector2((start.0 + end.0) as f64 / 2., (start.1 + end.1) as f64 / 2.);
+                (Vector2(size.0 as f64 / 2. - center.0 * scale, size.1 as f64 / 2. - center.1 * scale), scale)
+            }
+            _ => (Vector2::default(), DEFAULT_SCALE)
+        };
+
+        let mut renderer = self.renderer.borrow_mut();
+        renderer.set_scale(scale);
+        renderer.translate(translation);
+        drop(renderer);
+        self.set_left_osd_label(&format!("{}, {}", translation.x() as i32, translation.y() as i32));
+    }
+
+    // zooms back to 1x and scrolls the first blocks of the plot into the top-left corner
+    fn reset_view(&self) {
+        const MARGIN: i32 = 50;
+        let start = self.plot_provider.borrow().with(|plot| plot.content_bounds()).flatten().map(|(start, _)| start).unwrap_or_default();
+        let translation = Vector2((MARGIN - start.0) as f64 * DEFAULT_SCALE, (MARGIN - start.1) as f64 * DEFAULT_SCALE);
+
+        let mut renderer = self.renderer.borrow_mut();
+        renderer.set_scale(DEFAULT_SCALE);
+        renderer.translate(translation);
+        drop(renderer);
+
+        self.drawing_area.queue_draw();
+        self.set_left_osd_label(&format!("{}, {}", translation.x() as i32, translation.y() as i32));
+    }
+
     fn selection_shift_click(&self, selected: Vec<Selectable>, position: Vector2<i32>) -> bool {
+        let wire_style = self.wire_style();
         self.plot_provider.borrow().with_mut(move |p| 
             if let Some(block_id) = p.get_block_at(position) {
                 let mut selected = selected.clone();
@@ -354,18 +1023,51 @@ impl CircuitViewTemplate {
 
                 true
             }
+            else if let Some(connection_id) = p.get_connection_at(position, wire_style) {
+                let mut selected = selected.clone();
+                let connection = p.get_connection_mut(&connection_id).unwrap();
+                if let Some(index) = selected.iter().position(|sel| sel == &Selectable::Connection(connection_id)) {
+                    selected.remove(index);
+                    connection.set_highlighted(false);
+                }
+                else {
+                    connection.set_highlighted(true);
+                    selected.push(Selectable::Connection(connection_id));
+                }
+                p.set_selection(Selection::Many(selected));
+
+                true
+            }
             else {
                 false
             }
         ).unwrap_or(false)
     }
 
+    // how wires are currently drawn, used for hit-testing them
+    fn wire_style(&self) -> WireView {
+        WireView::of(&*self.renderer.borrow())
+    }
+
     fn drag_begin(&self, position: Vector2<i32>) {
+        // a locked module can still be clicked and selected, but nothing may be moved or wired
+        let locked = self.plot_provider.borrow().is_locked();
         let selection = self.plot_provider.borrow().with(|p| p.selection().clone());
         match selection {
-            Some(Selection::MoveBlock(block)) =>
-                self.application.borrow()
-                    .new_action(Action::NewBlock(self.plot_provider.borrow().clone(), *block)),
+            Some(Selection::MoveBlock(block)) => {
+                // dropped onto a wire, a block with one input and one output gets inserted into it
+                let plot_provider = self.plot_provider.borrow().clone();
+                let spliced = plot_provider.with(|plot| plot.get_connection_at(position, self.wire_style())
+                    .and_then(|connection_id| plot.splice_block(&block, connection_id))
+                ).flatten();
+                self.application.borrow().new_action(match spliced {
+                    Some((original, upstream, downstream)) => Action::SpliceBlock(plot_provider, *block, original, upstream, downstream),
+                    None => Action::NewBlock(plot_provider, *block)
+                });
+                if locked {
+                    self.plot_provider.borrow().with_mut(|plot| plot.set_selection(Selection::None));
+                }
+            }
             Some(Selection::Many(block_ids)) => 
                 if self.shift_down.get() && self.selection_shift_click(block_ids, position) {
                     self.drawing_area.queue_draw();
@@ -379,7 +1081,15 @@ impl CircuitViewTemplate {
             _ => ()   
         }
 
+        let wire_style = self.wire_style();
         self.plot_provider.borrow().with_mut(|plot| {
+            // on empty canvas the selection is kept until drag_end knows if this was a click or a marquee
+            if plot.get_block_at(position).is_none() && plot.get_waypoint_at(position).is_none() && plot.get_connection_at(position, wire_style).is_none() {
+                self.marquee_base.replace(plot.selected());
+                plot.set_selection(Selection::Area(position, position));
+                return;
+            }
+
             plot.unhighlight();
             if let Some(id) = plot.get_block_at(position) {
                 let block = plot.get_block_mut(id).unwrap();
@@ -388,7 +1098,11 @@ impl CircuitViewTemplate {
                     plot.set_selection(Selection::MouseEvent(id));
                     plot.add_block_to_update(id);
                 }
-                else if let Some(i) = block.position_on_connection(position, false) {
+                else if !locked && let Some(i) = block.position_on_connection(position, true) && let Some(connection_id) = block.connection(Connector::Input(i)) {
+                    let start = block.get_connector_pos(Connector::Input(i));
+                    plot.set_selection(Selection::Connection(ConnectionSource::Endpoint(connection_id, id, i), start, start));
+                }
+                else if !locked && let Some(i) = block.position_on_connection(position, false) {
                     let start = block.get_connector_pos(Connector::Output(i));
                     plot.set_selection(Selection::Connection(ConnectionSource::Block(id, i), start, start));
                 }
@@ -402,7 +1116,7 @@ impl CircuitViewTemplate {
                 let waypoint = plot.get_connection_mut(id.connection_id()).and_then(|c| c.get_segment_mut(id.location())).unwrap();
                 let start = *waypoint.position().unwrap();
 
-                if self.alt_down.take() {
+                if self.alt_down.take() && !locked {
                     plot.set_selection(Selection::Connection(ConnectionSource::Waypoint(id), start, start))                    
                 }
                 else {
@@ -410,8 +1124,9 @@ impl CircuitViewTemplate {
                     plot.set_selection(Selection::Single(Selectable::Waypoint(id), start))
                 }
             }
-            else {
-                plot.set_selection(Selection::Area(position, position));
+            else if let Some(id) = plot.get_connection_at(position, wire_style) {
+                plot.get_connection_mut(&id).unwrap().set_highlighted(true);
+                plot.set_selection(Selection::Single(Selectable::Connection(id), position));
             }
         });
     
@@ -419,8 +1134,10 @@ impl CircuitViewTemplate {
     }
         
     fn drag_update(&self, offset: Vector2<i32>) {
+        let locked = self.plot_provider.borrow().is_locked();
         self.plot_provider.borrow().with_mut(|plot|
             match plot.selection().clone() {
+                Selection::Single(..) if locked => (),
                 Selection::Single(selected, Vector2(start_x, start_y)) => {
                     let new_position = self.editor_mode.borrow().align(Vector2(start_x, start_y) + offset);
 
@@ -443,11 +1160,12 @@ impl CircuitViewTemplate {
 
                             waypoint.unwrap().set_position(new_position);
                         }
+                        Selectable::Connection(_) => return
                     }
                     self.drawing_area.queue_draw();
                 }
                 Selection::Connection(source, start, _) => {
-                    let new_position = self.editor_mode.borrow().align(start + offset);
+                    let new_position = self.connection_end(plot, start + offset);
                     plot.set_selection(Selection::Connection(source, start, new_position));
                     self.drawing_area.queue_draw();
                 }
@@ -489,27 +1207,29 @@ impl CircuitViewTemplate {
                             Action::MoveWaypoint(plot_provider.clone(), id, Vector2(start_x, start_y), new_position)
                         })
                     }
+                    Selectable::Connection(_) => ()
                 }
             },
             Selection::Connection(ConnectionSource::Block(origin_id, output), _, position) => {
                 let connection = plot_provider.with_mut(|plot| {
                     plot.set_selection(Selection::None);
 
-                    if let Some(block_id) = plot.get_block_at(position) &&
-                        let Some(block) = plot.get_block(block_id) &&
-                        let Some(i) = block.position_on_connection(position, true) {
+                    // dropping onto a block without hitting a free input cancels the connection
+                    if let Some(block_id) = plot.get_block_at(position) {
+                        let i = plot.get_block(block_id)?.position_on_connection(position, true)?;
 
-                        block.connection(Connector::Input(i)).is_none().then(|| 
+                        plot.check_connection((origin_id, output), (block_id, i)).is_ok().then(|| 
                             Connection::new_basic(origin_id, output, block_id, i )
                         )
                     }
+                    // away from any pin the wire ends in a waypoint to continue from, the only way to route a wire around blocks
                     else {
                         Some(Connection::new(Port::Output(origin_id, output), vec![Segment::Waypoint(HashMap::new(), position, false)]))
                     }
                 }).flatten();
 
                 if let Some(connection) = connection {
-                    self.application.borrow().new_action(Action::NewConnection(plot_provider.clone(), connection));
+                    self.application.borrow().new_action(Action::NewConnection(plot_provider.clone(), connection, None));
                 }
 
                 self.drawing_area.queue_draw()
@@ -517,9 +1237,9 @@ impl CircuitViewTemplate {
             Selection::Connection(ConnectionSource::Waypoint(segment_id), _, position) => {
                 let segment = plot_provider.with_mut(|plot| {
                     plot.set_selection(Selection::None);
-                    if let Some(block_id) = plot.get_block_at(position) &&
-                        let Some(block) = plot.get_block(block_id) &&
-                        let Some(i) = block.position_on_connection(position, true) {
+                    if let Some(block_id) = plot.get_block_at(position) {
+                        let block = plot.get_block(block_id)?;
+                        let i = block.position_on_connection(position, true)?;
                         
                         block.connection(Connector::Input(i)).is_none().then_some(Segment::Block(block_id, i))
                     }   
@@ -534,8 +1254,26 @@ impl CircuitViewTemplate {
 
                 self.drawing_area.queue_draw();
             }
-            Selection::Area(_, _) => {
-                plot_provider.with_mut(|plot| plot.highlight_area());
+            Selection::Connection(ConnectionSource::Endpoint(connection_id, block_id, input), _, position) => {
+                // anything but a free input pin leaves the wire where it was
+                let target = plot_provider.with_mut(|plot| {
+                    plot.set_selection(Selection::None);
+                    let target_id = plot.get_block_at(position)?;
+                    let i = plot.get_block(target_id)?.position_on_connection(position, true)?;
+                    let origin = plot.get_connection(&connection_id)?.origin();
+                    plot.check_connection((origin.block_id(), origin.index()), (target_id, i)).is_ok().then_some((target_id, i))
+                }).flatten();
+
+                if let Some(target) = target {
+                    self.application.borrow().new_action(Action::ReanchorConnection(plot_provider.clone(), connection_id, (block_id, input), target));
+                }
+
+                self.drawing_area.queue_draw();
+            }
+            Selection::Area(start, end) => {
+                let previous = self.marquee_base.take();
+                let additive = self.shift_down.get();
+                plot_provider.with_mut(|plot| plot.finish_marquee(previous, (start, end), additive, offset.0 == 0 && offset.1 == 0));
                 self.drawing_area.queue_draw()
             }
             Selection::MouseEvent(block_id) => {
@@ -585,3 +1323,37 @@ impl WidgetImpl for CircuitViewTemplate {
 }
 
 impl BoxImpl for CircuitViewTemplate {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a project module sharing its prefix with the built-in And gate
+    fn project_with_andy() -> Project {
+        let mut project = Project::default();
+        project.add_module(Module::new(String::from("Andy"), 2, 1));
+        project
+    }
+
+    #[test]
+    fn project_modules_rank_above_builtins_of_the_same_prefix() {
+        let project = project_with_andy();
+        let ranked = QuickAdd::ranked(&project, "and", true);
+        assert_eq!(ranked[0], "Andy");
+        assert!(ranked.contains(&String::from("And")));
+    }
+
+    #[test]
+    fn builtins_are_left_out_unless_asked_for() {
+        let project = project_with_andy();
+        assert_eq!(QuickAdd::ranked(&project, "and", false), vec![String::from("Andy")]);
+        assert!(QuickAdd::ranked(&project, "xor", false).contains(&String::from("Xor")));
+    }
+
+    #[test]
+    fn recently_placed_modules_rank_first() {
+        let mut project = project_with_andy();
+        project.add_recent_module(&String::from("And"));
+        assert_eq!(QuickAdd::ranked(&project, "and", true)[0], "And");
+    }
+}
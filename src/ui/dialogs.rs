@@ -5,15 +5,16 @@ use gtk::{
     ButtonsType, Entry, MessageDialog, ResponseType, Orientation, Box, ColorButton, Label, Align, 
 };
 
-use std::future::Future;
-use crate::{simulator::Module, application::{Application, action::Action, selection::SelectionField}, renderer::{COLOR_THEME, IntoRGBA, IntoColor}};
+use std::{future::Future, rc::Rc, cell::Cell};
+use super::{circuit_panel::CircuitPanel, circuit_view::CircuitView};
+use crate::{project::Project, export::{self, ModuleFile, LibraryFile, TruthTable}, logisim::LogisimFile, simulator::{Module, PlotDescriptor, PlotProvider, BlockID, ConnectionID, breakpoint::{Breakpoint, Condition}, stimulus::Stimulus}, application::{Application, action::Action, selection::{SelectionField, Selection}, editor::{GridStyle, GRID_SIZE}}, renderer::{COLOR_THEME, Color, IntoRGBA, IntoColor, net_color, vector::Vector2}};
 
 fn create_new_module(app: Application, name: String, num_inputs: u8, num_outputs: u8) -> Result<(), String> {
     if name.is_empty() {
         return Err("Invalid name".to_string());
     }
 
-    if app.imp().project().lock().unwrap().module(&name).is_some() {
+    if app.imp().project().lock().module(&name).is_some() {
         let err = format!("Module with name \"{}\" already exists", name);
         warn!("{err}");
         return Err(err);
@@ -117,12 +118,18 @@ pub async fn basic_error(_app: Application, window: gtk::Window, message: String
 }
 
 pub async fn confirm_delete_module(app: Application, window: gtk::Window, module_name: String) {
+    let num_instances = app.imp().project().lock().find_instances(&module_name).len();
+    let text = match num_instances {
+        0 => format!("Do you really want to delete the module \"{module_name}\"?"),
+        1 => format!("Do you really want to delete the module \"{module_name}\"?\nIts placed block will be removed as well."),
+        n => format!("Do you really want to delete the module \"{module_name}\"?\nAll {n} of its placed blocks will be removed as well.")
+    };
     let dialog = MessageDialog::builder()
         .transient_for(&window)
         .modal(true)
         .buttons(ButtonsType::YesNo)
         .resizable(false)
-        .text(&format!("Do you really want to delete the module \"{module_name}\"?\nThis action is not reversable!"))
+        .text(&text)
         .title(&format!("Delete Module \"{module_name}\"?"))
         .build();
     
@@ -134,6 +141,27 @@ pub async fn confirm_delete_module(app: Application, window: gtk::Window, module
     }
 }
 
+pub async fn confirm_close_tab(_app: Application, window: gtk::Window, data: (CircuitPanel, adw::TabPage)) {
+    let (panel, page) = data;
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .buttons(ButtonsType::YesNo)
+        .resizable(false)
+        .text(&format!("\"{}\" is still being edited.\nClose it anyway and discard the unfinished edit?", page.title()))
+        .title("Close Tab?")
+        .build();
+
+    let answer = dialog.run_future().await;
+    dialog.close();
+
+    let confirm = answer == ResponseType::Yes;
+    if confirm && let Ok(circuit_view) = page.child().downcast::<CircuitView>() {
+        circuit_view.plot_provider().with_mut(|plot| plot.set_selection(Selection::None));
+    }
+    panel.finish_close_page(&page, confirm);
+}
+
 pub async fn select_border_color(app: Application, window: gtk::Window, _data: ()) {
     let dialog = MessageDialog::builder()
         .transient_for(&window)
@@ -165,6 +193,1218 @@ pub async fn select_border_color(app: Application, window: gtk::Window, _data: (
     }
 }
 
+pub async fn module_instances(app: Application, window: gtk::Window, data: (String, Vec<(PlotDescriptor, BlockID, Vector2<i32>)>)) {
+    let (module_name, instances) = data;
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .buttons(ButtonsType::Close)
+        .resizable(false)
+        .title(&format!("Instances of \"{module_name}\""))
+        .text(&match instances.len() {
+            0 => format!("\"{module_name}\" is not used anywhere."),
+            1 => format!("\"{module_name}\" is used once:"),
+            n => format!("\"{module_name}\" is used {n} times:")
+        })
+        .build();
+
+    let list = Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(6)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+
+    for (plot, block_id, position) in instances {
+        let location = match &plot {
+            PlotDescriptor::Main() => String::from("Main circuit"),
+            PlotDescriptor::Module(name) => format!("Module \"{name}\"")
+        };
+
+        let button = gtk::Button::builder()
+            .label(&format!("{location} at {}, {}", position.0, position.1))
+            .build();
+        button.connect_clicked(gtk::glib::clone!(@weak app, @weak dialog => move |_| {
+            let plot_provider = plot.to_provider(app.imp().project().clone());
+            if let Some(window) = app.imp().window().borrow().as_ref() {
+                window.panel().show_block(plot_provider, block_id);
+            }
+            dialog.close();
+        }));
+        list.append(&button);
+    }
+    dialog.content_area().append(&list);
+
+    dialog.run_future().await;
+    dialog.close();
+}
+
+// a thumbnail of every open tab, clicking one switches to it
+pub async fn tab_overview(app: Application, window: gtk::Window, panel: CircuitPanel) {
+    const THUMBNAIL_SIZE: Vector2<f64> = Vector2(200., 140.);
+
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .buttons(ButtonsType::Close)
+        .title("All Tabs")
+        .text("All Tabs")
+        .default_width(720)
+        .build();
+
+    let flow_box = gtk::FlowBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .homogeneous(true)
+        .row_spacing(12)
+        .column_spacing(12)
+        .max_children_per_line(4)
+        .build();
+
+    let wire_style = app.imp().preferences().borrow().wire_style();
+    let net_colors = app.imp().project().lock().net_colors();
+
+    let view = &panel.imp().view;
+    let pages = (0..view.n_pages()).map(|i| view.nth_page(i)).collect::<Vec<_>>();
+    for page in pages.iter() {
+        let b = Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(4)
+            .build();
+
+        let thumbnail = page.child().downcast::<CircuitView>().ok()
+            .and_then(|circuit_view| circuit_view.plot_provider().with(|plot| export::thumbnail(plot, wire_style, net_colors, true, THUMBNAIL_SIZE)))
+            .flatten();
+        match thumbnail {
+            Some(thumbnail) => b.append(&gtk::Picture::builder()
+                .paintable(&thumbnail)
+                .width_request(THUMBNAIL_SIZE.0 as i32)
+                .height_request(THUMBNAIL_SIZE.1 as i32)
+                .build()
+            ),
+            None => b.append(&Label::builder()
+                .label("Empty")
+                .css_classes(vec![String::from("dim-label")])
+                .width_request(THUMBNAIL_SIZE.0 as i32)
+                .height_request(THUMBNAIL_SIZE.1 as i32)
+                .build()
+            )
+        }
+
+        b.append(&Label::builder()
+            .label(&page.title())
+            .css_classes(if page.is_selected() { vec![String::from("heading")] } else { vec![] })
+            .ellipsize(gtk::pango::EllipsizeMode::End)
+            .build()
+        );
+        flow_box.append(&b);
+    }
+
+    flow_box.connect_child_activated(gtk::glib::clone!(@weak panel, @weak dialog => move |_, child| {
+        if let Some(page) = pages.get(child.index() as usize) {
+            panel.imp().view.set_selected_page(page);
+        }
+        dialog.close();
+    }));
+
+    let scrolled = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .min_content_height(200)
+        .max_content_height(500)
+        .propagate_natural_height(true)
+        .margin_start(12)
+        .margin_end(12)
+        .child(&flow_box)
+        .build();
+    dialog.content_area().append(&scrolled);
+
+    dialog.run_future().await;
+    dialog.close();
+}
+
+pub async fn activity_report(app: Application, window: gtk::Window, _data: ()) {
+    const MAX_ENTRIES: usize = 10;
+    let (tracking, report, depth) = {
+        let project = app.imp().project().lock();
+        let report = project.activity_report(MAX_ENTRIES).into_iter()
+            .map(|(plot, block_id, count)| {
+                let name = project.plot_by_descriptor(&plot)
+                    .and_then(|p| p.get_block(block_id))
+                    .map(|block| block.name().clone())
+                    .unwrap_or_default();
+                (plot, block_id, name, count)
+            })
+            .collect::<Vec<_>>();
+        (project.activity_tracking(), report, (project.convergence_depth(), project.max_convergence_depth()))
+    };
+
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .buttons(ButtonsType::Close)
+        .resizable(false)
+        .title("Activity Report")
+        .text(&if !tracking {
+            String::from("Activity tracking is disabled. Enable it and let the simulation run to find unstable blocks.")
+        } else if report.is_empty() {
+            String::from("No block outputs have changed yet.")
+        } else {
+            String::from("Blocks whose outputs changed most often within a single tick:")
+        })
+        .secondary_text(&format!(
+            "The last tick took {} round{} of updates to settle, the slowest tick since the simulation started took {}. Many rounds point at long feedback loops or a nearly unstable circuit.",
+            depth.0, if depth.0 == 1 { "" } else { "s" }, depth.1
+        ))
+        .build();
+
+    let list = Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(6)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+
+    for (plot, block_id, name, count) in report {
+        let button = gtk::Button::builder()
+            .label(&format!("\"{name}\" in {plot}: {count} changes"))
+            .build();
+        button.connect_clicked(gtk::glib::clone!(@weak app, @weak dialog => move |_| {
+            let plot_provider = plot.to_provider(app.imp().project().clone());
+            if let Some(window) = app.imp().window().borrow().as_ref() {
+                window.panel().show_block(plot_provider, block_id);
+            }
+            dialog.close();
+        }));
+        list.append(&button);
+    }
+    dialog.content_area().append(&list);
+
+    dialog.run_future().await;
+    dialog.close();
+}
+
+// wire counts and lengths of every circuit in the project, lengths are in grid cells
+pub async fn wire_statistics(app: Application, window: gtk::Window, _data: ()) {
+    let rows = {
+        let project = app.imp().project().lock();
+        let mut modules = project.modules().iter()
+            .filter_map(|(name, module)| Some((PlotDescriptor::Module(name.clone()), module.plot()?)))
+            .collect::<Vec<_>>();
+        modules.sort_by(|(a, _), (b, _)| a.to_string().cmp(&b.to_string()));
+
+        std::iter::once((PlotDescriptor::Main(), project.main_plot()))
+            .chain(modules)
+            .map(|(descriptor, plot)| {
+                let (count, length, busiest) = plot.wire_stats();
+                let busiest = busiest.map(|(id, inputs)| {
+                    let name = plot.get_connection(&id).and_then(|connection| connection.name().clone());
+                    (id, name, inputs)
+                });
+                (descriptor, count, length / GRID_SIZE as f64, busiest)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let (total_count, total_length) = rows.iter().fold((0, 0.), |(count, length), row| (count + row.1, length + row.2));
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .buttons(ButtonsType::Close)
+        .resizable(false)
+        .title("Wire Statistics")
+        .text(&format!("{total_count} wire{} with a total length of {total_length:.1} grid cells", if total_count == 1 { "" } else { "s" }))
+        .secondary_text("Lengths follow the bend points in straight lines. The busiest wire is the one reaching the most inputs.")
+        .build();
+
+    let grid = gtk::Grid::builder()
+        .row_spacing(6)
+        .column_spacing(12)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+
+    let heading = |label: &str| Label::builder().label(label).css_classes(vec![String::from("heading")]).halign(Align::Start).build();
+    grid.attach(&heading("Circuit"), 0, 0, 1, 1);
+    grid.attach(&heading("Wires"), 1, 0, 1, 1);
+    grid.attach(&heading("Length"), 2, 0, 1, 1);
+    grid.attach(&heading("Busiest Wire"), 3, 0, 1, 1);
+
+    for (i, (descriptor, count, length, busiest)) in rows.into_iter().enumerate() {
+        let row = i as i32 + 1;
+        let location = match &descriptor {
+            PlotDescriptor::Main() => String::from("Main circuit"),
+            PlotDescriptor::Module(name) => format!("Module \"{name}\"")
+        };
+        grid.attach(&Label::builder().label(&location).halign(Align::Start).build(), 0, row, 1, 1);
+        grid.attach(&Label::builder().label(&count.to_string()).halign(Align::End).build(), 1, row, 1, 1);
+        grid.attach(&Label::builder().label(&format!("{length:.1}")).halign(Align::End).build(), 2, row, 1, 1);
+
+        match busiest {
+            Some((connection_id, name, inputs)) => {
+                let button = gtk::Button::builder()
+                    .label(&format!("{}, {inputs} input{}", name.as_deref().unwrap_or("unnamed"), if inputs == 1 { "" } else { "s" }))
+                    .css_classes(vec![String::from("flat")])
+                    .halign(Align::Start)
+                    .build();
+                button.connect_clicked(gtk::glib::clone!(@weak app, @weak dialog => move |_| {
+                    let plot_provider = descriptor.to_provider(app.imp().project().clone());
+                    if let Some(window) = app.imp().window().borrow().as_ref() {
+                        window.panel().show_connection(plot_provider, connection_id);
+                    }
+                    dialog.close();
+                }));
+                grid.attach(&button, 3, row, 1, 1);
+            }
+            None => grid.attach(&Label::builder().label("-").halign(Align::Start).build(), 3, row, 1, 1)
+        }
+    }
+    dialog.content_area().append(&grid);
+
+    dialog.run_future().await;
+    dialog.close();
+}
+
+pub async fn replace_module(app: Application, window: gtk::Window, module_name: String) {
+    let mut candidates = {
+        let project = app.imp().project().lock();
+        project.modules().keys()
+            .filter(|name| project.check_replace_module(&module_name, name).is_ok())
+            .cloned()
+            .collect::<Vec<String>>()
+    };
+    candidates.sort();
+
+    if candidates.is_empty() {
+        basic_error(app, window, format!("There is no module that is pin-compatible with \"{module_name}\".")).await;
+        return;
+    }
+
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .buttons(ButtonsType::OkCancel)
+        .resizable(false)
+        .title(&format!("Replace \"{module_name}\""))
+        .text(&format!("Replace every instance of \"{module_name}\" with:"))
+        .build();
+
+    let drop_down = gtk::DropDown::from_strings(&candidates.iter().map(|name| name.as_str()).collect::<Vec<_>>());
+    drop_down.set_margin_start(12);
+    drop_down.set_margin_end(12);
+    dialog.content_area().append(&drop_down);
+
+    let answer = dialog.run_future().await;
+    dialog.close();
+
+    if answer == ResponseType::Ok && let Some(replacement) = candidates.get(drop_down.selected() as usize) {
+        let result = app.imp().project().lock().check_replace_module(&module_name, replacement);
+        match result {
+            Ok(()) => app.new_action(Action::ReplaceModule(app.imp().project().clone(), module_name, replacement.clone(), vec![])),
+            Err(err) => basic_error(app, window, err).await
+        }
+    }
+}
+
+pub async fn name_pins(app: Application, window: gtk::Window, module_name: String) {
+    let Some((inputs, outputs)) = app.imp().project().lock().module(&module_name).map(|module| module.pin_names()) else {
+        return;
+    };
+
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .buttons(ButtonsType::OkCancel)
+        .resizable(false)
+        .title(&format!("Name Pins of \"{module_name}\""))
+        .text(&format!("Name Pins of \"{module_name}\""))
+        .secondary_text("Unnamed pins are shown by their number.")
+        .build();
+
+    let grid = gtk::Grid::builder()
+        .row_spacing(6)
+        .column_spacing(12)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+
+    let mut row = 0;
+    let mut entries = |heading: &str, names: &Vec<Option<String>>| names.iter().enumerate().map(|(i, name)| {
+        let entry = Entry::builder()
+            .text(name.as_deref().unwrap_or_default())
+            .placeholder_text("Unnamed")
+            .hexpand(true)
+            .build();
+        grid.attach(&Label::builder().label(&format!("{heading} {}", i + 1)).halign(Align::Start).build(), 0, row, 1, 1);
+        grid.attach(&entry, 1, row, 1, 1);
+        row += 1;
+        entry
+    }).collect::<Vec<_>>();
+    let input_entries = entries("Input", &inputs);
+    let output_entries = entries("Output", &outputs);
+    dialog.content_area().append(&grid);
+
+    let answer = dialog.run_future().await;
+    dialog.close();
+
+    if answer == ResponseType::Ok {
+        let names = |entries: Vec<Entry>| entries.into_iter()
+            .map(|entry| {
+                let name = entry.buffer().text().trim().to_string();
+                (!name.is_empty()).then_some(name)
+            })
+            .collect::<Vec<_>>();
+        let pin_names = (names(input_entries), names(output_entries));
+        if pin_names != (inputs, outputs) {
+            app.new_action(Action::NamePins(app.imp().project().clone(), module_name, pin_names, Default::default()));
+        }
+    }
+}
+
+pub async fn change_block_module(app: Application, window: gtk::Window, data: (PlotProvider, BlockID)) {
+    let (plot_provider, block_id) = data;
+    let descriptor = PlotDescriptor::from(&plot_provider);
+    let (current, mut candidates) = {
+        let project = app.imp().project().lock();
+        let current = project.plot_by_descriptor(&descriptor)
+            .and_then(|plot| plot.get_block(block_id))
+            .map(|block| block.module_id().clone());
+        let candidates = project.modules().keys()
+            .filter(|name| project.check_change_block_module(&descriptor, block_id, name).is_ok())
+            .cloned()
+            .collect::<Vec<String>>();
+        (current, candidates)
+    };
+    candidates.sort();
+
+    let Some(current) = current else {
+        return;
+    };
+
+    if candidates.is_empty() {
+        basic_error(app, window, format!("There is no module that is pin-compatible with \"{current}\".")).await;
+        return;
+    }
+
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .buttons(ButtonsType::OkCancel)
+        .resizable(false)
+        .title("Change Module")
+        .text(&format!("Change this \"{current}\" block into:"))
+        .build();
+
+    let drop_down = gtk::DropDown::from_strings(&candidates.iter().map(|name| name.as_str()).collect::<Vec<_>>());
+    drop_down.set_margin_start(12);
+    drop_down.set_margin_end(12);
+    dialog.content_area().append(&drop_down);
+
+    let answer = dialog.run_future().await;
+    dialog.close();
+
+    if answer == ResponseType::Ok && let Some(replacement) = candidates.get(drop_down.selected() as usize) {
+        let result = app.imp().project().lock().check_change_block_module(&descriptor, block_id, replacement);
+        match result {
+            Ok(()) => app.new_action(Action::ChangeBlockModule(plot_provider, block_id, replacement.clone(), None)),
+            Err(err) => basic_error(app, window, err).await
+        }
+    }
+}
+
+pub async fn large_truth_table(app: Application, window: gtk::Window, data: (String, u128)) {
+    let (module_name, num_rows) = data;
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .resizable(false)
+        .title("Large Truth Table")
+        .text(&format!(
+            "The truth table of \"{module_name}\" has {num_rows} rows.\nExport only the first {} rows instead?",
+            Project::MAX_TRUTH_TABLE_ROWS
+        ))
+        .build();
+    dialog.add_button("Abort", ResponseType::Cancel);
+    dialog.add_button("Cap Rows", ResponseType::Accept);
+
+    let answer = dialog.run_future().await;
+    dialog.close();
+
+    if answer == ResponseType::Accept {
+        app.export_truth_table(module_name, Some(Project::MAX_TRUTH_TABLE_ROWS));
+    }
+}
+
+pub async fn large_circuit(_app: Application, window: gtk::Window, data: (CircuitPanel, String, PlotProvider, usize)) {
+    let (panel, title, plot_provider, complexity) = data;
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .resizable(false)
+        .title("Large Circuit")
+        .text(&format!(
+            "\"{title}\" has {complexity} blocks and connections and may make the editor slow to respond.\nOpen it in lite mode, without signal colors and with straight wires?"
+        ))
+        .build();
+    dialog.add_button("Open Normally", ResponseType::Reject);
+    dialog.add_button("Open in Lite Mode", ResponseType::Accept);
+    dialog.set_default_response(Some(ResponseType::Accept));
+
+    let answer = dialog.run_future().await;
+    dialog.close();
+
+    panel.open_view(&title, plot_provider, answer != ResponseType::Reject);
+}
+
+pub async fn truth_table(_app: Application, window: gtk::Window, data: (String, TruthTable)) {
+    const MIN_FONT_SIZE: u32 = 6;
+    const MAX_FONT_SIZE: u32 = 24;
+
+    let (module_name, table) = data;
+    let table = Rc::new(table);
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .buttons(ButtonsType::Close)
+        .resizable(true)
+        .title(&format!("Truth Table of \"{module_name}\""))
+        .text(&format!("\"{module_name}\" has {} rows:", table.num_rows()))
+        .build();
+
+    // the list only holds row numbers, the cells are only created for the rows scrolled into view
+    let rows = gtk::StringList::new(&[]);
+    (0..table.num_rows()).for_each(|row| rows.append(&row.to_string()));
+    let row_of = |item: &gtk::glib::Object| item.downcast_ref::<gtk::StringObject>()
+        .and_then(|item| item.string().parse::<usize>().ok());
+
+    // `None` shows every row, `Some((output, value))` only those where that output has the value
+    let output_filter = Rc::new(Cell::new(None::<(u8, bool)>));
+    let filter = gtk::CustomFilter::new(gtk::glib::clone!(@strong table, @strong output_filter => move |item|
+        match (output_filter.get(), row_of(item)) {
+            (Some((output, value)), Some(row)) => table.cell(row, table.num_inputs() + output) == value,
+            _ => true
+        }
+    ));
+
+    let column_view = gtk::ColumnView::builder()
+        .show_column_separators(true)
+        .show_row_separators(true)
+        .reorderable(false)
+        .build();
+    let filtered = gtk::FilterListModel::new(Some(&rows), Some(&filter));
+    let sorted = gtk::SortListModel::new(Some(&filtered), column_view.sorter().as_ref());
+    column_view.set_model(Some(&gtk::NoSelection::new(Some(&sorted))));
+
+    for column in 0..table.num_inputs() + table.num_outputs() {
+        let factory = gtk::SignalListItemFactory::new();
+        factory.connect_setup(|_, item| item.set_child(Some(&Label::new(None))));
+        factory.connect_bind(gtk::glib::clone!(@strong table => move |_, item| {
+            let label = item.child().and_then(|child| child.downcast::<Label>().ok());
+            let row = item.item().and_then(|item| row_of(&item));
+            if let Some(label) = label && let Some(row) = row {
+                label.set_label(if table.cell(row, column) { "1" } else { "0" });
+            }
+        }));
+
+        let sorter = gtk::CustomSorter::new(gtk::glib::clone!(@strong table => move |a, b| {
+            let cell = |item: &gtk::glib::Object| row_of(item).map(|row| table.cell(row, column));
+            cell(a).cmp(&cell(b)).into()
+        }));
+
+        let view_column = gtk::ColumnViewColumn::new(Some(&table.column_name(column)), Some(&factory));
+        view_column.set_sorter(Some(&sorter));
+        view_column.set_expand(true);
+        column_view.append_column(&view_column);
+    }
+
+    let filter_options = std::iter::once(String::from("All rows"))
+        .chain((0..table.num_outputs()).flat_map(|output| [0, 1].map(|value| format!("out{output} = {value}"))))
+        .collect::<Vec<_>>();
+    let filter_dropdown = gtk::DropDown::from_strings(&filter_options.iter().map(String::as_str).collect::<Vec<_>>());
+    filter_dropdown.connect_selected_notify(gtk::glib::clone!(@strong output_filter, @weak filter => move |dropdown| {
+        let selected = dropdown.selected();
+        output_filter.set((selected > 0).then(|| (((selected - 1) / 2) as u8, (selected - 1) % 2 == 1)));
+        filter.changed(gtk::FilterChange::Different);
+    }));
+
+    let font_size = Rc::new(Cell::new(11));
+    let css = gtk::CssProvider::new();
+    let apply_font_size = gtk::glib::clone!(@strong font_size, @strong css => move |size: u32| {
+        font_size.set(size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE));
+        css.load_from_data(format!("columnview {{ font-size: {}pt; }}", font_size.get()).as_bytes());
+    });
+    apply_font_size(font_size.get());
+    column_view.style_context().add_provider(&css, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+
+    let zoom_out = gtk::Button::from_icon_name("zoom-out-symbolic");
+    zoom_out.set_tooltip_text(Some("Zoom out"));
+    zoom_out.connect_clicked(gtk::glib::clone!(@strong font_size, @strong apply_font_size => move |_| apply_font_size(font_size.get().saturating_sub(1))));
+    let zoom_in = gtk::Button::from_icon_name("zoom-in-symbolic");
+    zoom_in.set_tooltip_text(Some("Zoom in"));
+    zoom_in.connect_clicked(gtk::glib::clone!(@strong font_size, @strong apply_font_size => move |_| apply_font_size(font_size.get() + 1)));
+
+    let toolbar = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    toolbar.append(&Label::new(Some("Show:")));
+    toolbar.append(&filter_dropdown);
+    toolbar.append(&Box::builder().hexpand(true).build());
+    toolbar.append(&zoom_out);
+    toolbar.append(&zoom_in);
+
+    let scrolled = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Automatic)
+        .min_content_height(360)
+        .min_content_width(320)
+        .vexpand(true)
+        .child(&column_view)
+        .build();
+
+    let content = Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(6)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+    content.append(&toolbar);
+    content.append(&scrolled);
+    dialog.content_area().append(&content);
+
+    dialog.run_future().await;
+    dialog.close();
+}
+
+pub async fn name_net(app: Application, window: gtk::Window, data: (PlotProvider, ConnectionID, Option<String>, Option<Color>)) {
+    let (plot_provider, connection_id, name, color) = data;
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .resizable(false)
+        .title("Name Net")
+        .text("Name Net")
+        .buttons(ButtonsType::OkCancel)
+        .build();
+
+    let name_input = Entry::builder()
+        .text(name.as_deref().unwrap_or_default())
+        .placeholder_text("Unnamed")
+        .hexpand(true)
+        .build();
+
+    let color_enabled = gtk::CheckButton::builder()
+        .label("Custom color:")
+        .active(color.is_some())
+        .build();
+    let color_button = ColorButton::with_rgba(&color.or_else(|| name.as_deref().map(net_color)).unwrap_or(unsafe { COLOR_THEME.accent_fg_color }).into_rgba());
+    color_button.set_sensitive(color.is_some());
+    color_enabled.connect_toggled(gtk::glib::clone!(@weak color_button => move |button| color_button.set_sensitive(button.is_active())));
+
+    let color_box = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    color_box.append(&color_enabled);
+    color_box.append(&color_button);
+
+    let content = dialog.content_area();
+    content.set_orientation(Orientation::Vertical);
+    content.set_spacing(6);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+    content.append(&name_input);
+    content.append(&color_box);
+
+    let answer = dialog.run_future().await;
+    dialog.close();
+
+    if answer == ResponseType::Ok {
+        let name = name_input.buffer().text().trim().to_string();
+        let name = (!name.is_empty()).then_some(name);
+        let color = color_enabled.is_active().then(|| color_button.rgba().into_color());
+        app.new_action(Action::NameNet(plot_provider, connection_id, (name, color), (None, None)));
+    }
+}
+
+pub async fn canvas_appearance(app: Application, window: gtk::Window, data: (PlotProvider, (Option<Color>, GridStyle))) {
+    let (plot_provider, (background, grid_style)) = data;
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .resizable(false)
+        .title("Canvas Appearance")
+        .text("Canvas Appearance")
+        .secondary_text("Grid style applies while the editor grid is shown.")
+        .buttons(ButtonsType::OkCancel)
+        .build();
+
+    let color_enabled = gtk::CheckButton::builder()
+        .label("Custom background:")
+        .active(background.is_some())
+        .build();
+    let color_button = ColorButton::with_rgba(&background.unwrap_or(unsafe { COLOR_THEME.bg_color }).into_rgba());
+    color_button.set_sensitive(background.is_some());
+    color_enabled.connect_toggled(gtk::glib::clone!(@weak color_button => move |button| color_button.set_sensitive(button.is_active())));
+
+    let color_box = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    color_box.append(&color_enabled);
+    color_box.append(&color_button);
+
+    const GRID_STYLES: [(GridStyle, &str); 2] = [(GridStyle::Dots, "Dots"), (GridStyle::Lines, "Lines")];
+    let grid_input = gtk::DropDown::from_strings(&GRID_STYLES.map(|(_, label)| label));
+    grid_input.set_selected(GRID_STYLES.iter().position(|(style, _)| *style == grid_style).unwrap_or_default() as u32);
+
+    let grid_box = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    grid_box.append(&Label::new(Some("Grid:")));
+    grid_box.append(&grid_input);
+
+    let content = dialog.content_area();
+    content.set_orientation(Orientation::Vertical);
+    content.set_spacing(6);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+    content.append(&color_box);
+    content.append(&grid_box);
+
+    let answer = dialog.run_future().await;
+    dialog.close();
+
+    if answer == ResponseType::Ok {
+        let new_background = color_enabled.is_active().then(|| color_button.rgba().into_color());
+        let new_grid_style = GRID_STYLES.get(grid_input.selected() as usize).map_or(grid_style, |(style, _)| *style);
+        if new_background != background || new_grid_style != grid_style {
+            app.new_action(Action::SetAppearance(plot_provider, (new_background, new_grid_style), (background, grid_style)));
+        }
+    }
+}
+
+pub async fn project_properties(app: Application, window: gtk::Window, _data: ()) {
+    let metadata = app.imp().project().lock().metadata().clone();
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .resizable(false)
+        .title("Project Properties")
+        .text("Project Properties")
+        .buttons(ButtonsType::OkCancel)
+        .build();
+
+    let author_input = Entry::builder()
+        .text(metadata.get("author").map(String::as_str).unwrap_or_default())
+        .placeholder_text("Author")
+        .hexpand(true)
+        .build();
+
+    let notes_input = gtk::TextView::builder()
+        .wrap_mode(gtk::WrapMode::WordChar)
+        .height_request(100)
+        .build();
+    notes_input.buffer().set_text(metadata.get("notes").map(String::as_str).unwrap_or_default());
+    let notes_frame = gtk::Frame::builder()
+        .child(&notes_input)
+        .build();
+
+    let content = dialog.content_area();
+    content.set_orientation(Orientation::Vertical);
+    content.set_spacing(6);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+    content.append(&author_input);
+    content.append(&Label::builder().label("Notes:").halign(Align::Start).build());
+    content.append(&notes_frame);
+    for (key, label) in [("created", "Created"), ("modified", "Last saved")] {
+        let value = metadata.get(key).map(String::as_str).unwrap_or("never");
+        content.append(&Label::builder()
+            .label(&format!("{label}: {value}"))
+            .halign(Align::Start)
+            .css_classes(vec![String::from("dim-label")])
+            .build()
+        );
+    }
+
+    let answer = dialog.run_future().await;
+    dialog.close();
+
+    if answer == ResponseType::Ok {
+        let buffer = notes_input.buffer();
+        let notes = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false).trim().to_string();
+        let author = author_input.buffer().text().trim().to_string();
+        if metadata.get("author").map_or(author.is_empty(), |old| old == &author) && metadata.get("notes").map_or(notes.is_empty(), |old| old == &notes) {
+            return;
+        }
+
+        let mut project = app.imp().project().lock();
+        project.set_metadata("author", author);
+        project.set_metadata("notes", notes);
+        drop(project);
+        app.imp().action_stack().borrow_mut().set_dirty(true);
+    }
+}
+
+pub async fn stimulus(app: Application, window: gtk::Window, _data: ()) {
+    let (inputs, existing) = {
+        let project = app.imp().project().lock();
+        let inputs = Stimulus::input_blocks(project.main_plot()).iter()
+            .map(|block| (block.id(), format!("{} at ({}, {})", block.name(), block.position().0, block.position().1)))
+            .collect::<Vec<_>>();
+        (inputs, project.stimulus().cloned())
+    };
+
+    if inputs.is_empty() {
+        return basic_error(app, window, String::from("The main circuit has no switches or buttons to drive.")).await;
+    }
+
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .resizable(false)
+        .title("Input Stimulus")
+        .text("Input Stimulus")
+        .secondary_text("Enter one step per line, with a 0 or 1 for each input in the order listed below.")
+        .buttons(ButtonsType::OkCancel)
+        .build();
+
+    let columns = inputs.iter()
+        .enumerate()
+        .map(|(i, (_, label))| format!("{}. {label}", i + 1))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // only keep the previous steps if they still drive the same inputs
+    let same_inputs = existing.as_ref().map_or(false, |stimulus| stimulus.inputs().iter().eq(inputs.iter().map(|(id, _)| id)));
+    let steps_input = gtk::TextView::builder()
+        .monospace(true)
+        .height_request(150)
+        .build();
+    if let Some(stimulus) = existing.as_ref().filter(|_| same_inputs) {
+        steps_input.buffer().set_text(&stimulus.steps_to_text());
+    }
+    let steps_frame = gtk::Frame::builder()
+        .child(&steps_input)
+        .build();
+
+    let period_adjustment = gtk::Adjustment::new(existing.as_ref().map_or(Stimulus::DEFAULT_PERIOD, |stimulus| stimulus.period()) as f64, 1.0, 10000.0, 1.0, 10.0, 0.0);
+    let period_chooser = gtk::SpinButton::builder()
+        .climb_rate(1.0)
+        .adjustment(&period_adjustment)
+        .numeric(true)
+        .build();
+    let period_box = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    period_box.append(&Label::new(Some("Ticks per step:")));
+    period_box.append(&period_chooser);
+
+    let looping = gtk::CheckButton::builder()
+        .label("Loop")
+        .active(existing.as_ref().map_or(false, |stimulus| stimulus.looping()))
+        .build();
+    let saved = gtk::CheckButton::builder()
+        .label("Save with project")
+        .active(existing.as_ref().map_or(false, |stimulus| stimulus.saved()))
+        .build();
+
+    let content = dialog.content_area();
+    content.set_orientation(Orientation::Vertical);
+    content.set_spacing(6);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+    content.append(&Label::builder().label(&columns).halign(Align::Start).build());
+    content.append(&steps_frame);
+    content.append(&period_box);
+    content.append(&looping);
+    content.append(&saved);
+
+    let answer = dialog.run_future().await;
+    dialog.close();
+
+    if answer == ResponseType::Ok {
+        let buffer = steps_input.buffer();
+        let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+        match Stimulus::parse_steps(&text, inputs.len()) {
+            Ok(steps) => {
+                let stimulus = (!steps.is_empty()).then(|| Stimulus::new(
+                    inputs.into_iter().map(|(id, _)| id).collect(),
+                    steps,
+                    period_chooser.value_as_int() as u64,
+                    looping.is_active(),
+                    saved.is_active()
+                ));
+                app.imp().project().lock().set_stimulus(stimulus);
+                app.sync_project_gactions();
+                if existing.map_or(false, |stimulus| stimulus.saved()) || saved.is_active() {
+                    app.imp().action_stack().borrow_mut().set_dirty(true);
+                }
+            }
+            Err(err) => basic_error(app, window, err).await
+        }
+    }
+}
+
+pub async fn add_breakpoint(app: Application, window: gtk::Window, data: (PlotProvider, ConnectionID)) {
+    let (plot_provider, connection_id) = data;
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .resizable(false)
+        .title("Add Breakpoint")
+        .text("Pause the simulation when this net:")
+        .buttons(ButtonsType::OkCancel)
+        .build();
+
+    let drop_down = gtk::DropDown::from_strings(&Condition::ALL.iter().map(|condition| condition.label()).collect::<Vec<_>>());
+    drop_down.set_margin_start(12);
+    drop_down.set_margin_end(12);
+    dialog.content_area().append(&drop_down);
+
+    let answer = dialog.run_future().await;
+    dialog.close();
+
+    if answer == ResponseType::Ok && let Some(condition) = Condition::ALL.get(drop_down.selected() as usize) {
+        let breakpoint = Breakpoint::new(PlotDescriptor::from(&plot_provider), connection_id, *condition);
+        app.imp().project().lock().add_breakpoint(breakpoint);
+    }
+}
+
+pub async fn breakpoints(app: Application, window: gtk::Window, _data: ()) {
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .resizable(false)
+        .title("Breakpoints")
+        .text("Breakpoints")
+        .buttons(ButtonsType::Close)
+        .build();
+
+    let list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .build();
+    list.add_css_class("boxed-list");
+    list.set_placeholder(Some(&Label::new(Some("No breakpoints set.\nRight-click a wire to add one."))));
+
+    let (breakpoints, descriptions) = {
+        let project = app.imp().project().lock();
+        let breakpoints = project.breakpoints().clone();
+        let descriptions = breakpoints.iter().map(|breakpoint| breakpoint.describe(&project)).collect::<Vec<_>>();
+        (breakpoints, descriptions)
+    };
+
+    for (breakpoint, description) in breakpoints.into_iter().zip(descriptions) {
+        let row = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(6)
+            .margin_start(6)
+            .margin_end(6)
+            .build();
+        row.append(&Label::builder().label(&description).hexpand(true).halign(Align::Start).build());
+
+        let remove_button = gtk::Button::from_icon_name("user-trash-symbolic");
+        remove_button.set_tooltip_text(Some("Remove Breakpoint"));
+        remove_button.add_css_class("flat");
+        remove_button.connect_clicked(gtk::glib::clone!(@weak app, @weak list, @weak row => move |_| {
+            app.imp().project().lock().remove_breakpoint(&breakpoint);
+            if let Some(list_row) = row.parent() {
+                list.remove(&list_row);
+            }
+        }));
+        row.append(&remove_button);
+        list.append(&row);
+    }
+
+    let content = dialog.content_area();
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+    content.append(&list);
+
+    dialog.run_future().await;
+    dialog.close();
+}
+
+pub async fn snapshots(app: Application, window: gtk::Window, _data: ()) {
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .resizable(false)
+        .title("Snapshots")
+        .text("Snapshots")
+        .secondary_text("Capture the state of every block to return to it later; the circuits themselves are left as they are.")
+        .buttons(ButtonsType::Close)
+        .build();
+
+    let name_input = Entry::builder()
+        .placeholder_text("Snapshot name")
+        .hexpand(true)
+        .build();
+    let capture_button = gtk::Button::with_label("Capture");
+    let capture_row = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .margin_bottom(6)
+        .build();
+    capture_row.append(&name_input);
+    capture_row.append(&capture_button);
+
+    let list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .build();
+    list.add_css_class("boxed-list");
+    list.set_placeholder(Some(&Label::new(Some("No snapshots captured."))));
+    fill_snapshot_list(&app, &list);
+
+    capture_button.connect_clicked(gtk::glib::clone!(@weak app, @weak list, @weak name_input => move |_| {
+        {
+            let mut project = app.imp().project().lock();
+            let name = match name_input.text().trim() {
+                "" => format!("Snapshot {}", project.snapshots().len() + 1),
+                name => name.to_string()
+            };
+            project.capture_snapshot(name);
+        }
+        name_input.set_text("");
+        fill_snapshot_list(&app, &list);
+    }));
+
+    let content = dialog.content_area();
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+    content.append(&capture_row);
+    content.append(&list);
+
+    dialog.run_future().await;
+    dialog.close();
+}
+
+// rebuilds the rows from scratch, since removing a snapshot moves the ones after it
+fn fill_snapshot_list(app: &Application, list: &gtk::ListBox) {
+    while let Some(row) = list.row_at_index(0) {
+        list.remove(&row);
+    }
+
+    let snapshots = app.imp().project().lock().snapshots().iter()
+        .map(|snapshot| (snapshot.name().clone(), snapshot.ticks()))
+        .collect::<Vec<_>>();
+
+    for (index, (name, ticks)) in snapshots.into_iter().enumerate() {
+        let row = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(6)
+            .margin_start(6)
+            .margin_end(6)
+            .build();
+        row.append(&Label::builder().label(&format!("{name} (tick {ticks})")).hexpand(true).halign(Align::Start).build());
+
+        let restore_button = gtk::Button::from_icon_name("edit-undo-symbolic");
+        restore_button.set_tooltip_text(Some("Restore Snapshot"));
+        restore_button.add_css_class("flat");
+        restore_button.connect_clicked(gtk::glib::clone!(@weak app => move |_| {
+            let result = app.imp().project().lock().restore_snapshot(index);
+            app.imp().rerender_editor();
+            if let Some(window) = app.imp().window().borrow().as_ref() {
+                match result {
+                    Ok(()) => window.panel().update_watches(),
+                    Err(err) => window.panel().push_error(err)
+                }
+            }
+        }));
+        row.append(&restore_button);
+
+        let remove_button = gtk::Button::from_icon_name("user-trash-symbolic");
+        remove_button.set_tooltip_text(Some("Remove Snapshot"));
+        remove_button.add_css_class("flat");
+        remove_button.connect_clicked(gtk::glib::clone!(@weak app, @weak list => move |_| {
+            app.imp().project().lock().remove_snapshot(index);
+            fill_snapshot_list(&app, &list);
+        }));
+        row.append(&remove_button);
+        list.append(&row);
+    }
+}
+
+pub async fn export_library(app: Application, window: gtk::Window, _data: ()) {
+    let mut names = app.imp().project().lock().modules().values()
+        .filter(|module| !module.builtin())
+        .map(|module| module.name().clone())
+        .collect::<Vec<_>>();
+    names.sort();
+
+    if names.is_empty() {
+        basic_error(app, window, String::from("This project does not contain any custom modules.")).await;
+        return;
+    }
+
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .buttons(ButtonsType::OkCancel)
+        .resizable(false)
+        .title("Export Library")
+        .text("Select the modules to put into the library; their dependencies are included as well.")
+        .build();
+
+    let name_entry = Entry::builder()
+        .placeholder_text("Library name")
+        .build();
+    let check_buttons = names.iter().map(|name| gtk::CheckButton::with_label(name)).collect::<Vec<_>>();
+    let content = dialog.content_area();
+    content.set_orientation(Orientation::Vertical);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+    content.append(&name_entry);
+    check_buttons.iter().for_each(|button| content.append(button));
+
+    let answer = dialog.run_future().await;
+    dialog.close();
+
+    let selected = names.into_iter()
+        .zip(check_buttons)
+        .filter(|(_, button)| button.is_active())
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    if answer != ResponseType::Ok || selected.is_empty() {
+        return;
+    }
+
+    let name = match name_entry.buffer().text().trim() {
+        "" => String::from("library"),
+        name => name.to_string()
+    };
+    app.export_library(name, selected);
+}
+
+pub async fn import_project_modules(app: Application, window: gtk::Window, project: Project) {
+    let mut names = project.modules().values()
+        .filter(|module| !module.builtin())
+        .map(|module| module.name().clone())
+        .collect::<Vec<_>>();
+    names.sort();
+
+    if names.is_empty() {
+        basic_error(app, window, String::from("The selected project does not contain any custom modules.")).await;
+        return;
+    }
+
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .buttons(ButtonsType::OkCancel)
+        .resizable(false)
+        .title("Import Modules")
+        .text("Select the modules to import; their dependencies are imported as well.")
+        .build();
+
+    let check_buttons = names.iter().map(|name| gtk::CheckButton::with_label(name)).collect::<Vec<_>>();
+    let content = dialog.content_area();
+    content.set_orientation(Orientation::Vertical);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+    check_buttons.iter().for_each(|button| content.append(button));
+
+    let answer = dialog.run_future().await;
+    dialog.close();
+
+    let selected = names.into_iter()
+        .zip(check_buttons)
+        .filter(|(_, button)| button.is_active())
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    if answer != ResponseType::Ok || selected.is_empty() {
+        return;
+    }
+
+    merge_module_file(app, window, ModuleFile::from_project(&project, &selected)).await;
+}
+
+pub async fn import_library(app: Application, window: gtk::Window, library: LibraryFile) {
+    merge_module_file(app, window, library.into_module_file()).await;
+}
+
+pub async fn import_logisim(app: Application, window: gtk::Window, circuit: LogisimFile) {
+    let (mod_file, skipped) = circuit.into_parts();
+    merge_module_file(app.clone(), window, mod_file).await;
+
+    if !skipped.is_empty() && let Some(window) = app.imp().window().borrow().as_ref() {
+        window.panel().push_error(format!(
+            "{} Logisim component{} could not be imported and {} left out:\n{}",
+            skipped.len(), if skipped.len() == 1 { "" } else { "s" }, if skipped.len() == 1 { "was" } else { "were" }, skipped.join("\n")
+        ));
+    }
+}
+
+// adds the bundled modules to the project, asking for new names where they clash with existing ones
+async fn merge_module_file(app: Application, window: gtk::Window, mut mod_file: ModuleFile) {
+    let conflicting = mod_file.check_compat(&app.imp().project().lock());
+    if !conflicting.is_empty() {
+        match resolve_module_conflicts(&app, &window, &mod_file, &conflicting).await {
+            Some(Ok(renames)) => renames.into_iter().for_each(|(from, to)| mod_file.rename_module(&from, to)),
+            Some(Err(err)) => {
+                basic_error(app, window, err).await;
+                return;
+            }
+            None => return
+        }
+    }
+
+    if let Err(err) = mod_file.merge(&app) {
+        basic_error(app, window, err).await;
+    }
+}
+
+// asks for a new name for every module that already exists in the current project
+async fn resolve_module_conflicts(app: &Application, window: &gtk::Window, mod_file: &ModuleFile, conflicting: &Vec<String>) -> Option<Result<Vec<(String, String)>, String>> {
+    let dialog = MessageDialog::builder()
+        .transient_for(window)
+        .modal(true)
+        .buttons(ButtonsType::OkCancel)
+        .resizable(false)
+        .title("Module Name Conflicts")
+        .text("These modules already exist in this project. Choose new names for the imported ones:")
+        .build();
+
+    let grid = gtk::Grid::builder()
+        .row_spacing(6)
+        .column_spacing(12)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+
+    let entries = conflicting.iter().enumerate().map(|(i, name)| {
+        let entry = Entry::builder()
+            .text(&format!("{name} (imported)"))
+            .hexpand(true)
+            .build();
+        grid.attach(&Label::builder().label(name).halign(Align::Start).build(), 0, i as i32, 1, 1);
+        grid.attach(&entry, 1, i as i32, 1, 1);
+        entry
+    }).collect::<Vec<_>>();
+    dialog.content_area().append(&grid);
+
+    let answer = dialog.run_future().await;
+    dialog.close();
+    if answer != ResponseType::Ok {
+        return None;
+    }
+
+    let project = app.imp().project().lock();
+    let mut renames: Vec<(String, String)> = Vec::new();
+    for (name, entry) in conflicting.iter().zip(entries) {
+        let new_name = entry.buffer().text().trim().to_string();
+        if new_name.is_empty() {
+            return Some(Err(format!("The new name for \"{name}\" must not be empty.")));
+        }
+        if project.module(&new_name).is_some() || mod_file.module_names().contains(&&new_name) || renames.iter().any(|(_, other)| other == &new_name) {
+            return Some(Err(format!("A module named \"{new_name}\" already exists.")));
+        }
+        renames.push((name.clone(), new_name));
+    }
+
+    Some(Ok(renames))
+}
+
 pub fn run<F, T>(application: Application, window: gtk::Window, data: T, dialog: fn(Application, gtk::Window, T) -> F) 
 where
     F: Future<Output = ()> + 'static,
@@ -30,7 +30,8 @@ impl MainWindow {
         let panel = &self.imp().circuit_panel;
         let module_list = &self.imp().module_list;
         if !module.builtin() {
-            panel.new_tab(module.name(), PlotProvider::Module(app.imp().project().clone(), module.name().clone()));
+            let complexity = module.plot().map(|plot| plot.complexity()).unwrap_or_default();
+            panel.new_tab(module.name(), PlotProvider::Module(app.imp().project().clone(), module.name().clone()), complexity);
         }
         module_list.add_module_to_ui(app, module);
     }
@@ -56,12 +57,19 @@ impl MainWindow {
         self.set_subtitle(&app.imp().file_name());
         self.set_icon_name(Some(config::APP_ICON_NAME));
         
-        let panel = &self.imp().circuit_panel;
-        panel.new_tab("Main Circuit", PlotProvider::Main(app.imp().project().clone()));
-
         let project = app.imp().project();
-        let project = project.lock().unwrap();
-        project.modules().iter().filter(|(_, module)| !module.hidden()).for_each(|(_, module)| self.add_module_to_ui(app, module));
+        let project = project.lock();
+
+        let panel = &self.imp().circuit_panel;
+        panel.new_tab("Main Circuit", PlotProvider::Main(app.imp().project().clone()), project.main_plot().complexity());
+
+        // restore the saved tab order, unknown modules get appended alphabetically
+        let mut modules = project.modules().values().filter(|module| !module.hidden()).collect::<Vec<_>>();
+        modules.sort_by_key(|module| (
+            project.tab_order().iter().position(|name| name == module.name()).unwrap_or(usize::MAX),
+            module.name()
+        ));
+        modules.into_iter().for_each(|module| self.add_module_to_ui(app, module));
 
         self.connect_close_request(glib::clone!(@weak app => @default-panic, move |_| {
                 app.quit();
@@ -1,6 +1,6 @@
 use gtk::{prelude::*, subclass::prelude::*, glib, gdk, gio};
 
-use crate::{application::{Application, selection::*}, simulator::*, renderer::vector::Vector2};
+use crate::{application::{Application, selection::*, editor::WireStyle}, simulator::*, renderer::vector::Vector2, project::Project, export};
 
 macro_rules! add_menu_item {
     ($model: expr, $name: expr, $action: expr, $value: expr) => {
@@ -59,6 +59,10 @@ impl ModuleList {
         self.imp().remove_module_from_ui(module_name);
     }
 
+    pub fn set_favorite(&self, app: &Application, module: &Module, favorite: bool) {
+        self.imp().set_favorite(app, module, favorite);
+    }
+
     pub fn clear_list(&self) {
         self.imp().clear_list();
     }
@@ -114,6 +118,12 @@ pub struct ModuleListTemplate {
     #[template_child]
     custom_list_box: TemplateChild<gtk::ListBox>,
 
+    #[template_child]
+    favorites_label: TemplateChild<gtk::Label>,
+
+    #[template_child]
+    favorites_list_box: TemplateChild<gtk::ListBox>,
+
     #[template_child]
     search_bar: TemplateChild<gtk::SearchBar>,
 
@@ -134,8 +144,8 @@ impl ModuleListTemplate {
         }
     }
 
-    fn lists(&self) -> [&gtk::ListBox; 6] {
-        [&self.basic_list_box, &self.input_output_list_box, &self.gate_list_box, &self.latch_list_box, &self.flip_flop_list_box, &self.custom_list_box]
+    fn lists(&self) -> [&gtk::ListBox; 7] {
+        [&self.favorites_list_box, &self.basic_list_box, &self.input_output_list_box, &self.gate_list_box, &self.latch_list_box, &self.flip_flop_list_box, &self.custom_list_box]
     }
 
     fn module_item_content(&self, module: &Module) -> gtk::Box {        
@@ -154,13 +164,33 @@ impl ModuleListTemplate {
     }
 
     fn add_module_to_ui(&self, application: &Application, module: &Module) {
+        self.list_for(module.category())
+            .append(&self.module_row(application, module));
+
+        if application.imp().preferences().borrow().is_favorite(module.name()) {
+            self.set_favorite(application, module, true);
+        }
+    }
+
+    fn set_favorite(&self, application: &Application, module: &Module, favorite: bool) {
+        Self::remove_rows(&self.favorites_list_box, module.name());
+        if favorite {
+            self.favorites_list_box.append(&self.module_row(application, module));
+        }
+        self.update_favorites_visibility();
+    }
+
+    fn update_favorites_visibility(&self) {
+        let visible = self.favorites_list_box.row_at_index(0).is_some();
+        self.favorites_label.set_visible(visible);
+        self.favorites_list_box.set_visible(visible);
+    }
+
+    fn module_row(&self, application: &Application, module: &Module) -> gtk::ListBoxRow {
         let item = gtk::ListBoxRow::builder()
             .child(&self.module_item_content(module))
             .css_classes(vec![String::from("module_list_item")])
             .build();
-        
-        self.list_for(module.category())
-            .append(&item);
             
         let left_click_gesture = gtk::GestureClick::builder()
             .button(gdk::ffi::GDK_BUTTON_PRIMARY as u32)
@@ -169,7 +199,7 @@ impl ModuleListTemplate {
         let name = module.name().to_owned();
         left_click_gesture.connect_pressed(glib::clone!(@weak application => move |_, _, _, _| {
             let project = application.imp().project().clone();
-            let project = project.lock().unwrap();
+            let project = project.lock();
             if let Some(module) = project.module(&name) && let Some(plot) = application.imp().current_plot() {
                 let border_color = application.imp().current_circuit_view().and_then(|view| view.fetch_border_color());
                 let block = Block::new(&module, Vector2(0, 0), border_color);
@@ -182,6 +212,19 @@ impl ModuleListTemplate {
         }));
         item.add_controller(&left_click_gesture);
 
+        let name = module.name().to_owned();
+        item.set_has_tooltip(true);
+        item.connect_query_tooltip(glib::clone!(@weak application => @default-return false, move |_, _, _, _, tooltip| {
+            let project = application.imp().project().clone();
+            let project = project.lock();
+            let Some(module) = project.module(&name) else {
+                return false;
+            };
+            let wire_style = application.imp().preferences().borrow().wire_style();
+            tooltip.set_custom(Some(&Self::module_preview(&project, module, wire_style)));
+            true
+        }));
+
         let right_click_gesture = gtk::GestureClick::builder()
             .button(gdk::ffi::GDK_BUTTON_SECONDARY as u32)
             .build();
@@ -189,20 +232,90 @@ impl ModuleListTemplate {
 
         let name = module.name().to_owned();
         let is_builtin = module.builtin();
-        right_click_gesture.connect_pressed(glib::clone!(@weak self as widget => move |_, _, _, _| {
-            if !is_builtin {
-                widget.custom_module_context(&item, &name);
-            }
+        right_click_gesture.connect_pressed(glib::clone!(@weak self as widget, @weak application, @weak item => move |_, _, _, _| {
+            let favorite = application.imp().preferences().borrow().is_favorite(&name);
+            let (locked, source_or_sink) = application.imp().project().lock().module(&name).map_or((false, None), |module| (
+                module.locked(),
+                (module.get_num_inputs() == 0 || module.get_num_outputs() == 0).then(|| module.source_or_sink())
+            ));
+            widget.module_context(&item, &name, is_builtin, favorite, locked, source_or_sink);
         }));
+
+        item
+    }
+
+    // the pins of a module and a thumbnail of its circuit, shown before it gets placed
+    fn module_preview(project: &Project, module: &Module, wire_style: WireStyle) -> gtk::Box {
+        const THUMBNAIL_SIZE: Vector2<f64> = Vector2(240., 160.);
+
+        let b = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(4)
+            .build();
+
+        b.append(&gtk::Label::builder()
+            .label(format!("<b>{}</b>", glib::markup_escape_text(module.name())))
+            .use_markup(true)
+            .xalign(0.0)
+            .build()
+        );
+
+        let plural = |count: u8, noun: &str| format!("{count} {noun}{}", if count == 1 { "" } else { "s" });
+        b.append(&gtk::Label::builder()
+            .label(format!("{}, {}", plural(module.get_num_inputs(), "input"), plural(module.get_num_outputs(), "output")))
+            .xalign(0.0)
+            .build()
+        );
+
+        let (inputs, outputs) = module.port_names();
+        for (heading, names) in [("Inputs", inputs), ("Outputs", outputs)] {
+            if names.iter().all(|name| name.is_none()) {
+                continue;
+            }
+            let names = names.iter().enumerate()
+                .map(|(i, name)| match name {
+                    Some(name) => format!("{} {name}", i + 1),
+                    None => (i + 1).to_string()
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            b.append(&gtk::Label::builder()
+                .label(format!("{heading}: {names}"))
+                .css_classes(vec![String::from("dim-label")])
+                .xalign(0.0)
+                .build()
+            );
+        }
+
+        let thumbnail = module.plot().and_then(|plot| export::thumbnail(plot, wire_style, project.net_colors(), false, THUMBNAIL_SIZE));
+
+        if let Some(thumbnail) = thumbnail {
+            b.append(&gtk::Picture::builder()
+                .paintable(&thumbnail)
+                .can_shrink(false)
+                .margin_top(4)
+                .build()
+            );
+        }
+
+        b
     }
 
     fn remove_module_from_ui(&self, module_name: &String) {
+        Self::remove_rows(&self.custom_list_box, module_name);
+        Self::remove_rows(&self.favorites_list_box, module_name);
+        self.update_favorites_visibility();
+    }
+
+    fn remove_rows(list: &gtk::ListBox, module_name: &String) {
         let mut i = 0;
-        while let Some(row) = self.custom_list_box.row_at_index(i) {
-            if row.child().and_downcast::<gtk::Box>().unwrap().last_child().and_downcast::<gtk::Label>().unwrap().label().eq(module_name) {
-                self.custom_list_box.remove(&row);
+        while let Some(row) = list.row_at_index(i) {
+            if row.label().map_or(false, |label| label.eq(module_name)) {
+                list.remove(&row);
+            }
+            else {
+                i += 1;
             }
-            i += 1;
         }
     }
 
@@ -212,13 +325,29 @@ impl ModuleListTemplate {
                 list.remove(&row);
             }
         );
+        self.update_favorites_visibility();
     }
 
-    fn custom_module_context(&self, item: &gtk::ListBoxRow, name: &String) {
+    // `source_or_sink` is only set for modules without inputs or outputs
+    fn module_context(&self, item: &gtk::ListBoxRow, name: &String, is_builtin: bool, favorite: bool, locked: bool, source_or_sink: Option<bool>) {
         let model = gio::Menu::new();
-        add_menu_item!(model, "_Edit Contents", "app.edit-module",   &name.to_variant());
-        add_menu_item!(model, "E_xport",        "app.export-module", &name.to_variant());
-        add_menu_item!(model, "_Delete",        "app.delete-module", &name.to_variant());
+        let favorite_label = if favorite { "Remove from _Favorites" } else { "Add to _Favorites" };
+        add_menu_item!(model, favorite_label,            "app.toggle-favorite-module", &name.to_variant());
+        if !is_builtin {
+            add_menu_item!(model, "_Edit Contents",      "app.edit-module",            &name.to_variant());
+            add_menu_item!(model, if locked { "_Unlock" } else { "_Lock" }, "app.toggle-module-lock", &name.to_variant());
+            add_menu_item!(model, "Find _Instances",     "app.find-module-instances",  &name.to_variant());
+            add_menu_item!(model, "_Replace Usages",     "app.replace-module",         &name.to_variant());
+            add_menu_item!(model, "Name _Pins",          "app.name-pins",              &name.to_variant());
+            if let Some(source_or_sink) = source_or_sink {
+                add_menu_item!(model, if source_or_sink { "Warn About Missing Pi_ns" } else { "Mark as Source or Si_nk" }, "app.toggle-module-source-sink", &name.to_variant());
+            }
+            add_menu_item!(model, "_Copy",               "app.copy-module",            &name.to_variant());
+            add_menu_item!(model, "E_xport",             "app.export-module",          &name.to_variant());
+            add_menu_item!(model, "_View Truth Table",   "app.show-truth-table",       &name.to_variant());
+            add_menu_item!(model, "Export _Truth Table", "app.export-truth-table",     &name.to_variant());
+            add_menu_item!(model, "_Delete",             "app.delete-module",          &name.to_variant());
+        }
 
         let popover = gtk::PopoverMenu::from_model(Some(&model));
         popover.set_parent(item);